@@ -0,0 +1,109 @@
+//! Local ledger of observed wallet balances.
+//!
+//! With `track_balance = true` in the config, every `wallet balance` call
+//! appends the balance it saw to `./balance.json`, and `wallet history` shows
+//! the series. Only the newest [`MAX_POINTS`] are kept, and the file is
+//! replaced atomically so an interrupted write never truncates it.
+
+use crate::error::Result;
+use crate::json_file;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Balance ledger file name.
+pub const BALANCE_LOG_FILE: &str = "balance.json";
+
+/// Most balances kept in the ledger; older ones are dropped on append.
+pub const MAX_POINTS: usize = 365;
+
+/// A balance observed at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalancePoint {
+    /// When the balance was read, in seconds since the Unix epoch.
+    pub timestamp: u64,
+
+    /// Balance in euros.
+    pub balance: i32,
+}
+
+/// Recorded balances, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BalanceLog {
+    /// Recorded balances.
+    #[serde(default)]
+    pub points: Vec<BalancePoint>,
+}
+
+impl BalanceLog {
+    /// Load the ledger, returning an empty one if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path, "balance log")
+    }
+
+    /// Write the ledger through a temporary file renamed over `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing or renaming fails.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(self, path, "balance log")
+    }
+
+    /// Add a balance, dropping the oldest ones beyond `max_points`.
+    pub fn append(&mut self, point: BalancePoint, max_points: usize) {
+        self.points.push(point);
+        let excess = self.points.len().saturating_sub(max_points);
+        self.points.drain(..excess);
+    }
+}
+
+/// Append a balance to the ledger file at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the ledger cannot be read or written.
+pub fn record(path: &Path, point: BalancePoint) -> Result<()> {
+    let mut log = BalanceLog::load(path)?;
+    log.append(point, MAX_POINTS);
+    log.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, balance: i32) -> BalancePoint {
+        BalancePoint { timestamp, balance }
+    }
+
+    #[test]
+    fn append_keeps_only_newest_points() {
+        let mut log = BalanceLog::default();
+        for day in 0..5 {
+            log.append(point(day, 100 - i32::try_from(day).unwrap()), 3);
+        }
+        assert_eq!(log.points, vec![point(2, 98), point(3, 97), point(4, 96)]);
+    }
+
+    #[test]
+    fn record_appends_to_file_and_leaves_no_temp_file() {
+        let path = std::env::temp_dir().join(format!("njalla-balance-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        record(&path, point(1_700_000_000, 150)).unwrap();
+        record(&path, point(1_700_086_400, 135)).unwrap();
+
+        let log = BalanceLog::load(&path).unwrap();
+        let tmp_exists = path.with_extension("json.tmp").exists();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            log.points,
+            vec![point(1_700_000_000, 150), point(1_700_086_400, 135)]
+        );
+        assert!(!tmp_exists);
+    }
+}
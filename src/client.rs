@@ -2,13 +2,22 @@
 //!
 //! Handles all communication with the Njalla API.
 
+use crate::concurrency::{fan_out, DEFAULT_CONCURRENCY};
 use crate::config::Config;
-use crate::error::{NjallaError, Result};
+use crate::error::{NjallaError, ResourceKind, Result};
 use crate::types::{
-    AddRecordParams, ApiRequest, ApiResponse, Domain, DomainsResult, EditRecordParams,
-    MarketDomain, MarketDomainsResult, Payment, PaymentMethod, Record, RecordsResult,
-    RegisterResult, TaskStatus, Transaction, TransactionsResult, WalletBalance,
+    normalize_record_name, AddRecordParams, ApiMethod, ApiRequest, ApiResponse, AuthCodeResult,
+    Domain, DomainsResult, EditRecordParams, GetDomainResult, MarketDomain, MarketDomainsResult,
+    Payment, PaymentMethod, Record, RecordType, RecordsResult, RegisterResult, TaskStatus,
+    Transaction, TransactionsResult, WalletBalance,
 };
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 /// Njalla API endpoint.
 pub const API_ENDPOINT: &str = "https://njal.la/api/1/";
@@ -16,6 +25,135 @@ pub const API_ENDPOINT: &str = "https://njal.la/api/1/";
 /// Default request timeout in seconds.
 pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
+/// TTL in seconds the API assigns to records created without one.
+pub const DEFAULT_RECORD_TTL: i32 = 10800;
+
+/// Wait before the first retry; each further retry doubles it.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between two attempts, also for a larger `Retry-After`.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Options controlling client behaviour, set from global CLI flags.
+#[derive(Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ClientOptions {
+    /// Debug mode - print raw responses.
+    pub debug: bool,
+
+    /// Append a JSON transcript of every request to this file.
+    pub trace_file: Option<PathBuf>,
+
+    /// Request timeout in seconds (`DEFAULT_TIMEOUT_SECS` if unset).
+    pub timeout_secs: Option<u64>,
+
+    /// API endpoint (`API_ENDPOINT` if unset).
+    pub base_url: Option<String>,
+
+    /// Retries after a transport failure (none if unset).
+    pub retries: Option<u32>,
+
+    /// API token that takes precedence over the environment and config file.
+    pub token: Option<String>,
+
+    /// Skip TLS certificate verification (`--no-verify-tls`, never from config).
+    pub insecure_tls: bool,
+
+    /// Concurrent requests for fan-out operations (`DEFAULT_CONCURRENCY` if unset).
+    pub max_concurrency: Option<usize>,
+
+    /// Reject responses with `null` in fields the result type requires.
+    pub strict_json: bool,
+
+    /// Wall-clock limit for the whole command, retries included (`--deadline`).
+    pub deadline: Option<Deadline>,
+
+    /// Print each request as an equivalent `curl` command on stderr.
+    pub print_curl: bool,
+}
+
+impl std::fmt::Debug for ClientOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientOptions")
+            .field("debug", &self.debug)
+            .field("trace_file", &self.trace_file)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("base_url", &self.base_url)
+            .field("retries", &self.retries)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("insecure_tls", &self.insecure_tls)
+            .field("max_concurrency", &self.max_concurrency)
+            .field("strict_json", &self.strict_json)
+            .field("deadline", &self.deadline)
+            .field("print_curl", &self.print_curl)
+            .finish()
+    }
+}
+
+/// Point in time by which a whole command must finish (`--deadline`).
+///
+/// Unlike the per-request timeout, this bounds every request, retry and
+/// polling wait of a command together.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    /// When the command runs out of time.
+    at: Instant,
+
+    /// The limit as given, for the error message.
+    secs: u64,
+}
+
+impl Deadline {
+    /// A deadline `secs` seconds from now.
+    #[must_use]
+    pub fn after_secs(secs: u64) -> Self {
+        Self {
+            at: Instant::now() + Duration::from_secs(secs),
+            secs,
+        }
+    }
+
+    /// Time left before the deadline.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NjallaError::Deadline` once the deadline has passed.
+    pub fn remaining(&self) -> Result<Duration> {
+        match self.at.checked_duration_since(Instant::now()) {
+            Some(left) if !left.is_zero() => Ok(left),
+            _ => Err(NjallaError::Deadline { secs: self.secs }),
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Apply a command-level `--timeout`, which wins over the global one.
+    #[must_use]
+    pub fn with_command_timeout(&self, timeout_secs: Option<u64>) -> Self {
+        Self {
+            timeout_secs: timeout_secs.or(self.timeout_secs),
+            ..self.clone()
+        }
+    }
+
+    /// Fill settings not given on the command line from the config file.
+    #[must_use]
+    pub fn with_config(&self, config: &Config) -> Self {
+        Self {
+            timeout_secs: self.timeout_secs.or(config.timeout),
+            base_url: self.base_url.clone().or_else(|| config.base_url.clone()),
+            retries: self.retries.or(config.retries),
+            ..self.clone()
+        }
+    }
+
+    /// Effective request timeout in seconds.
+    #[must_use]
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+}
+
 /// Njalla API client.
 pub struct NjallaClient {
     /// API token.
@@ -26,74 +164,246 @@ pub struct NjallaClient {
 
     /// Debug mode - print raw responses.
     debug: bool,
+
+    /// Transcript file for `--trace-file`.
+    trace_file: Option<PathBuf>,
+
+    /// Request timeout in seconds.
+    timeout_secs: u64,
+
+    /// Retries after a transport failure.
+    retries: u32,
+
+    /// Concurrent requests for fan-out operations.
+    max_concurrency: usize,
+
+    /// Reject responses with `null` fields (`--strict-json`).
+    strict_json: bool,
+
+    /// Records per domain from `list_records`, dropped when the domain changes.
+    records_cache: Mutex<HashMap<String, Vec<Record>>>,
+
+    /// Limit for all requests made by this client (`--deadline`).
+    deadline: Option<Deadline>,
+
+    /// Print each request as a `curl` command (`--print-curl`).
+    print_curl: bool,
+
+    /// Id for the next request, starting at 1.
+    next_id: AtomicU64,
 }
 
 impl NjallaClient {
     /// Create a new client from configuration.
     ///
     /// Loads token from (in order of precedence):
-    /// 1. `NJALLA_API_TOKEN` environment variable
-    /// 2. Config file at `./config.toml`
+    /// 1. `options.token` (e.g. `--token-stdin`)
+    /// 2. `NJALLA_API_TOKEN` environment variable
+    /// 3. Config file at `./config.toml`
+    ///
+    /// `base_url`, `timeout` and `retries` from the config file apply where
+    /// `options` leaves them unset.
     ///
     /// # Errors
     ///
-    /// Returns `NjallaError::MissingToken` if no token is configured.
-    pub fn new(debug: bool) -> Result<Self> {
+    /// Returns `NjallaError::MissingToken` if no token is configured, or
+    /// `NjallaError::Config` if `insecure_tls` is requested.
+    pub fn new(options: &ClientOptions) -> Result<Self> {
+        if options.insecure_tls {
+            // bitreq's rustls backend always verifies certificates and has no
+            // switch to turn that off, so refuse rather than silently verify.
+            return Err(NjallaError::Config {
+                message: "--no-verify-tls is not supported: the HTTP client (bitreq) \
+                          always verifies TLS certificates. Use --debug or --trace-file \
+                          to inspect API traffic instead"
+                    .to_string(),
+            });
+        }
+
         let config = Config::load()?;
-        let token = config.api_token()?.to_string();
+        let token = match &options.token {
+            Some(token) => token.as_str(),
+            None => config.api_token()?,
+        };
 
-        Ok(Self {
-            token,
-            base_url: API_ENDPOINT.to_string(),
-            debug,
-        })
+        Ok(Self::with_token(token, &options.with_config(&config)))
+    }
+
+    /// Create a new client with an explicit token, bypassing configuration.
+    #[must_use]
+    pub fn with_token(token: &str, options: &ClientOptions) -> Self {
+        Self {
+            token: token.to_string(),
+            base_url: options
+                .base_url
+                .clone()
+                .unwrap_or_else(|| API_ENDPOINT.to_string()),
+            debug: options.debug,
+            trace_file: options.trace_file.clone(),
+            timeout_secs: options.timeout_secs(),
+            retries: options.retries.unwrap_or(0),
+            max_concurrency: options.max_concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+            strict_json: options.strict_json,
+            records_cache: Mutex::default(),
+            deadline: options.deadline,
+            print_curl: options.print_curl,
+            next_id: AtomicU64::new(1),
+        }
     }
 
     /// Create a new client with a custom base URL (for testing).
     #[cfg(test)]
+    #[must_use]
     pub fn with_base_url(token: &str, base_url: &str) -> Self {
         Self {
             token: token.to_string(),
             base_url: base_url.to_string(),
             debug: false,
+            trace_file: None,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            retries: 0,
+            max_concurrency: DEFAULT_CONCURRENCY,
+            strict_json: false,
+            records_cache: Mutex::default(),
+            deadline: None,
+            print_curl: false,
+            next_id: AtomicU64::new(1),
         }
     }
 
-    /// Make an API request.
+    /// Make an API request that must return a result payload.
     ///
     /// # Errors
     ///
-    /// Returns an error if the request fails or the API returns an error.
+    /// Returns an error if the request fails, the API returns an error, or
+    /// the response has no result.
     fn request<T: for<'de> serde::Deserialize<'de>>(
         &self,
-        method: &str,
+        method: ApiMethod,
         params: serde_json::Value,
     ) -> Result<T> {
+        self.request_optional(method, params)?
+            .ok_or_else(|| NjallaError::Api {
+                message: "Missing result in response".to_string(),
+            })
+    }
+
+    /// Make an API request whose success carries no payload.
+    ///
+    /// Any result, including `null` or an absent one, counts as success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    fn request_unit(&self, method: ApiMethod, params: serde_json::Value) -> Result<()> {
+        let _: Option<serde::de::IgnoredAny> = self.request_optional(method, params)?;
+        Ok(())
+    }
+
+    /// Make an API request, returning `None` when the result is null or absent.
+    ///
+    /// Errors that [`NjallaError::is_retryable`] accepts for `method` are
+    /// retried up to `retries` times with the same request id; methods that
+    /// are not idempotent are only retried when the request never reached the
+    /// server. With `--print-curl`, the
+    /// request is printed once as a `curl` command before it is sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    fn request_optional<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        method: ApiMethod,
+        params: serde_json::Value,
+    ) -> Result<Option<T>> {
         let request_body = ApiRequest {
-            method: method.to_string(),
+            method,
             params,
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
         };
 
         let body = serde_json::to_string(&request_body)?;
+        if self.print_curl {
+            eprintln!("{}", curl_command(&self.base_url, &body));
+        }
 
+        let mut attempt = 0;
+        loop {
+            let mut retry_after = None;
+            match self.exchange(&request_body, &body, &mut retry_after) {
+                Err(e) if attempt < self.retries && e.is_retryable(method) => {
+                    let delay = retry_delay(attempt, retry_after);
+                    // No point waiting for a retry the deadline cuts off.
+                    if !self
+                        .time_left()
+                        .is_ok_and(|left| left.is_none_or(|left| left > delay))
+                    {
+                        return Err(e);
+                    }
+                    attempt += 1;
+                    if self.debug {
+                        eprintln!(
+                            "[DEBUG] Request failed ({e}), retry {attempt}/{} in {delay:?}",
+                            self.retries
+                        );
+                    }
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a serialized request once and parse its response.
+    ///
+    /// `retry_after` is set to the delay the response asks for in its
+    /// `Retry-After` header, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    fn exchange<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        request_body: &ApiRequest,
+        body: &str,
+        retry_after: &mut Option<Duration>,
+    ) -> Result<Option<T>> {
+        let method = request_body.method;
         if self.debug {
             eprintln!("[DEBUG] Request: {method} {body}");
         }
 
-        let response = bitreq::post(&self.base_url)
-            .with_header("Authorization", format!("Njalla {}", self.token))
-            .with_header("Content-Type", "application/json")
-            .with_body(body.into_bytes())
-            .with_timeout(DEFAULT_TIMEOUT_SECS)
-            .send()?;
+        let start = Instant::now();
+        let response = self.send(body)?;
+        *retry_after = requested_delay(&response);
 
         let response_text = response.as_str()?;
 
         if self.debug {
-            eprintln!("[DEBUG] Response: {response_text}");
+            eprintln!(
+                "[DEBUG] Response: {}",
+                loggable_response(method, response_text)
+            );
+        }
+
+        if let Some(path) = &self.trace_file {
+            let entry = serde_json::json!({
+                "id": request_body.id,
+                "method": method,
+                "params": request_body.params,
+                "response_text": loggable_response(method, response_text),
+                "status": response.status_code,
+                "duration_ms": u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            });
+            append_trace(path, &entry)?;
+        }
+
+        if self.strict_json {
+            reject_nulls(response_text)?;
         }
 
         let api_response: ApiResponse<T> = serde_json::from_str(response_text)?;
+        check_response_id(request_body.id, api_response.id.as_ref())?;
 
         if let Some(error) = api_response.error {
             return Err(NjallaError::Api {
@@ -101,8 +411,60 @@ impl NjallaClient {
             });
         }
 
-        api_response.result.ok_or_else(|| NjallaError::Api {
-            message: "Missing result in response".to_string(),
+        Ok(api_response.result)
+    }
+
+    /// POST a request body once.
+    ///
+    /// With a deadline, no attempt starts after it has passed and each one
+    /// may take at most the time left, rounded up to a whole second.
+    fn send(&self, body: &str) -> Result<bitreq::Response> {
+        let timeout_secs = match self.time_left()? {
+            Some(left) => self
+                .timeout_secs
+                .min(left.as_secs() + u64::from(left.subsec_nanos() > 0)),
+            None => self.timeout_secs,
+        };
+        let result = bitreq::post(&self.base_url)
+            .with_header("Authorization", format!("Njalla {}", self.token))
+            .with_header("Content-Type", "application/json")
+            .with_body(body.as_bytes().to_vec())
+            .with_timeout(timeout_secs)
+            .send();
+        if result.is_err() {
+            // A request cut short by the deadline reports the deadline.
+            self.time_left()?;
+        }
+        Ok(result?)
+    }
+
+    /// Time left before the command's deadline, or `None` without one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NjallaError::Deadline` once the deadline has passed.
+    pub fn time_left(&self) -> Result<Option<Duration>> {
+        self.deadline.as_ref().map(Deadline::remaining).transpose()
+    }
+
+    /// Number of requests fan-out operations may have in flight at once.
+    #[must_use]
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Run several API calls, up to `max_concurrency` at a time.
+    ///
+    /// The API has no multicall envelope, so each `(method, params)` pair is
+    /// its own request. Results come back in call order and fail
+    /// independently: one error does not affect the other calls.
+    #[must_use]
+    pub fn batch(
+        &self,
+        calls: &[(ApiMethod, serde_json::Value)],
+    ) -> Vec<Result<serde_json::Value>> {
+        fan_out(calls, self.max_concurrency, |(method, params)| {
+            self.request(*method, params.clone())
         })
     }
 
@@ -112,12 +474,32 @@ impl NjallaClient {
 
     /// List all domains in the account.
     ///
+    /// Follows the `next` cursor if the API ever returns one, so paginated
+    /// responses are combined into a single list.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the API request fails.
+    /// Returns an error if an API request fails or a cursor repeats.
     pub fn list_domains(&self) -> Result<Vec<Domain>> {
-        let result: DomainsResult = self.request("list-domains", serde_json::json!({}))?;
-        Ok(result.domains)
+        let mut result: DomainsResult =
+            self.request(ApiMethod::ListDomains, serde_json::json!({}))?;
+        let mut domains = std::mem::take(&mut result.domains);
+        let mut seen = std::collections::HashSet::new();
+
+        while let Some(cursor) = result.next.take() {
+            if !seen.insert(cursor.clone()) {
+                return Err(NjallaError::Api {
+                    message: format!("list-domains returned cursor {cursor} twice"),
+                });
+            }
+            result = self.request(
+                ApiMethod::ListDomains,
+                serde_json::json!({ "cursor": cursor }),
+            )?;
+            domains.append(&mut result.domains);
+        }
+
+        Ok(domains)
     }
 
     /// Get detailed info for a specific domain.
@@ -126,7 +508,38 @@ impl NjallaClient {
     ///
     /// Returns an error if the API request fails or the domain is not found.
     pub fn get_domain(&self, domain: &str) -> Result<Domain> {
-        self.request("get-domain", serde_json::json!({ "domain": domain }))
+        let result: GetDomainResult = self.request(
+            ApiMethod::GetDomain,
+            serde_json::json!({ "domain": domain }),
+        )?;
+        Ok(result.into_domain())
+    }
+
+    /// Get a domain as the raw API result, keeping the API's field order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails or the domain is not found.
+    pub fn get_domain_raw(&self, domain: &str) -> Result<serde_json::Value> {
+        self.request(
+            ApiMethod::GetDomain,
+            serde_json::json!({ "domain": domain }),
+        )
+    }
+
+    /// Get the EPP auth code for transferring a domain out of Njalla.
+    ///
+    /// The response is never shown by `--debug` or written to `--trace-file`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    pub fn get_auth_code(&self, domain: &str) -> Result<String> {
+        let result: AuthCodeResult = self.request(
+            ApiMethod::GetAuthCode,
+            serde_json::json!({ "domain": domain }),
+        )?;
+        Ok(result.auth_code)
     }
 
     /// Search for available domains.
@@ -135,8 +548,10 @@ impl NjallaClient {
     ///
     /// Returns an error if the API request fails.
     pub fn find_domains(&self, query: &str) -> Result<Vec<MarketDomain>> {
-        let result: MarketDomainsResult =
-            self.request("find-domains", serde_json::json!({ "query": query }))?;
+        let result: MarketDomainsResult = self.request(
+            ApiMethod::FindDomains,
+            serde_json::json!({ "query": query }),
+        )?;
         Ok(result.domains)
     }
 
@@ -151,7 +566,7 @@ impl NjallaClient {
     /// Returns an error if the API request fails or the domain is unavailable.
     pub fn register_domain(&self, domain: &str, years: i32) -> Result<String> {
         let result: RegisterResult = self.request(
-            "register-domain",
+            ApiMethod::RegisterDomain,
             serde_json::json!({ "domain": domain, "years": years }),
         )?;
         Ok(result.task)
@@ -163,7 +578,7 @@ impl NjallaClient {
     ///
     /// Returns an error if the API request fails or the task is not found.
     pub fn check_task(&self, task_id: &str) -> Result<TaskStatus> {
-        self.request("check-task", serde_json::json!({ "id": task_id }))
+        self.request(ApiMethod::CheckTask, serde_json::json!({ "id": task_id }))
     }
 
     // ========================================================================
@@ -172,13 +587,97 @@ impl NjallaClient {
 
     /// List DNS records for a domain.
     ///
+    /// Records are cached for the life of the client, so a command that
+    /// reads a zone more than once hits the API once. Adding, editing or
+    /// removing a record drops the domain's entry, and
+    /// [`refresh_records`](Self::refresh_records) bypasses the cache.
+    ///
     /// # Errors
     ///
     /// Returns an error if the API request fails.
     pub fn list_records(&self, domain: &str) -> Result<Vec<Record>> {
-        let result: RecordsResult =
-            self.request("list-records", serde_json::json!({ "domain": domain }))?;
-        Ok(result.records)
+        if let Some(records) = self.records_cache().get(&cache_key(domain)) {
+            return Ok(records.clone());
+        }
+        self.refresh_records(domain)
+    }
+
+    /// List DNS records from the API, replacing any cached copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    pub fn refresh_records(&self, domain: &str) -> Result<Vec<Record>> {
+        let records = self.list_records_filtered(domain, None, None)?;
+        self.records_cache()
+            .insert(cache_key(domain), records.clone());
+        Ok(records)
+    }
+
+    fn records_cache(&self) -> MutexGuard<'_, HashMap<String, Vec<Record>>> {
+        self.records_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Drop cached records of `domain`.
+    ///
+    /// Changes call this both before and after their request, whether it
+    /// succeeded or not, so a listing that ran concurrently with the change
+    /// is not kept either.
+    fn forget_records(&self, domain: &str) {
+        self.records_cache().remove(&cache_key(domain));
+    }
+
+    /// List DNS records as the raw API result, keeping the API's field order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    pub fn list_records_raw(&self, domain: &str) -> Result<serde_json::Value> {
+        self.request(
+            ApiMethod::ListRecords,
+            serde_json::json!({ "domain": domain }),
+        )
+    }
+
+    /// List DNS records for a domain, filtered by name and/or type.
+    ///
+    /// Filters are sent to the API so it can narrow the result, and are
+    /// applied again locally in case the API ignores them. Record names, and
+    /// the name filter, are normalized with [`normalize_record_name`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn list_records_filtered(
+        &self,
+        domain: &str,
+        name: Option<&str>,
+        record_type: Option<RecordType>,
+    ) -> Result<Vec<Record>> {
+        let name = name.map(|n| normalize_record_name(n, domain));
+        let mut json_params = serde_json::json!({ "domain": domain });
+
+        // Safe: json! macro always creates an object when given object syntax
+        let obj = json_params.as_object_mut().expect("json object");
+
+        if let Some(name) = &name {
+            obj.insert("name".to_string(), serde_json::json!(name));
+        }
+        if let Some(record_type) = record_type {
+            obj.insert("type".to_string(), serde_json::json!(record_type));
+        }
+
+        let result: RecordsResult = self.request(ApiMethod::ListRecords, json_params)?;
+        Ok(result
+            .records
+            .into_iter()
+            .map(|r| with_normalized_name(r, domain))
+            .filter(|r| name.as_ref().is_none_or(|n| r.name == *n))
+            .filter(|r| record_type.is_none_or(|t| r.record_type == t))
+            .collect())
     }
 
     /// Add a DNS record to a domain.
@@ -188,10 +687,11 @@ impl NjallaClient {
     /// Returns an error if the API request fails.
     #[allow(clippy::missing_panics_doc)]
     pub fn add_record(&self, params: &AddRecordParams) -> Result<Record> {
+        self.forget_records(&params.domain);
         let mut json_params = serde_json::json!({
             "domain": params.domain,
             "type": params.record_type,
-            "name": params.name,
+            "name": normalize_record_name(&params.name, &params.domain),
         });
 
         // Safe: json! macro always creates an object when given object syntax
@@ -225,7 +725,9 @@ impl NjallaClient {
             obj.insert("ssh_type".to_string(), serde_json::json!(ssh_type));
         }
 
-        self.request("add-record", json_params)
+        let record = self.request(ApiMethod::AddRecord, json_params);
+        self.forget_records(&params.domain);
+        Ok(with_normalized_name(record?, &params.domain))
     }
 
     /// Edit an existing DNS record.
@@ -235,6 +737,7 @@ impl NjallaClient {
     /// Returns an error if the API request fails.
     #[allow(clippy::missing_panics_doc)]
     pub fn edit_record(&self, params: &EditRecordParams) -> Result<Record> {
+        self.forget_records(&params.domain);
         let mut json_params = serde_json::json!({
             "domain": params.domain,
             "id": params.id,
@@ -244,6 +747,7 @@ impl NjallaClient {
         let obj = json_params.as_object_mut().expect("json object");
 
         if let Some(name) = &params.name {
+            let name = normalize_record_name(name, &params.domain);
             obj.insert("name".to_string(), serde_json::json!(name));
         }
         if let Some(content) = &params.content {
@@ -274,7 +778,9 @@ impl NjallaClient {
             obj.insert("ssh_type".to_string(), serde_json::json!(ssh_type));
         }
 
-        self.request("edit-record", json_params)
+        let record = self.request(ApiMethod::EditRecord, json_params);
+        self.forget_records(&params.domain);
+        Ok(with_normalized_name(record?, &params.domain))
     }
 
     /// Remove a DNS record from a domain.
@@ -283,11 +789,13 @@ impl NjallaClient {
     ///
     /// Returns an error if the API request fails.
     pub fn remove_record(&self, domain: &str, id: &str) -> Result<()> {
-        let _: serde_json::Value = self.request(
-            "remove-record",
+        self.forget_records(domain);
+        let result = self.request_unit(
+            ApiMethod::RemoveRecord,
             serde_json::json!({ "domain": domain, "id": id }),
-        )?;
-        Ok(())
+        );
+        self.forget_records(domain);
+        result
     }
 
     // ========================================================================
@@ -300,7 +808,7 @@ impl NjallaClient {
     ///
     /// Returns an error if the API request fails.
     pub fn get_balance(&self) -> Result<WalletBalance> {
-        self.request("get-balance", serde_json::json!({}))
+        self.request(ApiMethod::GetBalance, serde_json::json!({}))
     }
 
     /// Add payment to refill wallet.
@@ -315,7 +823,7 @@ impl NjallaClient {
     /// Returns an error if the API request fails or parameters are invalid.
     pub fn add_payment(&self, amount: i32, via: PaymentMethod) -> Result<Payment> {
         self.request(
-            "add-payment",
+            ApiMethod::AddPayment,
             serde_json::json!({
                 "amount": amount,
                 "via": via.to_string()
@@ -327,9 +835,19 @@ impl NjallaClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if the API request fails or the payment is not found.
+    /// Returns `NjallaError::NotFound` if there is no payment with that id, or
+    /// another error if the API request fails.
     pub fn get_payment(&self, id: &str) -> Result<Payment> {
-        self.request("get-payment", serde_json::json!({ "id": id }))
+        self.request(ApiMethod::GetPayment, serde_json::json!({ "id": id }))
+            .map_err(|e| match e {
+                NjallaError::Api { message } if message.to_lowercase().contains("not found") => {
+                    NjallaError::NotFound {
+                        kind: ResourceKind::Payment,
+                        id: id.to_string(),
+                    }
+                }
+                other => other,
+            })
     }
 
     /// List transactions from the last 90 days.
@@ -339,17 +857,159 @@ impl NjallaClient {
     /// Returns an error if the API request fails.
     pub fn list_transactions(&self) -> Result<Vec<Transaction>> {
         let result: TransactionsResult =
-            self.request("list-transactions", serde_json::json!({}))?;
+            self.request(ApiMethod::ListTransactions, serde_json::json!({}))?;
         Ok(result.transactions)
     }
 }
 
+/// Key of `domain` in the records cache.
+fn cache_key(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// A record as returned by the API, with its name normalized for `domain`.
+fn with_normalized_name(mut record: Record, domain: &str) -> Record {
+    record.name = normalize_record_name(&record.name, domain);
+    record
+}
+
+/// How long to wait before retry number `attempt + 1`.
+///
+/// A `Retry-After` delay from the server wins over the doubling backoff;
+/// both are capped at [`RETRY_MAX_DELAY`].
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// The delay a response asks for with `Retry-After: <seconds>`.
+///
+/// The HTTP-date form is not used by the API and is ignored.
+fn requested_delay(response: &bitreq::Response) -> Option<Duration> {
+    let secs = response.headers.get("retry-after")?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Fail if the response answers a different request than `request_id`.
+///
+/// Njalla may not echo ids, so a missing or `null` id is accepted. An echoed
+/// id may be a number or its string form.
+fn check_response_id(request_id: u64, response_id: Option<&serde_json::Value>) -> Result<()> {
+    let matches = match response_id {
+        None | Some(serde_json::Value::Null) => true,
+        Some(serde_json::Value::Number(id)) => id.as_u64() == Some(request_id),
+        Some(serde_json::Value::String(id)) => *id == request_id.to_string(),
+        Some(_) => false,
+    };
+    if matches {
+        return Ok(());
+    }
+    Err(NjallaError::Api {
+        message: format!(
+            "Response id {} does not match request id {request_id}",
+            response_id.map_or_else(String::new, ToString::to_string)
+        ),
+    })
+}
+
+/// Fail if the response result has a `null` anywhere in it (`--strict-json`).
+///
+/// The lenient parse turns a `null` into `None` or the field's default, so an
+/// API change that starts sending `null` would otherwise go unnoticed. A
+/// `null` result as a whole still means "no payload". The check works on the
+/// raw JSON, so it covers typed results and raw `Value` results (`batch`,
+/// `--raw`) alike.
+fn reject_nulls(response_text: &str) -> Result<()> {
+    fn find_nulls(value: &serde_json::Value, path: &str, found: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Null => found.push(path.to_string()),
+            serde_json::Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    find_nulls(item, &format!("{path}[{i}]"), found);
+                }
+            }
+            serde_json::Value::Object(fields) => {
+                for (key, field) in fields {
+                    find_nulls(field, &format!("{path}.{key}"), found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let response: serde_json::Value = serde_json::from_str(response_text)?;
+    let Some(result) = response.get("result").filter(|result| !result.is_null()) else {
+        return Ok(());
+    };
+    let mut nulls = Vec::new();
+    find_nulls(result, "result", &mut nulls);
+    if nulls.is_empty() {
+        return Ok(());
+    }
+    Err(NjallaError::Parse(serde::de::Error::custom(format!(
+        "unexpected null at {} (--strict-json)",
+        nulls.join(", ")
+    ))))
+}
+
+/// The `curl` command that sends `body` to `url` as the client would.
+///
+/// The token is left as `$NJALLA_API_TOKEN` for the shell to expand, so the
+/// command can be shared without leaking it.
+fn curl_command(url: &str, body: &str) -> String {
+    format!(
+        "curl -X POST {} -H \"Authorization: Njalla $NJALLA_API_TOKEN\" \
+         -H 'Content-Type: application/json' -d {}",
+        shell_quote(url),
+        shell_quote(body)
+    )
+}
+
+/// Quote `s` as a single POSIX shell word.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Methods whose responses carry secrets and are never logged.
+const SECRET_METHODS: &[ApiMethod] = &[ApiMethod::GetAuthCode];
+
+/// Response text for `--debug` and `--trace-file`, redacted for secret methods.
+fn loggable_response(method: ApiMethod, response_text: &str) -> &str {
+    if SECRET_METHODS.contains(&method) {
+        "<redacted>"
+    } else {
+        response_text
+    }
+}
+
+/// Append a single transcript entry as one JSON line.
+///
+/// The entry never includes request headers, so the API token is not written.
+fn append_trace(path: &Path, entry: &serde_json::Value) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| NjallaError::Config {
+            message: format!("Failed to open trace file: {e}"),
+        })?;
+
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, entry)?;
+    writeln!(writer)
+        .and_then(|()| writer.flush())
+        .map_err(|e| NjallaError::Config {
+            message: format!("Failed to write trace file: {e}"),
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::PaymentMethod;
     use std::sync::LazyLock;
-    use wiremock::matchers::{body_json_string, header, method};
+    use wiremock::matchers::{body_json_string, body_partial_json, header, method};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     // wiremock requires tokio runtime for MockServer
@@ -385,7 +1045,7 @@ mod tests {
         let client = NjallaClient::with_base_url("test-token", &mock_server.uri());
 
         let result: std::result::Result<serde_json::Value, _> =
-            client.request("list-domains", serde_json::json!({}));
+            client.request(ApiMethod::ListDomains, serde_json::json!({}));
 
         assert!(result.is_ok());
     }
@@ -406,7 +1066,7 @@ mod tests {
         let client = NjallaClient::with_base_url("bad-token", &mock_server.uri());
 
         let result: std::result::Result<serde_json::Value, _> =
-            client.request("list-domains", serde_json::json!({}));
+            client.request(ApiMethod::ListDomains, serde_json::json!({}));
 
         assert!(matches!(result, Err(NjallaError::Api { message }) if message == "Invalid token"));
     }
@@ -419,7 +1079,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"test-method","params":{"key":"value"}}"#,
+                    r#"{"method":"get-domain","params":{"domain":"example.com"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {}
@@ -427,12 +1087,622 @@ mod tests {
                 .expect(1),
         );
 
-        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+
+        let result: std::result::Result<serde_json::Value, _> = client.request(
+            ApiMethod::GetDomain,
+            serde_json::json!({"domain": "example.com"}),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn request_ids_increase_and_echoed_ids_must_match() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({ "id": 1 })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": 1, "result": {}
+                }))),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({ "id": 2 })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": "2", "result": {}
+                }))),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({ "id": 3 })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": 2, "result": {}
+                }))),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let call =
+            || client.request::<serde_json::Value>(ApiMethod::GetBalance, serde_json::json!({}));
+
+        assert!(call().is_ok());
+        assert!(call().is_ok());
+        assert!(matches!(
+            call(),
+            Err(NjallaError::Api { message })
+                if message == "Response id 2 does not match request id 3"
+        ));
+    }
+
+    #[test]
+    fn response_without_id_is_accepted() {
+        assert!(check_response_id(4, None).is_ok());
+        assert!(check_response_id(4, Some(&serde_json::Value::Null)).is_ok());
+        assert!(check_response_id(4, Some(&serde_json::json!(4))).is_ok());
+        assert!(check_response_id(4, Some(&serde_json::json!("5"))).is_err());
+        assert!(check_response_id(4, Some(&serde_json::json!([4]))).is_err());
+    }
+
+    #[test]
+    fn timeout_precedence_command_then_global_then_default() {
+        let global = ClientOptions {
+            timeout_secs: Some(120),
+            ..ClientOptions::default()
+        };
+
+        assert_eq!(global.with_command_timeout(Some(5)).timeout_secs(), 5);
+        assert_eq!(global.with_command_timeout(None).timeout_secs(), 120);
+        assert_eq!(
+            ClientOptions::default()
+                .with_command_timeout(None)
+                .timeout_secs(),
+            DEFAULT_TIMEOUT_SECS
+        );
+
+        let client = NjallaClient::with_token("token", &global.with_command_timeout(Some(5)));
+        assert_eq!(client.timeout_secs, 5);
+    }
+
+    #[test]
+    fn config_base_url_used_unless_overridden() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST")).respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "domains": [] }
+                })),
+            ),
+        );
+
+        let config = Config {
+            base_url: Some(mock_server.uri()),
+            timeout: Some(60),
+            retries: Some(2),
+            ..Config::default()
+        };
+        let options = ClientOptions::default().with_config(&config);
+        let client = NjallaClient::with_token("token", &options);
+
+        assert_eq!(client.base_url, mock_server.uri());
+        assert_eq!(client.timeout_secs, 60);
+        assert_eq!(client.retries, 2);
+        assert!(client.list_domains().unwrap().is_empty());
+
+        let cli = ClientOptions {
+            base_url: Some("http://127.0.0.1:1/".to_string()),
+            timeout_secs: Some(5),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &cli.with_config(&config));
+        assert_eq!(client.base_url, "http://127.0.0.1:1/");
+        assert_eq!(client.timeout_secs, 5);
+    }
+
+    #[test]
+    fn batch_returns_results_independently() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({
+                    "method": "get-domain",
+                    "params": { "domain": "missing.com" }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": { "message": "Domain not found" }
+                }))),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({
+                    "method": "get-domain",
+                    "params": { "domain": "example.com" }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "name": "example.com", "status": "active" }
+                }))),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let results = client.batch(&[
+            (
+                ApiMethod::GetDomain,
+                serde_json::json!({ "domain": "missing.com" }),
+            ),
+            (
+                ApiMethod::GetDomain,
+                serde_json::json!({ "domain": "example.com" }),
+            ),
+        ]);
+
+        assert_eq!(results.len(), 2);
+        assert!(
+            matches!(&results[0], Err(NjallaError::Api { message }) if message == "Domain not found")
+        );
+        assert_eq!(results[1].as_ref().unwrap()["name"], "example.com");
+    }
+
+    #[test]
+    fn batch_serializes_requests_at_concurrency_one() {
+        let mock_server = mock_server();
+        let delay = std::time::Duration::from_millis(100);
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "balance": 5 } }))
+                        .set_delay(delay),
+                )
+                .expect(3),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            max_concurrency: Some(1),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+        let call = (ApiMethod::GetBalance, serde_json::json!({}));
+
+        let started = Instant::now();
+        let results = client.batch(&[call.clone(), call.clone(), call]);
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(started.elapsed() >= delay * 3);
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn transient_api_error_is_retried_with_same_id() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": { "message": "Rate limit exceeded" }
+                })))
+                .up_to_n_times(1)
+                .expect(1),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "balance": 5 } })),
+                )
+                .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            retries: Some(1),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let start = Instant::now();
+        assert_eq!(client.get_balance().unwrap().balance, 5);
+        assert!(start.elapsed() >= RETRY_BASE_DELAY);
+        let ids: Vec<serde_json::Value> = RT
+            .block_on(mock_server.received_requests())
+            .unwrap()
+            .iter()
+            .map(|r| serde_json::from_slice::<serde_json::Value>(&r.body).unwrap()["id"].clone())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], ids[1]);
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn retry_waits_as_long_as_retry_after_asks() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Retry-After", "2")
+                        .set_body_json(serde_json::json!({
+                            "error": { "message": "Too many requests" }
+                        })),
+                )
+                .up_to_n_times(1)
+                .expect(1),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "balance": 5 } })),
+                )
+                .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            retries: Some(1),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let start = Instant::now();
+        assert_eq!(client.get_balance().unwrap().balance, 5);
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn retry_is_skipped_when_the_wait_outlasts_the_deadline() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Retry-After", "10")
+                        .set_body_json(serde_json::json!({
+                            "error": { "message": "Rate limit exceeded" }
+                        })),
+                )
+                .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            retries: Some(3),
+            deadline: Some(Deadline::after_secs(5)),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let start = Instant::now();
+        let result = client.get_balance();
+        assert!(
+            matches!(result, Err(NjallaError::Api { message }) if message == "Rate limit exceeded")
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay(0, None), Duration::from_millis(500));
+        assert_eq!(retry_delay(2, None), Duration::from_secs(2));
+        assert_eq!(retry_delay(20, None), RETRY_MAX_DELAY);
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(3))),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(45))),
+            RETRY_MAX_DELAY
+        );
+    }
+
+    #[test]
+    fn definitive_api_error_is_not_retried() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": { "message": "Invalid token" }
+                })))
+                .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            retries: Some(3),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        assert!(matches!(
+            client.get_balance(),
+            Err(NjallaError::Api { message }) if message == "Invalid token"
+        ));
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn connection_failure_is_retryable() {
+        let options = ClientOptions {
+            base_url: Some("http://127.0.0.1:1/".to_string()),
+            timeout_secs: Some(5),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let err = client.get_balance().unwrap_err();
+        assert!(matches!(err, NjallaError::Request(_)));
+        assert!(err.is_retryable(ApiMethod::AddRecord));
+        assert!(err.is_unsent());
+    }
+
+    #[test]
+    fn add_record_is_not_resent_after_read_timeout() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST")).respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "result": { "id": "rec1", "name": "www", "type": "A", "content": "192.0.2.1" }
+                    }))
+                    .set_delay(Duration::from_secs(3)),
+            )
+            .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            timeout_secs: Some(1),
+            retries: Some(2),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+        let params = AddRecordParams {
+            domain: "example.com".to_string(),
+            record_type: RecordType::A,
+            name: "www".to_string(),
+            content: Some("192.0.2.1".to_string()),
+            ttl: None,
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        };
+
+        let err = client.add_record(&params).unwrap_err();
+        assert!(matches!(err, NjallaError::Request(_)), "{err}");
+        assert!(!err.is_unsent());
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn deadline_aborts_slow_request_despite_retries() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST")).respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "result": { "balance": 5 } }))
+                    .set_delay(Duration::from_secs(5)),
+            ),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            retries: Some(3),
+            deadline: Some(Deadline::after_secs(1)),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let started = Instant::now();
+        let result = client.get_balance();
+
+        assert!(matches!(result, Err(NjallaError::Deadline { secs: 1 })));
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn passed_deadline_sends_nothing() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            deadline: Some(Deadline::after_secs(0)),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        assert!(matches!(
+            client.get_balance(),
+            Err(NjallaError::Deadline { secs: 0 })
+        ));
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn new_prefers_explicit_token() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(header("Authorization", "Njalla piped-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "balance": 5 }
+                })))
+                .expect(1),
+        );
+
+        let options = ClientOptions {
+            base_url: Some(mock_server.uri()),
+            token: Some(crate::config::read_token("piped-token\n".as_bytes()).unwrap()),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::new(&options).unwrap();
+
+        assert_eq!(client.get_balance().unwrap().balance, 5);
+        assert!(!format!("{options:?}").contains("piped-token"));
+    }
+
+    #[test]
+    fn new_rejects_insecure_tls() {
+        let options = ClientOptions {
+            token: Some("token".to_string()),
+            insecure_tls: true,
+            ..ClientOptions::default()
+        };
+
+        let result = NjallaClient::new(&options);
+
+        assert!(
+            matches!(result, Err(NjallaError::Config { message }) if message.contains("--no-verify-tls"))
+        );
+    }
+
+    #[test]
+    fn request_appends_trace_entries() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "balance": 42 }
+                })))
+                .expect(2),
+        );
+
+        let trace_path = std::env::temp_dir().join(format!(
+            "njalla-trace-test-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&trace_path);
+
+        let mut client = NjallaClient::with_base_url("secret-token", &mock_server.uri());
+        client.trace_file = Some(trace_path.clone());
+        client.get_balance().unwrap();
+        client.get_balance().unwrap();
+
+        let transcript = std::fs::read_to_string(&trace_path).unwrap();
+        std::fs::remove_file(&trace_path).unwrap();
+
+        let entries: Vec<serde_json::Value> = transcript
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["method"], "get-balance");
+        assert_eq!(entries[0]["status"], 200);
+        assert!(entries[1]["duration_ms"].is_u64());
+        assert!(!transcript.contains("secret-token"));
+    }
+
+    #[test]
+    fn get_auth_code_is_redacted_from_logs() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"get-auth-code","params":{"domain":"example.com"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "auth_code": "s3cret-epp" }
+                }))),
+        );
+
+        let trace_path = std::env::temp_dir().join(format!(
+            "njalla-trace-authcode-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&trace_path);
+
+        let mut client = NjallaClient::with_base_url("token", &mock_server.uri());
+        client.trace_file = Some(trace_path.clone());
+        assert_eq!(client.get_auth_code("example.com").unwrap(), "s3cret-epp");
+
+        let transcript = std::fs::read_to_string(&trace_path).unwrap();
+        std::fs::remove_file(&trace_path).unwrap();
+        assert!(transcript.contains("get-auth-code"));
+        assert!(!transcript.contains("s3cret-epp"));
+
+        let body = r#"{"result":{"auth_code":"s3cret-epp"}}"#;
+        assert_eq!(
+            loggable_response(ApiMethod::GetAuthCode, body),
+            "<redacted>"
+        );
+        assert_eq!(loggable_response(ApiMethod::GetDomain, body), body);
+    }
+
+    #[test]
+    fn curl_command_redacts_token_and_quotes_body() {
+        let body = r#"{"method":"add-record","params":{"content":"it's"},"id":1}"#;
+        let curl = curl_command("https://njal.la/api/1/", body);
+
+        assert!(curl.starts_with("curl -X POST 'https://njal.la/api/1/' "));
+        assert!(curl.contains(r#"-H "Authorization: Njalla $NJALLA_API_TOKEN""#));
+        assert!(
+            curl.ends_with(r#"-d '{"method":"add-record","params":{"content":"it'\''s"},"id":1}'"#)
+        );
+    }
+
+    #[test]
+    fn strict_json_rejects_nulls_the_lenient_parse_would_default() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST")).respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "result": { "id": "pay123", "amount": 15, "status": null }
+                }),
+            )),
+        );
+
+        let mut client = NjallaClient::with_base_url("token", &mock_server.uri());
+        assert_eq!(client.get_payment("pay123").unwrap().status, None);
 
-        let result: std::result::Result<serde_json::Value, _> =
-            client.request("test-method", serde_json::json!({"key": "value"}));
+        client.strict_json = true;
+        let result = client.get_payment("pay123");
+        assert!(
+            matches!(&result, Err(NjallaError::Parse(e))
+                if e.to_string().contains("result.status") && e.to_string().contains("--strict-json")),
+            "{result:?}"
+        );
 
-        assert!(result.is_ok());
+        let batched =
+            client.batch(&[(ApiMethod::GetPayment, serde_json::json!({ "id": "pay123" }))]);
+        assert!(matches!(batched[0], Err(NjallaError::Parse(_))));
     }
 
     #[test]
@@ -442,7 +1712,9 @@ mod tests {
         mount(
             &mock_server,
             Mock::given(method("POST"))
-                .and(body_json_string(r#"{"method":"get-balance","params":{}}"#))
+                .and(body_json_string(
+                    r#"{"method":"get-balance","params":{},"id":1}"#,
+                ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": { "balance": 42 }
                 })))
@@ -463,7 +1735,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"add-payment","params":{"amount":15,"via":"bitcoin"}}"#,
+                    r#"{"method":"add-payment","params":{"amount":15,"via":"bitcoin"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -491,7 +1763,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"get-payment","params":{"id":"pay456"}}"#,
+                    r#"{"method":"get-payment","params":{"id":"pay456"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -510,6 +1782,50 @@ mod tests {
         assert_eq!(payment.status, Some("completed".to_string()));
     }
 
+    #[test]
+    fn get_payment_maps_not_found() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"get-payment","params":{"id":"stale"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": { "message": "Payment not found" }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let result = client.get_payment("stale");
+
+        assert!(matches!(
+            result,
+            Err(NjallaError::NotFound { kind: ResourceKind::Payment, id }) if id == "stale"
+        ));
+    }
+
+    #[test]
+    fn get_payment_keeps_other_api_errors() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": { "message": "Invalid token" }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let result = client.get_payment("pay123");
+
+        assert!(matches!(result, Err(NjallaError::Api { message }) if message == "Invalid token"));
+    }
+
     #[test]
     fn list_transactions_unwraps_result() {
         let mock_server = mock_server();
@@ -518,7 +1834,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"list-transactions","params":{}}"#,
+                    r#"{"method":"list-transactions","params":{},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -567,7 +1883,9 @@ mod tests {
         mount(
             &mock_server,
             Mock::given(method("POST"))
-                .and(body_json_string(r#"{"method":"list-domains","params":{}}"#))
+                .and(body_json_string(
+                    r#"{"method":"list-domains","params":{},"id":1}"#,
+                ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
                         "domains": [
@@ -597,6 +1915,107 @@ mod tests {
         assert_eq!(domains[1].status, "pending");
     }
 
+    #[test]
+    fn list_domains_follows_cursor() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-domains","params":{},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": {
+                        "domains": [{ "name": "a.com", "status": "active" }],
+                        "next": "page-2"
+                    }
+                })))
+                .expect(1),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-domains","params":{"cursor":"page-2"},"id":2}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": {
+                        "domains": [
+                            { "name": "b.com", "status": "active" },
+                            { "name": "c.com", "status": "pending" }
+                        ]
+                    }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let names: Vec<String> = client
+            .list_domains()
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+
+        assert_eq!(names, ["a.com", "b.com", "c.com"]);
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn list_domains_rejects_repeated_cursor() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "domains": [], "cursor": "same" }
+                })))
+                .expect(2),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+
+        assert!(matches!(
+            client.list_domains(),
+            Err(NjallaError::Api { .. })
+        ));
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn list_records_raw_keeps_api_field_order() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-records","params":{"domain":"example.com"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    r#"{"result":{"records":[{"ttl":3600,"type":"A","name":"@","id":"1","content":"192.0.2.1"}]}}"#,
+                    "application/json",
+                )),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let raw = client.list_records_raw("example.com").unwrap();
+
+        let keys: Vec<&str> = raw["records"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys, ["ttl", "type", "name", "id", "content"]);
+        assert_eq!(
+            crate::output::to_json(&raw["records"][0]).unwrap(),
+            "{\n  \"ttl\": 3600,\n  \"type\": \"A\",\n  \"name\": \"@\",\n  \"id\": \"1\",\n  \"content\": \"192.0.2.1\"\n}"
+        );
+    }
+
     #[test]
     fn list_domains_empty() {
         let mock_server = mock_server();
@@ -604,7 +2023,9 @@ mod tests {
         mount(
             &mock_server,
             Mock::given(method("POST"))
-                .and(body_json_string(r#"{"method":"list-domains","params":{}}"#))
+                .and(body_json_string(
+                    r#"{"method":"list-domains","params":{},"id":1}"#,
+                ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": { "domains": [] }
                 })))
@@ -625,7 +2046,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"get-domain","params":{"domain":"example.com"}}"#,
+                    r#"{"method":"get-domain","params":{"domain":"example.com"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -650,6 +2071,26 @@ mod tests {
         assert_eq!(domain.max_nameservers, Some(4));
     }
 
+    #[test]
+    fn get_domain_accepts_wrapped_response() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "domain": { "name": "example.com", "status": 1 } }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let domain = client.get_domain("example.com").unwrap();
+
+        assert_eq!(domain.name, "example.com");
+        assert_eq!(domain.status, "1");
+    }
+
     #[test]
     fn get_domain_not_found() {
         let mock_server = mock_server();
@@ -658,7 +2099,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"get-domain","params":{"domain":"notfound.com"}}"#,
+                    r#"{"method":"get-domain","params":{"domain":"notfound.com"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "error": { "message": "Domain not found" }
@@ -680,7 +2121,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"find-domains","params":{"query":"example"}}"#,
+                    r#"{"method":"find-domains","params":{"query":"example"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -707,15 +2148,13 @@ mod tests {
 
     #[test]
     fn list_records_returns_dns_records() {
-        use crate::types::RecordType;
-
         let mock_server = mock_server();
 
         mount(
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"list-records","params":{"domain":"example.com"}}"#,
+                    r#"{"method":"list-records","params":{"domain":"example.com"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -763,6 +2202,190 @@ mod tests {
         assert_eq!(records[2].priority, Some(10));
     }
 
+    /// Mount `list-records` and `remove-record` for example.com, expecting
+    /// `lists` listings.
+    fn mount_records(server: &MockServer, lists: u64) {
+        mount(
+            server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "list-records" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "records": [
+                        { "id": "rec1", "name": "@", "type": "A", "content": "192.0.2.1" }
+                    ] }
+                })))
+                .expect(lists),
+        );
+        mount(
+            server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "remove-record" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": {}
+                }))),
+        );
+    }
+
+    #[test]
+    fn list_records_is_cached_per_domain() {
+        let mock_server = mock_server();
+        mount_records(&mock_server, 1);
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+
+        let first = client.list_records("example.com").unwrap();
+        let second = client.list_records("Example.com.").unwrap();
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(second[0].id, first[0].id);
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn mutation_invalidates_cached_records() {
+        let mock_server = mock_server();
+        mount_records(&mock_server, 3);
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+
+        client.list_records("example.com").unwrap();
+        client.remove_record("example.com", "rec1").unwrap();
+        client.list_records("example.com").unwrap();
+        client.refresh_records("example.com").unwrap();
+        client.list_records("example.com").unwrap();
+
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn listing_during_a_mutation_is_not_kept() {
+        let mock_server = mock_server();
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "list-records" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "records": [] }
+                })))
+                .expect(2),
+        );
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "remove-record" }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": {} }))
+                        .set_delay(Duration::from_millis(300)),
+                ),
+        );
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+
+        std::thread::scope(|scope| {
+            let removal = scope.spawn(|| client.remove_record("example.com", "rec1"));
+            std::thread::sleep(Duration::from_millis(100));
+            client.list_records("example.com").unwrap();
+            removal.join().unwrap().unwrap();
+        });
+        client.list_records("example.com").unwrap();
+
+        RT.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn list_records_normalizes_names_and_name_filter() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-records","params":{"domain":"example.com","name":"@"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": {
+                        "records": [
+                            { "id": "rec1", "name": "", "type": "A", "content": "192.0.2.1" },
+                            { "id": "rec2", "name": "example.com.", "type": "MX", "content": "mx" },
+                            { "id": "rec3", "name": "www.example.com.", "type": "A", "content": "192.0.2.2" }
+                        ]
+                    }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let records = client
+            .list_records_filtered("example.com", Some("example.com."), None)
+            .unwrap();
+
+        let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["rec1", "rec2"]);
+        assert!(records.iter().all(|r| r.name == "@"));
+    }
+
+    #[test]
+    fn list_records_omits_filters_when_not_supplied() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-records","params":{"domain":"example.com"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "records": [] }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let records = client
+            .list_records_filtered("example.com", None, None)
+            .unwrap();
+
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn list_records_sends_filters_and_filters_locally() {
+        let mock_server = mock_server();
+
+        // The server ignores the filters and returns the whole zone.
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .and(body_json_string(
+                    r#"{"method":"list-records","params":{"domain":"example.com","name":"www","type":"A"},"id":1}"#,
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": {
+                        "records": [
+                            { "id": "rec1", "name": "@", "type": "A", "content": "192.0.2.1" },
+                            { "id": "rec2", "name": "www", "type": "A", "content": "192.0.2.2" },
+                            { "id": "rec3", "name": "www", "type": "AAAA", "content": "2001:db8::1" }
+                        ]
+                    }
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let records = client
+            .list_records_filtered("example.com", Some("www"), Some(RecordType::A))
+            .unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, "rec2");
+    }
+
     // ========================================================================
     // Registration Methods Tests
     // ========================================================================
@@ -775,7 +2398,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"register-domain","params":{"domain":"newdomain.com","years":1}}"#,
+                    r#"{"method":"register-domain","params":{"domain":"newdomain.com","years":1},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": { "task": "task-abc123" }
@@ -797,7 +2420,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"register-domain","params":{"domain":"expensive.com","years":2}}"#,
+                    r#"{"method":"register-domain","params":{"domain":"expensive.com","years":2},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "error": { "message": "Insufficient funds" }
@@ -819,7 +2442,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"check-task","params":{"id":"task-abc123"}}"#,
+                    r#"{"method":"check-task","params":{"id":"task-abc123"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -845,7 +2468,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"check-task","params":{"id":"task-xyz789"}}"#,
+                    r#"{"method":"check-task","params":{"id":"task-xyz789"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -871,7 +2494,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"check-task","params":{"id":"task-fail"}}"#,
+                    r#"{"method":"check-task","params":{"id":"task-fail"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -903,7 +2526,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"add-record","params":{"domain":"example.com","type":"A","name":"@","content":"1.2.3.4","ttl":3600}}"#,
+                    r#"{"method":"add-record","params":{"domain":"example.com","type":"A","name":"@","content":"1.2.3.4","ttl":3600},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -951,7 +2574,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"add-record","params":{"domain":"example.com","type":"MX","name":"@","content":"mail.example.com","ttl":3600,"prio":10}}"#,
+                    r#"{"method":"add-record","params":{"domain":"example.com","type":"MX","name":"@","content":"mail.example.com","ttl":3600,"prio":10},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -998,7 +2621,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"add-record","params":{"domain":"example.com","type":"SRV","name":"_sip._tcp","content":"sipserver.example.com","ttl":3600,"prio":10,"weight":5,"port":5060}}"#,
+                    r#"{"method":"add-record","params":{"domain":"example.com","type":"SRV","name":"_sip._tcp","content":"sipserver.example.com","ttl":3600,"prio":10,"weight":5,"port":5060},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -1048,7 +2671,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"add-record","params":{"domain":"example.com","type":"Dynamic","name":"home"}}"#,
+                    r#"{"method":"add-record","params":{"domain":"example.com","type":"Dynamic","name":"home"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -1091,7 +2714,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"edit-record","params":{"domain":"example.com","id":"rec123","content":"5.6.7.8","ttl":300}}"#,
+                    r#"{"method":"edit-record","params":{"domain":"example.com","id":"rec123","content":"5.6.7.8","ttl":300},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {
@@ -1136,7 +2759,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"remove-record","params":{"domain":"example.com","id":"rec123"}}"#,
+                    r#"{"method":"remove-record","params":{"domain":"example.com","id":"rec123"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "result": {}
@@ -1150,6 +2773,25 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn remove_record_accepts_null_result() {
+        let mock_server = mock_server();
+
+        mount(
+            &mock_server,
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": null
+                })))
+                .expect(1),
+        );
+
+        let client = NjallaClient::with_base_url("token", &mock_server.uri());
+        let result = client.remove_record("example.com", "rec123");
+
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn remove_record_not_found() {
         let mock_server = mock_server();
@@ -1158,7 +2800,7 @@ mod tests {
             &mock_server,
             Mock::given(method("POST"))
                 .and(body_json_string(
-                    r#"{"method":"remove-record","params":{"domain":"example.com","id":"notfound"}}"#,
+                    r#"{"method":"remove-record","params":{"domain":"example.com","id":"notfound"},"id":1}"#,
                 ))
                 .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                     "error": { "message": "Record not found" }
@@ -0,0 +1,172 @@
+//! Shared helpers for commands that operate on many items.
+
+use crate::error::{NjallaError, Result};
+use serde::Serialize;
+
+/// How a batch reacts when one item fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorMode {
+    /// Stop at the first failure; remaining items are skipped.
+    FailFast,
+    /// Attempt every item and report all failures at the end.
+    #[default]
+    ContinueOnError,
+}
+
+/// Result of one item in a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+/// Per-item outcome reported in the batch summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemOutcome {
+    /// Item label (e.g., a record ID).
+    pub item: String,
+    /// What happened to the item.
+    pub status: ItemStatus,
+    /// Error message for failed items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Totals and per-item outcomes for a batch.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    /// Number of items in the batch.
+    pub total: usize,
+    /// Items that were tried (total minus skipped).
+    pub attempted: usize,
+    /// Items that succeeded.
+    pub succeeded: usize,
+    /// Items that failed.
+    pub failed: usize,
+    /// Items not tried because of `--fail-fast`.
+    pub skipped: usize,
+    /// Outcome of each item, in input order.
+    pub items: Vec<ItemOutcome>,
+}
+
+impl BatchSummary {
+    /// Turn a summary with failures into an error, for a non-zero exit.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NjallaError::BatchFailed` if any item failed.
+    pub fn check(&self) -> Result<()> {
+        if self.failed == 0 {
+            Ok(())
+        } else {
+            Err(NjallaError::BatchFailed {
+                failed: self.failed,
+                total: self.total,
+            })
+        }
+    }
+}
+
+/// Run `f` on each item in order, honouring `mode` on failure.
+///
+/// Every bulk command goes through here so they all report the same totals
+/// and stop (or carry on) the same way.
+pub fn run_batch<T, L, F>(items: &[T], mode: ErrorMode, label: L, mut f: F) -> BatchSummary
+where
+    L: Fn(&T) -> String,
+    F: FnMut(&T) -> Result<()>,
+{
+    let mut outcomes = Vec::with_capacity(items.len());
+    let mut stopped = false;
+
+    for item in items {
+        if stopped {
+            outcomes.push(ItemOutcome {
+                item: label(item),
+                status: ItemStatus::Skipped,
+                error: None,
+            });
+            continue;
+        }
+
+        match f(item) {
+            Ok(()) => outcomes.push(ItemOutcome {
+                item: label(item),
+                status: ItemStatus::Ok,
+                error: None,
+            }),
+            Err(e) => {
+                outcomes.push(ItemOutcome {
+                    item: label(item),
+                    status: ItemStatus::Failed,
+                    error: Some(e.to_string()),
+                });
+                stopped = mode == ErrorMode::FailFast;
+            }
+        }
+    }
+
+    let count = |status| outcomes.iter().filter(|o| o.status == status).count();
+    let skipped = count(ItemStatus::Skipped);
+    BatchSummary {
+        total: items.len(),
+        attempted: items.len() - skipped,
+        succeeded: count(ItemStatus::Ok),
+        failed: count(ItemStatus::Failed),
+        skipped,
+        items: outcomes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn second_of_three_fails(mode: ErrorMode) -> (BatchSummary, usize) {
+        let mut calls = 0;
+        let summary = run_batch(&["a", "b", "c"], mode, |s| (*s).to_string(), |item| {
+            calls += 1;
+            if *item == "b" {
+                Err(NjallaError::Api {
+                    message: "Record not found".to_string(),
+                })
+            } else {
+                Ok(())
+            }
+        });
+        (summary, calls)
+    }
+
+    #[test]
+    fn run_batch_fail_fast_stops_after_first_failure() {
+        let (summary, calls) = second_of_three_fails(ErrorMode::FailFast);
+        assert_eq!(calls, 2);
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.attempted, 2);
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.items[2].status, ItemStatus::Skipped);
+        assert!(summary.check().is_err());
+    }
+
+    #[test]
+    fn run_batch_continue_on_error_attempts_everything() {
+        let (summary, calls) = second_of_three_fails(ErrorMode::ContinueOnError);
+        assert_eq!(calls, 3);
+        assert_eq!(summary.attempted, 3);
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(
+            summary.items[1].error.as_deref(),
+            Some("API error: Record not found")
+        );
+        assert!(matches!(
+            summary.check(),
+            Err(NjallaError::BatchFailed { failed: 1, total: 3 })
+        ));
+    }
+}
@@ -0,0 +1,379 @@
+//! Config maintenance commands.
+
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::config::{Config, CONFIG_FILE, PLACEHOLDER_TOKEN_MESSAGE};
+use crate::error::{NjallaError, Result};
+use crate::output::to_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Status shown by `njalla config`: whether the file exists and where the
+/// token comes from, with all but its first and last four characters masked.
+/// A blank token is reported as the unedited template placeholder.
+#[must_use]
+pub fn config_status(file_exists: bool, token: Option<&str>, from_env: bool) -> serde_json::Value {
+    let api_token = match token.map(str::trim) {
+        Some("") => serde_json::json!({
+            "configured": false,
+            "message": PLACEHOLDER_TOKEN_MESSAGE
+        }),
+        Some(token) => serde_json::json!({
+            "configured": true,
+            "masked_token": mask_token(token),
+            "source": if from_env { "env" } else { "config file" }
+        }),
+        None => serde_json::json!({
+            "configured": false,
+            "message": "Run 'njalla config --init' to create a config file"
+        }),
+    };
+
+    serde_json::json!({
+        "config_file": "./config.toml",
+        "file_exists": file_exists,
+        "api_token": api_token
+    })
+}
+
+/// Keep the first and last four characters of a token; short tokens are
+/// hidden entirely.
+fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() > 8 {
+        let head: String = chars[..4].iter().collect();
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("{head}...{tail}")
+    } else {
+        "****".to_string()
+    }
+}
+
+/// Run the config rotate command.
+///
+/// Validates the new token with `get-balance` before writing it, keeping the
+/// previous config file as a backup.
+pub fn run_rotate(new_token: &str, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::with_token(new_token, options);
+    let path = Path::new(CONFIG_FILE);
+
+    let backup = rotate_token(&client, path, new_token)?;
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "status": "rotated",
+        "path": path,
+        "backup": backup,
+        "env_override": std::env::var("NJALLA_API_TOKEN").is_ok_and(|t| !t.is_empty()),
+        "message": "Token validated and saved. Run 'njalla config rotate --confirm' to delete the backup, or '--revert' to restore it."
+    }))?))
+}
+
+/// Run the config rotate --revert command.
+///
+/// Restores the config file from the backup left by a rotation.
+pub fn run_revert() -> Result<CommandOutput> {
+    let path = Path::new(CONFIG_FILE);
+    let backup = backup_path(path);
+
+    if !backup.exists() {
+        return Err(NjallaError::Config {
+            message: format!("No backup found at {}", backup.display()),
+        });
+    }
+    fs::rename(&backup, path).map_err(|e| NjallaError::Config {
+        message: format!("Failed to restore config backup: {e}"),
+    })?;
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "status": "reverted",
+        "path": path,
+    }))?))
+}
+
+/// Run the config rotate --confirm command.
+///
+/// Deletes the backup once the new token is known to work.
+pub fn run_confirm() -> Result<CommandOutput> {
+    let backup = backup_path(Path::new(CONFIG_FILE));
+
+    if backup.exists() {
+        fs::remove_file(&backup).map_err(|e| NjallaError::Config {
+            message: format!("Failed to remove config backup: {e}"),
+        })?;
+    }
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "status": "confirmed",
+        "backup_removed": backup,
+    }))?))
+}
+
+/// Run the config edit command.
+///
+/// Creates the config file from the template if needed, opens it in the
+/// user's editor, and parses it once the editor exits.
+pub fn run_edit() -> Result<CommandOutput> {
+    let path = Path::new(CONFIG_FILE);
+    let created = Config::init(path)?;
+    let editor = resolve_editor(
+        std::env::var("VISUAL").ok().as_deref(),
+        std::env::var("EDITOR").ok().as_deref(),
+    );
+
+    let config = edit_and_validate(path, |path| launch_editor(&editor, path))?;
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "status": "edited",
+        "path": path,
+        "created": created,
+        "api_token_set": config.api_token.is_some_and(|t| !t.is_empty()),
+    }))?))
+}
+
+/// Pick the editor command: `$VISUAL`, then `$EDITOR`, then a platform default.
+fn resolve_editor(visual: Option<&str>, editor: Option<&str>) -> String {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|e| !e.is_empty())
+        .map_or_else(
+            || if cfg!(windows) { "notepad" } else { "vi" }.to_string(),
+            str::to_string,
+        )
+}
+
+/// Run `editor` on `path` and wait for it. The command may carry arguments,
+/// e.g. `code --wait`.
+fn launch_editor(editor: &str, path: &Path) -> Result<()> {
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or(editor);
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| NjallaError::Config {
+            message: format!("Failed to launch editor '{editor}': {e}"),
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(NjallaError::Config {
+            message: format!("Editor '{editor}' exited with {status}"),
+        })
+    }
+}
+
+/// Let `edit` change the file at `path`, then parse the result.
+fn edit_and_validate<F>(path: &Path, edit: F) -> Result<Config>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    edit(path)?;
+    Config::load_file(path)
+}
+
+/// Path of the backup kept during token rotation.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Validate `new_token` through `client`, then write it to `path`.
+///
+/// Nothing is written unless validation succeeds. Returns the backup path
+/// if there was an existing config to back up.
+fn rotate_token(client: &NjallaClient, path: &Path, new_token: &str) -> Result<Option<PathBuf>> {
+    let backup = backup_path(path);
+    if backup.exists() {
+        return Err(NjallaError::Config {
+            message: format!(
+                "A previous backup exists at {}; run 'njalla config rotate --confirm' or '--revert' first",
+                backup.display()
+            ),
+        });
+    }
+
+    let mut config = Config::load_file(path)?;
+    client.get_balance()?;
+
+    let backup = if path.exists() {
+        fs::copy(path, &backup).map_err(|e| NjallaError::Config {
+            message: format!("Failed to back up config file: {e}"),
+        })?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    config.api_token = Some(new_token.to_string());
+    config.save(path)?;
+
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+
+    #[test]
+    fn status_masks_configured_token_and_names_its_source() {
+        assert_eq!(
+            config_status(true, Some("abcd1234efgh5678"), false),
+            serde_json::json!({
+                "config_file": "./config.toml",
+                "file_exists": true,
+                "api_token": {
+                    "configured": true,
+                    "masked_token": "abcd...5678",
+                    "source": "config file"
+                }
+            })
+        );
+        let status = config_status(false, Some("short"), true);
+        assert_eq!(status["api_token"]["masked_token"], "****");
+        assert_eq!(status["api_token"]["source"], "env");
+    }
+
+    #[test]
+    fn status_without_token_points_at_init() {
+        let status = config_status(false, None, false);
+        assert_eq!(status["file_exists"], false);
+        assert_eq!(status["api_token"]["configured"], false);
+        assert!(status["api_token"].get("masked_token").is_none());
+        assert!(status["api_token"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("config --init"));
+    }
+
+    #[test]
+    fn status_with_blank_token_names_the_placeholder() {
+        for token in ["", "   "] {
+            let status = config_status(true, Some(token), false);
+            assert_eq!(status["api_token"]["configured"], false);
+            assert_eq!(status["api_token"]["message"], PLACEHOLDER_TOKEN_MESSAGE);
+        }
+    }
+
+    #[test]
+    fn mask_token_counts_characters_not_bytes() {
+        assert_eq!(mask_token("ééééxxxxéééé"), "éééé...éééé");
+    }
+
+    fn balance_server(token: &str, response: serde_json::Value) -> MockServer {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(header("Authorization", format!("Njalla {token}").as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(response))
+                .mount(&server),
+        );
+        server
+    }
+
+    fn temp_config(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("njalla-rotate-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "api_token = \"old-token\"\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn rotate_with_invalid_token_leaves_config_unchanged() {
+        let server = balance_server(
+            "bad-token",
+            serde_json::json!({ "error": { "message": "Invalid token" } }),
+        );
+        let client = NjallaClient::with_base_url("bad-token", &server.uri());
+        let path = temp_config("invalid");
+
+        let result = rotate_token(&client, &path, "bad-token");
+
+        assert!(matches!(result, Err(NjallaError::Api { .. })));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "api_token = \"old-token\"\n");
+        assert!(!backup_path(&path).exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rotate_with_valid_token_writes_config_and_backup() {
+        let server = balance_server("new-token", serde_json::json!({ "result": { "balance": 5 } }));
+        let client = NjallaClient::with_base_url("new-token", &server.uri());
+        let path = temp_config("valid");
+
+        let backup = rotate_token(&client, &path, "new-token").unwrap().unwrap();
+
+        assert_eq!(
+            Config::load_file(&path).unwrap().api_token.as_deref(),
+            Some("new-token")
+        );
+        assert_eq!(
+            Config::load_file(&backup).unwrap().api_token.as_deref(),
+            Some("old-token")
+        );
+        assert!(rotate_token(&client, &path, "new-token").is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn resolve_editor_prefers_visual_then_editor() {
+        assert_eq!(resolve_editor(Some("nvim"), Some("nano")), "nvim");
+        assert_eq!(
+            resolve_editor(Some(" "), Some("code --wait")),
+            "code --wait"
+        );
+        assert_eq!(
+            resolve_editor(None, None),
+            if cfg!(windows) { "notepad" } else { "vi" }
+        );
+    }
+
+    #[test]
+    fn edit_creates_config_then_validates_result() {
+        let path = temp_config("edit");
+        fs::remove_file(&path).unwrap();
+
+        assert!(Config::init(&path).unwrap());
+        assert!(!Config::init(&path).unwrap());
+        let config = edit_and_validate(&path, |p| {
+            assert!(fs::read_to_string(p).unwrap().contains("api_token = \"\""));
+            fs::write(p, "api_token = \"edited\"\n").unwrap();
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(config.api_token.as_deref(), Some("edited"));
+
+        let result = edit_and_validate(&path, |p| {
+            fs::write(p, "api_token = \"unterminated\n").unwrap();
+            Ok(())
+        });
+        assert!(
+            matches!(&result, Err(NjallaError::Config { message }) if message.starts_with("Failed to parse config file"))
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn backup_path_appends_suffix() {
+        assert_eq!(
+            backup_path(Path::new("config.toml")),
+            PathBuf::from("config.toml.bak")
+        );
+    }
+}
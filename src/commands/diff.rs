@@ -0,0 +1,153 @@
+//! Diff command: compare the domains of two accounts.
+
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::concurrency::{fan_out, DEFAULT_CONCURRENCY};
+use crate::config::Config;
+use crate::error::{NjallaError, Result};
+use crate::output::to_json;
+use crate::types::Domain;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Domains present in one or both of two accounts, sorted by name.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct DomainDiff {
+    /// Domains only in the first account.
+    pub only_in_first: Vec<String>,
+    /// Domains only in the second account.
+    pub only_in_second: Vec<String>,
+    /// Domains in both accounts.
+    pub in_both: Vec<String>,
+}
+
+/// Compare two domain lists by name.
+#[must_use]
+pub fn compare_domains(first: &[Domain], second: &[Domain]) -> DomainDiff {
+    let first: BTreeSet<&str> = first.iter().map(|d| d.name.as_str()).collect();
+    let second: BTreeSet<&str> = second.iter().map(|d| d.name.as_str()).collect();
+    let names = |set: BTreeSet<&&str>| set.into_iter().map(|s| (*s).to_string()).collect();
+
+    DomainDiff {
+        only_in_first: names(first.difference(&second).collect()),
+        only_in_second: names(second.difference(&first).collect()),
+        in_both: names(first.intersection(&second).collect()),
+    }
+}
+
+/// Run the diff command.
+///
+/// Each account is a config file with its own `api_token`. Both are listed
+/// concurrently; a failure on one account is reported without hiding the
+/// other, and the domain comparison is printed only when both succeed.
+pub fn run(first: &Path, second: &Path, options: &ClientOptions) -> Result<CommandOutput> {
+    let configs = [first.to_path_buf(), second.to_path_buf()];
+    let listings = fan_out(
+        &configs,
+        options.max_concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+        |path| list_account(path, options),
+    );
+    let listed = listings.len();
+    let Ok([first_domains, second_domains]) = <[_; 2]>::try_from(listings) else {
+        return Err(NjallaError::BatchFailed {
+            failed: 2usize.saturating_sub(listed),
+            total: 2,
+        });
+    };
+
+    let accounts: Vec<serde_json::Value> = configs
+        .iter()
+        .zip([&first_domains, &second_domains])
+        .map(|(path, listing)| account_summary(path, listing))
+        .collect();
+
+    let (Ok(first_domains), Ok(second_domains)) = (&first_domains, &second_domains) else {
+        let failed = usize::from(first_domains.is_err()) + usize::from(second_domains.is_err());
+        return Ok(CommandOutput::Failed(
+            to_json(&serde_json::json!({ "accounts": accounts }))?,
+            NjallaError::BatchFailed { failed, total: 2 },
+        ));
+    };
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "accounts": accounts,
+        "diff": compare_domains(first_domains, second_domains),
+    }))?))
+}
+
+/// List the domains of the account configured in `path`.
+fn list_account(path: &Path, options: &ClientOptions) -> Result<Vec<Domain>> {
+    if !path.exists() {
+        return Err(NjallaError::Config {
+            message: format!("Config file not found: {}", path.display()),
+        });
+    }
+    let config = Config::load_file(path)?;
+    let client = NjallaClient::with_token(config.api_token()?, &options.with_config(&config));
+    client.list_domains()
+}
+
+/// Per-account line of the report: domain count or error.
+fn account_summary(path: &Path, listing: &Result<Vec<Domain>>) -> serde_json::Value {
+    match listing {
+        Ok(domains) => serde_json::json!({
+            "config": path.display().to_string(),
+            "domains": domains.len(),
+        }),
+        Err(e) => serde_json::json!({
+            "config": path.display().to_string(),
+            "error": e.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains(names: &[&str]) -> Vec<Domain> {
+        names
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::json!({ "name": name, "status": "active" }))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn compare_overlapping_accounts() {
+        let diff = compare_domains(
+            &domains(&["c.com", "a.com", "shared.org"]),
+            &domains(&["shared.org", "b.net"]),
+        );
+        assert_eq!(diff.only_in_first, strings(&["a.com", "c.com"]));
+        assert_eq!(diff.only_in_second, strings(&["b.net"]));
+        assert_eq!(diff.in_both, strings(&["shared.org"]));
+    }
+
+    #[test]
+    fn compare_disjoint_accounts() {
+        let diff = compare_domains(&domains(&["a.com"]), &domains(&["b.com", "c.com"]));
+        assert_eq!(diff.only_in_first, strings(&["a.com"]));
+        assert_eq!(diff.only_in_second, strings(&["b.com", "c.com"]));
+        assert!(diff.in_both.is_empty());
+    }
+
+    #[test]
+    fn missing_config_is_reported_per_account() {
+        let path = Path::new("/nonexistent/njalla-diff.toml");
+        let listing = list_account(path, &ClientOptions::default());
+        let summary = account_summary(path, &listing);
+        assert!(summary["error"]
+            .as_str()
+            .unwrap()
+            .contains("Config file not found"));
+        assert!(summary.get("domains").is_none());
+    }
+}
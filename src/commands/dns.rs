@@ -1,57 +1,2716 @@
 //! DNS record management commands.
 
-use crate::client::NjallaClient;
-use crate::error::Result;
-use crate::output::{format_record, format_records};
-use crate::types::{AddRecordParams, EditRecordParams};
+use crate::client::{ClientOptions, NjallaClient, DEFAULT_RECORD_TTL};
+use crate::commands::bulk::{run_batch, BatchSummary, ErrorMode};
+use crate::commands::CommandOutput;
+use crate::config::Config;
+use crate::error::{NjallaError, Result};
+use crate::export::{export, ExportFormat};
+use crate::filter::FieldFilter;
+use crate::output::{
+    format_grouped_records, format_record, format_records, format_records_porcelain, group_records,
+    retain_listed, sort_records, streaming, to_json, write_grouped_records, write_records, GroupBy,
+};
+use crate::record_comments::{RecordComments, COMMENTS_FILE};
+use crate::types::{
+    is_system_record, normalize_content, normalize_record_name, AddRecordParams, EditRecordParams,
+    Record, RecordType,
+};
+use crate::zone_snapshots::{ZoneSnapshots, SNAPSHOTS_FILE};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Run the dns list command.
 ///
-/// Lists all DNS records for a domain.
-pub fn run_list(domain: &str, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Lists DNS records for a domain, optionally filtered by name and type.
+/// Records are sorted for stable output unless `unsorted` is set, and
+/// printed as an object of groups when `group_by` is given. With
+/// `user_records_only`, records Njalla manages itself are left out (see
+/// [`is_system_record`]), and `only` keeps records passing every `--only`
+/// filter. Records with a local comment from `dns comment`
+/// show it. With `raw`, the API result is printed unchanged, in the API's
+/// field order; with `porcelain`, as tab-separated lines.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+pub fn run_list(
+    domain: &str,
+    name: Option<&str>,
+    record_type: Option<RecordType>,
+    unsorted: bool,
+    group_by: Option<GroupBy>,
+    user_records_only: bool,
+    only: &[FieldFilter],
+    raw: bool,
+    porcelain: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
+    if raw {
+        return Ok(CommandOutput::Text(to_json(
+            &client.list_records_raw(domain)?,
+        )?));
+    }
+
+    let mut records = client.list_records_filtered(domain, name, record_type)?;
+    if user_records_only {
+        records.retain(|r| !is_system_record(r, domain));
+    }
+    let comments = list_comments(Path::new(COMMENTS_FILE), domain, &records);
+    retain_listed(&mut records, &comments, only)?;
+    if !unsorted {
+        sort_records(&mut records);
+    }
+    if porcelain {
+        return Ok(CommandOutput::Text(format_records_porcelain(&records)));
+    }
+    let streamed = streaming();
+    let output = match group_by {
+        Some(by) => {
+            let groups = group_records(records, by);
+            if streamed {
+                CommandOutput::stream(move |out| write_grouped_records(out, &groups, &comments))
+            } else {
+                CommandOutput::Text(format_grouped_records(&groups, &comments)?)
+            }
+        }
+        None if streamed => {
+            CommandOutput::stream(move |out| write_records(out, &records, &comments))
+        }
+        None => CommandOutput::Text(format_records(&records, &comments)?),
+    };
+    Ok(output)
+}
+
+/// Comments on `records` by record id. An unreadable comments file is
+/// warned about rather than failing the listing.
+fn list_comments(path: &Path, domain: &str, records: &[Record]) -> BTreeMap<String, String> {
+    let comments = match RecordComments::load(path) {
+        Ok(comments) => comments,
+        Err(e) => {
+            eprintln!("Warning: {e}");
+            return BTreeMap::new();
+        }
+    };
+    records
+        .iter()
+        .filter_map(|record| {
+            let comment = comments.comment_for(domain, record)?;
+            Some((record.id.clone(), comment.to_string()))
+        })
+        .collect()
+}
+
+/// Run the dns comment command.
+///
+/// Sets the local comment on a record, or clears it when `comment` is `None`.
+/// The record is looked up so its name, type and content can identify it
+/// if Njalla later gives it a new id.
+pub fn run_comment(
+    domain: &str,
+    id: &str,
+    comment: Option<&str>,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let record = fetch_record(&client, domain, id)?;
+    let path = Path::new(COMMENTS_FILE);
+    let mut comments = RecordComments::load(path)?;
+    let status = match comment {
+        Some(comment) => {
+            comments.set(domain, &record, comment);
+            "set"
+        }
+        None if comments.remove(domain, &record) => "cleared",
+        None => "none",
+    };
+    comments.save(path)?;
+
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "domain": domain,
+        "id": record.id,
+        "name": record.name,
+        "type": record.record_type,
+        "comment": comment,
+        "status": status,
+    }))?))
+}
+
+/// Run the dns snapshot command.
+///
+/// Saves the domain's live records in `./snapshots.json` for `dns changed`,
+/// replacing the domain's previous snapshot.
+pub fn run_snapshot(domain: &str, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let summary = save_snapshot(&client, domain, timestamp, Path::new(SNAPSHOTS_FILE))?;
+    Ok(CommandOutput::Text(to_json(&summary)?))
+}
+
+fn save_snapshot(
+    client: &NjallaClient,
+    domain: &str,
+    timestamp: u64,
+    path: &Path,
+) -> Result<serde_json::Value> {
     let records = client.list_records(domain)?;
-    let formatted = format_records(&records)?;
-    println!("{formatted}");
+    let count = records.len();
+    let mut snapshots = ZoneSnapshots::load(path)?;
+    snapshots.set(domain, timestamp, records);
+    snapshots.save(path)?;
+    Ok(serde_json::json!({
+        "domain": domain,
+        "timestamp": timestamp,
+        "records": count,
+    }))
+}
 
-    Ok(())
+/// Run the dns changed command.
+///
+/// Compares the domain's live records with its last `dns snapshot`, using
+/// the same matching as `dns replace`: records added and removed since,
+/// and records modified in place (same name and type, other fields
+/// changed). Records Njalla manages itself are ignored.
+pub fn run_changed(domain: &str, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+    let changes = zone_changes(&client, domain, Path::new(SNAPSHOTS_FILE))?;
+    Ok(CommandOutput::Text(to_json(&changes)?))
+}
+
+fn zone_changes(client: &NjallaClient, domain: &str, path: &Path) -> Result<serde_json::Value> {
+    let snapshots = ZoneSnapshots::load(path)?;
+    let Some(snapshot) = snapshots.get(domain) else {
+        return Err(NjallaError::Input {
+            message: format!("No snapshot of {domain}; run 'njalla dns snapshot {domain}' first"),
+        });
+    };
+    let plan = plan_replace(
+        &snapshot.records,
+        &client.list_records(domain)?,
+        domain,
+        None,
+    );
+    Ok(serde_json::json!({
+        "domain": domain,
+        "snapshot_timestamp": snapshot.timestamp,
+        "changed": !(plan.add.is_empty() && plan.edit.is_empty() && plan.remove.is_empty()),
+        "added": plan.add.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "removed": plan.remove.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "modified": plan.edit.iter().map(edit_label).collect::<Vec<_>>(),
+        "unchanged": plan.unchanged,
+    }))
+}
+
+/// Run the dns export command.
+///
+/// Prints the domain's records in another provider's import format.
+/// Records the format cannot express are listed as warnings on stderr. With
+/// `user_records_only`, records Njalla manages itself are left out.
+pub fn run_export(
+    domain: &str,
+    format: ExportFormat,
+    user_records_only: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let mut records = client.list_records(domain)?;
+    if user_records_only {
+        records.retain(|r| !is_system_record(r, domain));
+    }
+    sort_records(&mut records);
+    let export = export(format, domain, &records)?;
+    for skipped in &export.skipped {
+        eprintln!("Warning: skipped {skipped}");
+    }
+    // Printing adds the final newline.
+    let output = export.output.strip_suffix('\n').unwrap_or(&export.output);
+    Ok(CommandOutput::Text(output.to_string()))
+}
+
+/// TTL bounds from `--dns-ttl-min` and `--dns-ttl-max`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TtlLimits {
+    /// Lowest TTL allowed.
+    pub min: Option<i32>,
+    /// Highest TTL allowed.
+    pub max: Option<i32>,
+}
+
+/// A TTL bound and the setting it came from, for error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TtlBound {
+    value: i32,
+    source: &'static str,
+}
+
+/// Allowed TTL range: `dns_ttl_min`/`dns_ttl_max` from the config,
+/// tightened by the `--dns-ttl-*` flags.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TtlPolicy {
+    min: Option<TtlBound>,
+    max: Option<TtlBound>,
+}
+
+impl TtlPolicy {
+    /// Combine the config's bounds with the flags. A flag may only narrow
+    /// the range; one that would widen it is an error.
+    fn new(config: &Config, limits: TtlLimits) -> Result<Self> {
+        let min = tighter_bound(
+            config.dns_ttl_min.map(|value| TtlBound {
+                value,
+                source: "dns_ttl_min in config",
+            }),
+            limits.min.map(|value| TtlBound {
+                value,
+                source: "--dns-ttl-min",
+            }),
+            |flag, config| flag >= config,
+        )?;
+        let max = tighter_bound(
+            config.dns_ttl_max.map(|value| TtlBound {
+                value,
+                source: "dns_ttl_max in config",
+            }),
+            limits.max.map(|value| TtlBound {
+                value,
+                source: "--dns-ttl-max",
+            }),
+            |flag, config| flag <= config,
+        )?;
+        if let (Some(min), Some(max)) = (min, max) {
+            if min.value > max.value {
+                return Err(NjallaError::Input {
+                    message: format!(
+                        "TTL policy allows nothing: minimum {} ({}) is above maximum {} ({})",
+                        min.value, min.source, max.value, max.source
+                    ),
+                });
+            }
+        }
+        Ok(Self { min, max })
+    }
+
+    /// Reject `ttl` if it is outside the policy, naming the bound's source.
+    fn check(&self, ttl: i32) -> Result<()> {
+        let violation = match (self.min, self.max) {
+            (Some(min), _) if ttl < min.value => Some(("below the minimum", min)),
+            (_, Some(max)) if ttl > max.value => Some(("above the maximum", max)),
+            _ => None,
+        };
+        match violation {
+            Some((what, bound)) => Err(NjallaError::Input {
+                message: format!(
+                    "TTL {ttl} is {what} of {} set by {}",
+                    bound.value, bound.source
+                ),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The flag's bound if it is at least as strict as the config's
+/// (`stricter(flag, config)`), the config's if there is no flag.
+fn tighter_bound(
+    config: Option<TtlBound>,
+    flag: Option<TtlBound>,
+    stricter: fn(i32, i32) -> bool,
+) -> Result<Option<TtlBound>> {
+    match (config, flag) {
+        (Some(config), Some(flag)) if !stricter(flag.value, config.value) => {
+            Err(NjallaError::Input {
+                message: format!(
+                    "{} {} would loosen {} = {}; flags can only tighten the policy",
+                    flag.source, flag.value, config.source, config.value
+                ),
+            })
+        }
+        (config, flag) => Ok(flag.or(config)),
+    }
 }
 
 /// Run the dns add command.
 ///
-/// Adds a new DNS record to a domain.
-pub fn run_add(params: &AddRecordParams, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Adds a new DNS record to a domain. The name is normalized first, so
+/// `example.com.` and `www.example.com.` work like `@` and `www`. Records
+/// without a TTL inherit `default_ttl`, falling back to `default_ttl` from
+/// the config file. The TTL the record ends up with, the API default
+/// included, must satisfy the TTL policy.
+/// The record is summarized on stderr before it is sent. Unless `verify` is
+/// false, the created record is compared with the request and mismatches are
+/// reported as warnings. With `replace_existing`, see [`add_or_replace`].
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+pub fn run_add(
+    params: AddRecordParams,
+    default_ttl: Option<i32>,
+    ttl_limits: TtlLimits,
+    verify: bool,
+    with_ptr: Option<&str>,
+    atomic: bool,
+    replace_existing: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    check_add_flags(&params)?;
+    let client = NjallaClient::new(options)?;
+    let config = Config::load()?;
+    let default_ttl = default_ttl.or(config.default_ttl);
+    let mut params = params.with_default_ttl(default_ttl);
+    TtlPolicy::new(&config, ttl_limits)?.check(params.ttl.unwrap_or(DEFAULT_RECORD_TTL))?;
+    params.name = normalize_record_name(&params.name, &params.domain);
 
-    let record = client.add_record(params)?;
-    let formatted = format_record(&record)?;
-    println!("{formatted}");
+    if params.record_type == RecordType::Aname {
+        validate_aname_target(params.content.as_deref())?;
+    }
+    let ptr = with_ptr
+        .map(|reverse_zone| ptr_params(&params, reverse_zone))
+        .transpose()?;
 
-    Ok(())
+    if add_checks_listing(&params, replace_existing) {
+        let existing = client.list_records(&params.domain)?;
+        for warning in add_warnings(&params, &existing) {
+            eprintln!("Warning: {warning}");
+        }
+    }
+
+    let record = if replace_existing {
+        add_or_replace(&client, &params)?
+    } else {
+        eprintln!("Adding {params}");
+        client.add_record(&params)?
+    };
+    if verify {
+        for warning in verify_created(&params, &record) {
+            eprintln!("Warning: {warning}");
+        }
+    }
+    let Some(ptr) = ptr else {
+        let formatted = format_record(&record)?;
+        return Ok(CommandOutput::Text(formatted));
+    };
+    let ptr = add_ptr_companion(&client, &params.domain, &record, &ptr, atomic)?;
+    let formatted = to_json(&serde_json::json!({ "record": record, "ptr": ptr }))?;
+    Ok(CommandOutput::Text(formatted))
+}
+
+/// The PTR record for `--with-ptr`, pointing the address of an A/AAAA record
+/// in `params` back at its fully qualified name.
+///
+/// The reverse name of the address (`4.3.2.1.in-addr.arpa`, or nibbles under
+/// `ip6.arpa`) must lie inside `reverse_zone`, and the PTR is named relative
+/// to it. It gets the forward record's TTL.
+fn ptr_params(params: &AddRecordParams, reverse_zone: &str) -> Result<AddRecordParams> {
+    if !matches!(params.record_type, RecordType::A | RecordType::Aaaa) {
+        return Err(NjallaError::Input {
+            message: format!(
+                "--with-ptr needs an A or AAAA record, not {}",
+                params.record_type
+            ),
+        });
+    }
+    let content = params.content.as_deref().unwrap_or_default();
+    let address: IpAddr = content.trim().parse().map_err(|_| NjallaError::Input {
+        message: format!("--with-ptr needs an IP address as content, got '{content}'"),
+    })?;
+    let reverse = reverse_name(address);
+    let zone = reverse_zone.trim_end_matches('.').to_ascii_lowercase();
+    let name = if reverse == zone {
+        "@".to_string()
+    } else {
+        reverse
+            .strip_suffix(&format!(".{zone}"))
+            .ok_or_else(|| NjallaError::Input {
+                message: format!("{reverse} is not inside reverse zone {zone}"),
+            })?
+            .to_string()
+    };
+    let domain = params.domain.trim_end_matches('.');
+    let target = match params.name.as_str() {
+        "" | "@" => domain.to_string(),
+        name => format!("{name}.{domain}"),
+    };
+
+    Ok(AddRecordParams {
+        domain: zone,
+        record_type: RecordType::Ptr,
+        name,
+        content: Some(target),
+        ttl: params.ttl,
+        priority: None,
+        weight: None,
+        port: None,
+        target: None,
+        value: None,
+        ssh_algorithm: None,
+        ssh_type: None,
+    })
+}
+
+/// Reverse DNS name of an address, without a trailing dot.
+fn reverse_name(address: IpAddr) -> String {
+    match address {
+        IpAddr::V4(v4) => {
+            let octets: Vec<String> = v4.octets().iter().rev().map(u8::to_string).collect();
+            format!("{}.in-addr.arpa", octets.join("."))
+        }
+        IpAddr::V6(v6) => {
+            let nibbles: Vec<String> = v6
+                .octets()
+                .iter()
+                .rev()
+                .flat_map(|byte| [byte & 0xf, byte >> 4])
+                .map(|nibble| format!("{nibble:x}"))
+                .collect();
+            format!("{}.ip6.arpa", nibbles.join("."))
+        }
+    }
+}
+
+/// Add a record for `dns add --replace-existing`, editing the existing record
+/// with the same name and type instead if there is exactly one.
+///
+/// Meant for singletons such as an apex A record or a CNAME, where adding
+/// usually means "set this to". With several matching records it is not
+/// clear which one to replace, so nothing is changed.
+///
+/// # Errors
+///
+/// Returns `NjallaError::Input` if more than one record matches, or an error
+/// if an API request fails.
+fn add_or_replace(client: &NjallaClient, params: &AddRecordParams) -> Result<Record> {
+    let existing = client.list_records(&params.domain)?;
+    let matching: Vec<&Record> = existing
+        .iter()
+        .filter(|r| {
+            r.record_type == params.record_type && r.name.eq_ignore_ascii_case(&params.name)
+        })
+        .collect();
+    match matching.as_slice() {
+        [] => {
+            eprintln!("Adding {params}");
+            client.add_record(params)
+        }
+        [record] => {
+            eprintln!("Replacing {record} with {params}");
+            client.edit_record(&edit_params(&record.id, params))
+        }
+        several => Err(NjallaError::Input {
+            message: format!(
+                "{} {} records named {} exist; --replace-existing needs at most one \
+                 (edit one with 'njalla dns edit --id')",
+                several.len(),
+                params.record_type,
+                params.name
+            ),
+        }),
+    }
+}
+
+/// Create the PTR for a forward record that was just added.
+///
+/// If the PTR cannot be created and `atomic` is set, the forward record is
+/// removed again; otherwise it is kept and reported on stderr. Either way
+/// the PTR's error is returned.
+fn add_ptr_companion(
+    client: &NjallaClient,
+    domain: &str,
+    forward: &Record,
+    ptr: &AddRecordParams,
+    atomic: bool,
+) -> Result<Record> {
+    let err = match client.add_record(ptr) {
+        Ok(record) => return Ok(record),
+        Err(err) => err,
+    };
+    let label = forward.to_string();
+    if !atomic {
+        eprintln!("Warning: kept {label} in {domain}, but its PTR was not created");
+    } else if let Err(rollback) = client.remove_record(domain, &forward.id) {
+        eprintln!("Warning: failed to roll back {label} in {domain}: {rollback}");
+    } else {
+        eprintln!("Rolled back {label} in {domain}");
+    }
+    Err(err)
 }
 
 /// Run the dns edit command.
 ///
-/// Edits an existing DNS record.
-pub fn run_edit(params: &EditRecordParams, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Edits an existing DNS record, summarizing the edit on stderr first. A new
+/// TTL must satisfy the TTL policy.
+/// With `if_unchanged`, the edit goes through [`guarded_edit`].
+pub fn run_edit(
+    params: &EditRecordParams,
+    if_unchanged: Option<&str>,
+    ttl_limits: TtlLimits,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+    if let Some(ttl) = params.ttl {
+        TtlPolicy::new(&Config::load()?, ttl_limits)?.check(ttl)?;
+    }
 
-    let record = client.edit_record(params)?;
+    eprintln!("Editing {params}");
+    let record = if let Some(fingerprint) = if_unchanged {
+        guarded_edit(&client, params, fingerprint)?
+    } else {
+        client.edit_record(params)?
+    };
     let formatted = format_record(&record)?;
-    println!("{formatted}");
+    Ok(CommandOutput::Text(formatted))
+}
 
-    Ok(())
+/// Edit a record only if it still has the `fingerprint` `dns list` showed.
+///
+/// Njalla has no conditional writes, so this reads the record once right
+/// before editing and aborts with `NjallaError::RecordChanged` if its
+/// [`Record::fingerprint`] differs. A change landing after that read is
+/// still overwritten; this narrows the race window but cannot close it.
+fn guarded_edit(
+    client: &NjallaClient,
+    params: &EditRecordParams,
+    fingerprint: &str,
+) -> Result<Record> {
+    let current = fetch_record(client, &params.domain, &params.id)?.fingerprint();
+    if !current.eq_ignore_ascii_case(fingerprint.trim()) {
+        return Err(NjallaError::RecordChanged {
+            id: params.id.clone(),
+        });
+    }
+    client.edit_record(params)
+}
+
+/// Fetch a single record by ID from the API, never from the client's cache.
+fn fetch_record(client: &NjallaClient, domain: &str, id: &str) -> Result<Record> {
+    client
+        .refresh_records(domain)?
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| NjallaError::Api {
+            message: format!("Record {id} not found"),
+        })
 }
 
 /// Run the dns remove command.
 ///
-/// Removes a DNS record from a domain.
-pub fn run_remove(domain: &str, id: &str, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Removes DNS records from a domain. A single ID keeps the original
+/// output; several IDs run as a batch and print a summary.
+pub fn run_remove(
+    domain: &str,
+    ids: &[String],
+    mode: ErrorMode,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    if let [id] = ids {
+        client.remove_record(domain, id)?;
+        return Ok(CommandOutput::Text(
+            serde_json::json!({"status": "removed", "id": id}).to_string(),
+        ));
+    }
+
+    let summary = run_batch(ids, mode, Clone::clone, |id| {
+        client.remove_record(domain, id)
+    });
+    Ok(CommandOutput::checked(to_json(&summary)?, summary.check()))
+}
+
+/// Run the dns copy command.
+///
+/// Recreates the records of `source` on `dest`. Hostnames under `source`
+/// (CNAME/MX/NS/SRV targets and the like) are rewritten to `dest`, records
+/// Njalla manages itself are left alone (see [`is_system_record`]), and
+/// records `dest` already has are skipped.
+/// With `replace`, other records on `dest` are removed once the adds are
+/// done, like `dns replace`, so no name is left without an answer. With
+/// `dry_run`, only the plan is printed.
+pub fn run_copy(
+    source: &str,
+    dest: &str,
+    dry_run: bool,
+    replace: bool,
+    mode: ErrorMode,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let plan = plan_copy(
+        &client.list_records(source)?,
+        &client.list_records(dest)?,
+        source,
+        dest,
+        replace,
+    );
+    if dry_run {
+        return Ok(CommandOutput::Text(to_json(&plan.summary(source, dest))?));
+    }
+
+    let added = run_batch(&plan.add, mode, ToString::to_string, |params| {
+        client.add_record(params).map(|_| ())
+    });
+    // With --fail-fast, a failed add stops the copy before any removals.
+    let removed = (added.failed == 0 || mode == ErrorMode::ContinueOnError).then(|| {
+        run_batch(&plan.remove, mode, ToString::to_string, |r| {
+            client.remove_record(dest, &r.id)
+        })
+    });
+    let text = to_json(&serde_json::json!({
+        "source": source,
+        "destination": dest,
+        "skipped_system": plan.skipped_system,
+        "skipped_existing": plan.skipped_existing,
+        "added": added,
+        "removed": removed,
+    }))?;
+
+    let check = added
+        .check()
+        .and_then(|()| removed.map_or(Ok(()), |removed| removed.check()));
+    Ok(CommandOutput::checked(text, check))
+}
+
+/// Run the dns replace command.
+///
+/// Reconciles `domain` with the records in `path` (a `dns list` style JSON
+/// array, or `-` for stdin): unchanged records are kept, records whose name
+/// and type survive are edited in place, and the rest are added or removed.
+/// Adds run first and removals last, so a name being changed keeps an answer
+/// throughout. Records Njalla manages itself are left alone on both sides.
+/// With `floor_ttl`, lower TTLs in the file are raised to it, each noted on
+/// stderr. With `dry_run`, only the plan is printed.
+pub fn run_replace(
+    domain: &str,
+    path: &Path,
+    dry_run: bool,
+    floor_ttl: Option<i32>,
+    mode: ErrorMode,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let desired = parse_records_json(domain, &read_json_input(path, "records")?)?;
+    let client = NjallaClient::new(options)?;
+
+    let plan = plan_replace(
+        &client.refresh_records(domain)?,
+        &desired,
+        domain,
+        floor_ttl,
+    );
+    for raised in &plan.raised_ttl {
+        eprintln!("Note: raised TTL of {raised}");
+    }
+    if dry_run {
+        return Ok(CommandOutput::Text(to_json(&plan.summary(domain))?));
+    }
+
+    let proceed =
+        |summary: &BatchSummary| summary.failed == 0 || mode == ErrorMode::ContinueOnError;
+    let added = run_batch(&plan.add, mode, ToString::to_string, |params| {
+        client.add_record(params).map(|_| ())
+    });
+    // With --fail-fast, a failed step stops the steps after it.
+    let edited = proceed(&added).then(|| {
+        run_batch(&plan.edit, mode, edit_label, |(record, params)| {
+            client
+                .edit_record(&edit_params(&record.id, params))
+                .map(|_| ())
+        })
+    });
+    let removed = edited.as_ref().filter(|edited| proceed(edited)).map(|_| {
+        run_batch(&plan.remove, mode, ToString::to_string, |record| {
+            client.remove_record(domain, &record.id)
+        })
+    });
+    let text = to_json(&serde_json::json!({
+        "domain": domain,
+        "unchanged": plan.unchanged,
+        "skipped_system": plan.skipped_system,
+        "added": added,
+        "edited": edited,
+        "removed": removed,
+    }))?;
+
+    let check = [Some(&added), edited.as_ref(), removed.as_ref()]
+        .into_iter()
+        .flatten()
+        .try_for_each(BatchSummary::check);
+    Ok(CommandOutput::checked(text, check))
+}
+
+/// Changes that turn a domain's live records into the desired ones.
+#[derive(Debug, Default)]
+struct ReplacePlan {
+    add: Vec<AddRecordParams>,
+    /// Live records paired with what they become.
+    edit: Vec<(Record, AddRecordParams)>,
+    remove: Vec<Record>,
+    unchanged: usize,
+    skipped_system: usize,
+    /// Records whose TTL was raised to the floor, as `label: old -> new`.
+    raised_ttl: Vec<String>,
+}
+
+impl ReplacePlan {
+    /// Dry-run description of the plan.
+    fn summary(&self, domain: &str) -> serde_json::Value {
+        serde_json::json!({
+            "dry_run": true,
+            "domain": domain,
+            "add": self.add.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "edit": self.edit.iter().map(edit_label).collect::<Vec<_>>(),
+            "remove": self.remove.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "unchanged": self.unchanged,
+            "skipped_system": self.skipped_system,
+        })
+    }
+}
+
+/// Work out the fewest operations that turn `live` into `desired`.
+///
+/// System records are skipped on both sides; `skipped_system` counts the
+/// live ones left in place. Each desired record first claims an identical live record (same fields,
+/// and the same TTL if it sets one). Left-over desired records then take a
+/// left-over live record with the same name and type to edit, or are added.
+/// Live records nobody claimed are removed. Desired TTLs below `floor_ttl`
+/// are raised to it before matching.
+fn plan_replace(
+    live: &[Record],
+    desired: &[Record],
+    domain: &str,
+    floor_ttl: Option<i32>,
+) -> ReplacePlan {
+    let mut plan = ReplacePlan::default();
+    let (system, mut live): (Vec<&Record>, Vec<&Record>) =
+        live.iter().partition(|r| is_system_record(r, domain));
+    plan.skipped_system = system.len();
+
+    let mut pending = Vec::new();
+    for record in desired.iter().filter(|r| !is_system_record(r, domain)) {
+        // Rebasing a domain onto itself keeps every field as it is.
+        let mut params = copy_params(record, domain, domain);
+        if let Some(old) = floor_ttl.and_then(|floor| raise_ttl(&mut params, floor)) {
+            let new = params.ttl.unwrap_or_default();
+            plan.raised_ttl.push(format!("{params}: {old} -> {new}"));
+        }
+        let same = live.iter().position(|r| {
+            is_equivalent(r, &params) && params.ttl.is_none_or(|ttl| r.ttl == Some(ttl))
+        });
+        match same {
+            Some(index) => {
+                live.remove(index);
+                plan.unchanged += 1;
+            }
+            None => pending.push(params),
+        }
+    }
+
+    for params in pending {
+        let slot = live
+            .iter()
+            .position(|r| r.name == params.name && r.record_type == params.record_type);
+        match slot {
+            Some(index) => plan.edit.push((live.remove(index).clone(), params)),
+            None => plan.add.push(params),
+        }
+    }
+
+    plan.remove = live.into_iter().cloned().collect();
+    plan
+}
+
+/// Raise a TTL below `floor` to it, returning the TTL it replaced.
+///
+/// A record without a TTL gets the API default, which is never raised.
+fn raise_ttl(params: &mut AddRecordParams, floor: i32) -> Option<i32> {
+    let old = params.ttl.filter(|&ttl| ttl < floor)?;
+    params.ttl = Some(floor);
+    Some(old)
+}
+
+/// Parameters that set every field of an edited record to `params`.
+fn edit_params(id: &str, params: &AddRecordParams) -> EditRecordParams {
+    EditRecordParams {
+        domain: params.domain.clone(),
+        id: id.to_string(),
+        name: Some(params.name.clone()),
+        content: params.content.clone(),
+        ttl: params.ttl,
+        priority: params.priority,
+        weight: params.weight,
+        port: params.port,
+        target: params.target.clone(),
+        value: params.value.clone(),
+        ssh_algorithm: params.ssh_algorithm,
+        ssh_type: params.ssh_type,
+    }
+}
+
+/// `old => new`, with both sides labelled like every other planned record.
+fn edit_label((record, params): &(Record, AddRecordParams)) -> String {
+    format!("{record} => {params}")
+}
+
+/// Records to add to and remove from the destination of a copy.
+#[derive(Debug, Default)]
+struct CopyPlan {
+    add: Vec<AddRecordParams>,
+    remove: Vec<Record>,
+    skipped_system: usize,
+    skipped_existing: usize,
+}
+
+impl CopyPlan {
+    /// Dry-run description of the plan.
+    fn summary(&self, source: &str, dest: &str) -> serde_json::Value {
+        serde_json::json!({
+            "dry_run": true,
+            "source": source,
+            "destination": dest,
+            "add": self.add.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "remove": self.remove.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "skipped_system": self.skipped_system,
+            "skipped_existing": self.skipped_existing,
+        })
+    }
+}
+
+/// Work out which records to copy from `source_records` onto `dest`.
+fn plan_copy(
+    source_records: &[Record],
+    dest_records: &[Record],
+    source: &str,
+    dest: &str,
+    replace: bool,
+) -> CopyPlan {
+    let mut plan = CopyPlan::default();
+
+    for record in source_records {
+        if is_system_record(record, source) {
+            plan.skipped_system += 1;
+            continue;
+        }
+        let params = copy_params(record, source, dest);
+        if dest_records.iter().any(|r| is_equivalent(r, &params)) {
+            plan.skipped_existing += 1;
+        } else {
+            plan.add.push(params);
+        }
+    }
+
+    if replace {
+        let wanted: Vec<AddRecordParams> = source_records
+            .iter()
+            .filter(|r| !is_system_record(r, source))
+            .map(|r| copy_params(r, source, dest))
+            .collect();
+        plan.remove = dest_records
+            .iter()
+            .filter(|r| !is_system_record(r, dest) && !wanted.iter().any(|p| is_equivalent(r, p)))
+            .cloned()
+            .collect();
+    }
+
+    plan
+}
+
+/// Parameters recreating `record` on `dest`, with hostnames under `source`
+/// rewritten to `dest`.
+fn copy_params(record: &Record, source: &str, dest: &str) -> AddRecordParams {
+    let rewrites_content = matches!(
+        record.record_type,
+        RecordType::Aname
+            | RecordType::Cname
+            | RecordType::Mx
+            | RecordType::Ns
+            | RecordType::Ptr
+            | RecordType::Srv
+    );
+    let rebase = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| rebase_hostname(v, source, dest).unwrap_or_else(|| v.to_string()))
+    };
+
+    AddRecordParams {
+        domain: dest.to_string(),
+        record_type: record.record_type,
+        name: record.name.clone(),
+        content: if rewrites_content {
+            rebase(&record.content)
+        } else {
+            record.content.clone()
+        },
+        ttl: record.ttl,
+        priority: record.priority,
+        weight: record.weight,
+        port: record.port,
+        target: rebase(&record.target),
+        value: record.value.clone(),
+        ssh_algorithm: record.ssh_algorithm,
+        ssh_type: record.ssh_type,
+    }
+}
+
+/// Rewrite `value` if it is `source` or a name under it; keeps a trailing dot.
+fn rebase_hostname(value: &str, source: &str, dest: &str) -> Option<String> {
+    let (name, dot) = match value.strip_suffix('.') {
+        Some(name) => (name, "."),
+        None => (value, ""),
+    };
+    let source = source.trim_end_matches('.').to_ascii_lowercase();
+    let dest = dest.trim_end_matches('.');
+    let lower = name.to_ascii_lowercase();
+
+    if lower == source {
+        return Some(format!("{dest}{dot}"));
+    }
+    // ASCII lowercasing keeps byte offsets, so the prefix length carries over.
+    let prefix_len = lower.strip_suffix(&format!(".{source}"))?.len();
+    Some(format!("{}.{dest}{dot}", &name[..prefix_len]))
+}
+
+/// Whether an existing record already matches the parameters.
+///
+/// Names compare case-insensitively, and content and targets after
+/// [`normalize_content`], so `Example.com.` matches `example.com`.
+fn is_equivalent(record: &Record, params: &AddRecordParams) -> bool {
+    let normalized = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| normalize_content(record.record_type, v))
+    };
+
+    record.name.eq_ignore_ascii_case(&params.name)
+        && record.record_type == params.record_type
+        && normalized(&record.content) == normalized(&params.content)
+        && record.priority == params.priority
+        && record.weight == params.weight
+        && record.port == params.port
+        && normalized(&record.target) == normalized(&params.target)
+        && record.value == params.value
+}
+
+/// Read record content from a file for `--content-file`.
+///
+/// Only the trailing newline is removed, except for TXT records laid out as
+/// zone-file quoted chunks or a wrapped base64 key; see
+/// [`join_content_lines`]. `record_type` is `None` for `dns edit`, which does
+/// not know the record's type, so those two layouts are joined there too.
+/// The value is sent unsplit; TXT values over 255 bytes are not chunked
+/// client-side.
+pub fn read_content_file(path: &Path, record_type: Option<RecordType>) -> Result<String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| NjallaError::Input {
+        message: format!("Failed to read content file {}: {e}", path.display()),
+    })?;
+
+    let txt = record_type.is_none_or(|record_type| record_type == RecordType::Txt);
+    let content = join_content_lines(&contents, txt);
+    if content.trim().is_empty() {
+        return Err(NjallaError::Input {
+            message: format!("Content file {} is empty", path.display()),
+        });
+    }
+
+    Ok(content)
+}
+
+/// Read a whole record for `dns edit --json`, from a file or `-` for stdin.
+///
+/// The JSON is one record object as printed by `dns list`, `dns add` or
+/// `dns edit`; extra fields such as `ttl_default` are ignored. See
+/// [`parse_record_json`] for what is checked.
+pub fn read_record_json(domain: &str, path: &Path) -> Result<EditRecordParams> {
+    parse_record_json(domain, &read_json_input(path, "record")?)
+}
+
+/// Read JSON input from a file, or from stdin for `-`.
+fn read_json_input(path: &Path, what: &str) -> Result<String> {
+    if path.as_os_str() == "-" {
+        std::io::read_to_string(std::io::stdin().lock())
+    } else {
+        std::fs::read_to_string(path)
+    }
+    .map_err(|e| NjallaError::Input {
+        message: format!("Failed to read {what} JSON from {}: {e}", path.display()),
+    })
+}
+
+/// Parse the desired records for `dns replace`.
+///
+/// Takes an array of record objects as printed by `dns list`, bare or inside
+/// its `--with-schema` wrapper. An `id` is optional, names are normalized to
+/// `domain`, and each record's fields must fit its type as for `dns edit
+/// --json`.
+fn parse_records_json(domain: &str, json: &str) -> Result<Vec<Record>> {
+    let invalid = |e: serde_json::Error| NjallaError::Input {
+        message: format!("Invalid records JSON: {e}"),
+    };
+    let mut value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    if value.get("_schema_version").is_some() {
+        value = value["data"].take();
+    }
+    let serde_json::Value::Array(entries) = value else {
+        return Err(NjallaError::Input {
+            message: "Records JSON must be an array of record objects".to_string(),
+        });
+    };
 
-    client.remove_record(domain, id)?;
-    println!("{}", serde_json::json!({"status": "removed", "id": id}));
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut entry)| {
+            if let Some(object) = entry.as_object_mut() {
+                object.entry("id").or_insert_with(|| "".into());
+            }
+            let mut record: Record =
+                serde_json::from_value(entry).map_err(|e| NjallaError::Input {
+                    message: format!("Invalid record at index {index}: {e}"),
+                })?;
+            check_record_fields(&record).map_err(|e| match e {
+                NjallaError::Input { message } => NjallaError::Input {
+                    message: format!("Record at index {index}: {message}"),
+                },
+                e => e,
+            })?;
+            record.name = normalize_record_name(&record.name, domain);
+            Ok(record)
+        })
+        .collect()
+}
+
+/// Parse a record object into edit parameters that set all of its fields.
+///
+/// The `id` must be present, and the record must set the fields its type
+/// needs (`content` for A, `prio` too for MX, ...) and none that
+/// belong to other types.
+fn parse_record_json(domain: &str, json: &str) -> Result<EditRecordParams> {
+    let invalid = |e: serde_json::Error| NjallaError::Input {
+        message: format!("Invalid record JSON: {e}"),
+    };
+    let value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    if !value.is_object() {
+        return Err(NjallaError::Input {
+            message: "Record JSON must be a single record object".to_string(),
+        });
+    }
+    if value
+        .get("id")
+        .and_then(serde_json::Value::as_str)
+        .is_none_or(|id| id.trim().is_empty())
+    {
+        return Err(NjallaError::Input {
+            message: "Record JSON has no \"id\"; take the record from dns list".to_string(),
+        });
+    }
+
+    let record: Record = serde_json::from_value(value).map_err(invalid)?;
+    check_record_fields(&record)?;
+    Ok(EditRecordParams::from_record(domain, record))
+}
+
+/// A type-specific record field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeField {
+    Content,
+    Priority,
+    Weight,
+    Port,
+    Target,
+    Value,
+    SshAlgorithm,
+    SshType,
+}
+
+impl TypeField {
+    /// Every field, in the order record checks visit them.
+    const ALL: [Self; 8] = [
+        Self::Content,
+        Self::Priority,
+        Self::Weight,
+        Self::Port,
+        Self::Target,
+        Self::Value,
+        Self::SshAlgorithm,
+        Self::SshType,
+    ];
+
+    /// The field's name in record JSON.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Content => "content",
+            Self::Priority => "prio",
+            Self::Weight => "weight",
+            Self::Port => "port",
+            Self::Target => "target",
+            Self::Value => "value",
+            Self::SshAlgorithm => "ssh_algorithm",
+            Self::SshType => "ssh_type",
+        }
+    }
+
+    /// The `dns add` flag that sets the field.
+    fn flag(self) -> &'static str {
+        match self {
+            Self::Content => "--content",
+            Self::Priority => "--priority",
+            Self::Weight => "--weight",
+            Self::Port => "--port",
+            Self::Target => "--target",
+            Self::Value => "--value",
+            Self::SshAlgorithm => "--ssh-algorithm",
+            Self::SshType => "--ssh-type",
+        }
+    }
+
+    fn is_set_in_record(self, record: &Record) -> bool {
+        match self {
+            Self::Content => record.content.is_some(),
+            Self::Priority => record.priority.is_some(),
+            Self::Weight => record.weight.is_some(),
+            Self::Port => record.port.is_some(),
+            Self::Target => record.target.is_some(),
+            Self::Value => record.value.is_some(),
+            Self::SshAlgorithm => record.ssh_algorithm.is_some(),
+            Self::SshType => record.ssh_type.is_some(),
+        }
+    }
+
+    fn is_set_in_params(self, params: &AddRecordParams) -> bool {
+        match self {
+            Self::Content => params.content.is_some(),
+            Self::Priority => params.priority.is_some(),
+            Self::Weight => params.weight.is_some(),
+            Self::Port => params.port.is_some(),
+            Self::Target => params.target.is_some(),
+            Self::Value => params.value.is_some(),
+            Self::SshAlgorithm => params.ssh_algorithm.is_some(),
+            Self::SshType => params.ssh_type.is_some(),
+        }
+    }
+}
+
+/// The fields a record type needs, and the other fields it may set besides
+/// `content`, which every type may set. Types not listed are left to the API.
+///
+/// Both `dns add` and record JSON are checked against this one table.
+fn type_fields(record_type: RecordType) -> (&'static [TypeField], &'static [TypeField]) {
+    use TypeField::{Content, Port, Priority, SshAlgorithm, SshType, Target, Value, Weight};
+    match record_type {
+        RecordType::A | RecordType::Aaaa | RecordType::Cname | RecordType::Txt => (&[Content], &[]),
+        RecordType::Mx => (&[Content, Priority], &[Priority]),
+        RecordType::Srv => (
+            &[Content, Priority, Weight, Port],
+            &[Priority, Weight, Port],
+        ),
+        RecordType::Sshfp => (&[SshAlgorithm, SshType, Content], &[SshAlgorithm, SshType]),
+        RecordType::Https | RecordType::Svcb => (&[Priority, Target], &[Priority, Target, Value]),
+        _ => (&[], &[]),
+    }
+}
+
+/// Check that `dns add` has every flag its record type needs, naming all the
+/// missing ones at once rather than leaving the API to reject them one by one.
+fn check_add_flags(params: &AddRecordParams) -> Result<()> {
+    let (required, _) = type_fields(params.record_type);
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|field| !field.is_set_in_params(params))
+        .map(|field| field.flag())
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(NjallaError::Input {
+        message: format!("{} records need {}", params.record_type, missing.join(", ")),
+    })
+}
+
+/// Check that a record sets the type-specific fields its type needs and no
+/// others.
+fn check_record_fields(record: &Record) -> Result<()> {
+    let (required, allowed) = type_fields(record.record_type);
+
+    for field in TypeField::ALL {
+        let set = field.is_set_in_record(record);
+        if set && field != TypeField::Content && !allowed.contains(&field) {
+            return Err(NjallaError::Input {
+                message: format!(
+                    "{} records have no \"{}\" field",
+                    record.record_type,
+                    field.name()
+                ),
+            });
+        }
+        if !set && required.contains(&field) {
+            return Err(NjallaError::Input {
+                message: format!("{} records need \"{}\"", record.record_type, field.name()),
+            });
+        }
+    }
 
     Ok(())
 }
+
+/// Turn the text of a content file into a single value.
+///
+/// The trailing newline is removed. For `txt` content, two layouts are also
+/// joined without separators: lines that are each one quoted string
+/// (zone-file chunks, which lose their quotes), and a first line followed by
+/// lines that together are one valid base64 value (a wrapped DKIM key,
+/// optionally starting with its `p=` tag). Anything else keeps its line
+/// breaks, so text wrapped between two words is never glued together.
+fn join_content_lines(contents: &str, txt: bool) -> String {
+    let value = contents.trim_end_matches(['\r', '\n']);
+    if !txt {
+        return value.to_string();
+    }
+    let lines: Vec<&str> = value
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let quoted: Option<Vec<&str>> = lines
+        .iter()
+        .map(|line| {
+            line.strip_prefix('"')
+                .and_then(|l| l.strip_suffix('"'))
+                .filter(|inner| !inner.contains('"'))
+        })
+        .collect();
+    if let Some(chunks) = quoted.filter(|chunks| !chunks.is_empty()) {
+        return chunks.concat();
+    }
+
+    if lines.len() > 1 && is_base64(lines[1..].concat().trim_start_matches("p=")) {
+        return lines.concat();
+    }
+    value.to_string()
+}
+
+/// Whether `text` is valid base64: alphabet characters, at most two `=` of
+/// padding at the end, and a length that is a multiple of 4.
+fn is_base64(text: &str) -> bool {
+    let data = text.trim_end_matches('=');
+    !data.is_empty()
+        && text.len() - data.len() <= 2
+        && text.len().is_multiple_of(4)
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/'))
+}
+
+/// Warnings for fields the API stored differently from what was requested.
+///
+/// Only fields set in the request are compared, so server-side defaults
+/// (e.g. a TTL when none was given) are not reported. Content is compared
+/// through [`normalize_content`], so a hostname the API returns with a
+/// trailing dot or in another case still matches.
+fn verify_created(params: &AddRecordParams, record: &Record) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut check = |field: &str, requested: Option<String>, returned: Option<String>| {
+        let Some(requested) = requested else {
+            return;
+        };
+        let same = match (field, returned.as_deref()) {
+            ("content", Some(returned)) => {
+                normalize_content(params.record_type, returned)
+                    == normalize_content(params.record_type, &requested)
+            }
+            (_, returned) => returned == Some(requested.as_str()),
+        };
+        if !same {
+            warnings.push(format!(
+                "requested {field} '{requested}' but the API returned '{}'",
+                returned.unwrap_or_default()
+            ));
+        }
+    };
+    let text = |v: Option<i32>| v.map(|v| v.to_string());
+
+    check("name", Some(params.name.clone()), Some(record.name.clone()));
+    check(
+        "type",
+        Some(params.record_type.to_string()),
+        Some(record.record_type.to_string()),
+    );
+    check("content", params.content.clone(), record.content.clone());
+    check("ttl", text(params.ttl), text(record.ttl));
+    check("priority", text(params.priority), text(record.priority));
+    check("weight", text(params.weight), text(record.weight));
+    check("port", text(params.port), text(record.port));
+    check("target", params.target.clone(), record.target.clone());
+    check("value", params.value.clone(), record.value.clone());
+    warnings
+}
+
+/// Whether `dns add` lists the zone for [`add_warnings`].
+///
+/// Listing costs a request, so it is only made for an ANAME, an A/AAAA
+/// wildcard, or an A/AAAA add with `--replace-existing`, which lists the zone
+/// anyway (the client caches it, so it is fetched once). A plain A/AAAA add
+/// leaves the check for a coexisting ANAME to the ANAME's own add.
+fn add_checks_listing(params: &AddRecordParams, replace_existing: bool) -> bool {
+    match params.record_type {
+        RecordType::Aname => true,
+        RecordType::A | RecordType::Aaaa => replace_existing || is_wildcard(&params.name),
+        _ => false,
+    }
+}
+
+/// Warnings about how a new address record interacts with existing ones.
+fn add_warnings(params: &AddRecordParams, existing: &[Record]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let is_address = matches!(params.record_type, RecordType::A | RecordType::Aaaa);
+
+    if is_address && is_wildcard(&params.name) {
+        let shadowed = names_excluded_from_wildcard(&params.name, existing);
+        if !shadowed.is_empty() {
+            warnings.push(format!(
+                "wildcard '{}' will not apply to names with their own records: {}",
+                params.name,
+                shadowed.join(", ")
+            ));
+        }
+    }
+
+    let conflicting: Vec<&Record> = existing
+        .iter()
+        .filter(|r| r.name == params.name)
+        .filter(|r| {
+            if params.record_type == RecordType::Aname {
+                matches!(r.record_type, RecordType::A | RecordType::Aaaa)
+            } else {
+                is_address && r.record_type == RecordType::Aname
+            }
+        })
+        .collect();
+    if !conflicting.is_empty() {
+        let types: Vec<String> = conflicting
+            .iter()
+            .map(|r| r.record_type.to_string())
+            .collect();
+        warnings.push(format!(
+            "'{}' will have both ANAME and A/AAAA records ({} {}); the ANAME already \
+             serves the target's addresses, so resolvers may get mixed answers",
+            params.name,
+            params.record_type,
+            types.join(", ")
+        ));
+    }
+
+    warnings
+}
+
+/// Check that an ANAME target is a hostname rather than an address.
+fn validate_aname_target(content: Option<&str>) -> Result<()> {
+    let Some(target) = content else {
+        return Err(NjallaError::Input {
+            message: "ANAME records need --content with a target hostname".to_string(),
+        });
+    };
+
+    if target.parse::<std::net::IpAddr>().is_ok() {
+        return Err(NjallaError::Input {
+            message: format!("ANAME target '{target}' is an IP address; use an A/AAAA record"),
+        });
+    }
+    if !is_valid_hostname(target) {
+        return Err(NjallaError::Input {
+            message: format!("ANAME target '{target}' is not a valid hostname"),
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether `name` is a syntactically valid hostname (trailing dot allowed).
+fn is_valid_hostname(name: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if name.is_empty() || name.len() > 253 {
+        return false;
+    }
+
+    name.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+/// Build the wildcard record name for a subtree.
+///
+/// `@` or an empty subtree yields `*`; `api` yields `*.api`.
+pub fn wildcard_name(subtree: &str) -> String {
+    let subtree = subtree.trim().trim_end_matches('.');
+    let subtree = subtree.strip_prefix("*.").unwrap_or(subtree);
+    if subtree.is_empty() || subtree == "@" || subtree == "*" {
+        "*".to_string()
+    } else {
+        format!("*.{subtree}")
+    }
+}
+
+/// Name for a `dns add` without `--name` or `--wildcard`.
+///
+/// A, AAAA, MX and TXT records most often sit at the apex, so they default
+/// to `@`. Other types need an explicit name; a CNAME at the apex in
+/// particular would shadow every other record there.
+///
+/// # Errors
+///
+/// Returns `NjallaError::Input` for types without a default.
+pub fn default_record_name(record_type: RecordType) -> Result<&'static str> {
+    match record_type {
+        RecordType::A | RecordType::Aaaa | RecordType::Mx | RecordType::Txt => Ok("@"),
+        _ => Err(NjallaError::Input {
+            message: format!("{record_type} records need --name or --wildcard"),
+        }),
+    }
+}
+
+/// Expand a relative hostname target to a name inside `domain` for `--fqdn`.
+///
+/// Applies zone-file rules: a target with a trailing dot is already fully
+/// qualified and `@` means the apex; anything else gets `.domain` appended.
+/// Targets already ending in `domain` are left alone rather than doubled.
+/// Returns `None` when the target is unchanged.
+pub fn qualify_target(target: &str, domain: &str) -> Option<String> {
+    let domain = domain.trim_end_matches('.');
+    if target.is_empty() || target.ends_with('.') {
+        return None;
+    }
+    if target == "@" {
+        return Some(domain.to_string());
+    }
+    if target == domain || target.ends_with(&format!(".{domain}")) {
+        return None;
+    }
+    Some(format!("{target}.{domain}"))
+}
+
+/// Apply `--fqdn` to the content of CNAME, MX, NS and SRV records.
+///
+/// Every expansion is reported on stderr so nothing is appended silently.
+pub fn qualify_targets(params: &mut AddRecordParams) {
+    if !matches!(
+        params.record_type,
+        RecordType::Cname | RecordType::Mx | RecordType::Ns | RecordType::Srv
+    ) {
+        return;
+    }
+    let Some(content) = params.content.as_deref() else {
+        return;
+    };
+    if let Some(qualified) = qualify_target(content, &params.domain) {
+        eprintln!("Note: --fqdn expanded target '{content}' to '{qualified}'");
+        params.content = Some(qualified);
+    }
+}
+
+/// Whether a record name is a wildcard (`*` or `*.sub`).
+fn is_wildcard(name: &str) -> bool {
+    name == "*" || name.starts_with("*.")
+}
+
+/// Names below a wildcard's subtree that already have records.
+///
+/// A wildcard only answers for names that don't exist, so these names
+/// will keep resolving to their own records instead of the wildcard.
+fn names_excluded_from_wildcard(wildcard: &str, records: &[Record]) -> Vec<String> {
+    let parent = wildcard.strip_prefix("*.").unwrap_or("");
+
+    let mut names: Vec<String> = records
+        .iter()
+        .map(|r| r.name.as_str())
+        .filter(|name| !is_wildcard(name) && *name != "@")
+        .filter(|name| {
+            parent.is_empty()
+                || name
+                    .strip_suffix(parent)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+
+    fn record(name: &str, record_type: RecordType) -> Record {
+        Record {
+            id: format!("id-{name}"),
+            name: name.to_string(),
+            record_type,
+            content: Some("192.0.2.1".to_string()),
+            ttl: Some(3600),
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        }
+    }
+
+    #[test]
+    fn content_file_joins_multiline_dkim_key() {
+        let path = std::env::temp_dir().join(format!("njalla-dkim-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "v=DKIM1; k=rsa;\n  p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8A\n  MIIBCgKCAQEAwq==\n\n",
+        )
+        .unwrap();
+
+        let content = read_content_file(&path, Some(RecordType::Txt)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            content,
+            "v=DKIM1; k=rsa;p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAwq=="
+        );
+    }
+
+    #[test]
+    fn content_lines_strip_zone_file_quotes() {
+        let contents = "\"v=DKIM1; k=rsa; \"\n\"p=ABC\"\n";
+        assert_eq!(join_content_lines(contents, true), "v=DKIM1; k=rsa; p=ABC");
+        assert_eq!(join_content_lines("\"a\" \"b\"\n", true), "\"a\" \"b\"");
+    }
+
+    #[test]
+    fn content_lines_keep_breaks_between_words() {
+        assert_eq!(
+            join_content_lines("v=spf1 a\n -all\n", true),
+            "v=spf1 a\n -all"
+        );
+        assert_eq!(
+            join_content_lines("Contact us at\nsupport\nteam\n", true),
+            "Contact us at\nsupport\nteam"
+        );
+        assert_eq!(
+            join_content_lines("google-site-verification\nabcd\nx==y\n", true),
+            "google-site-verification\nabcd\nx==y"
+        );
+        assert_eq!(
+            join_content_lines("\"quoted\"\n\"chunks\"\n", false),
+            "\"quoted\"\n\"chunks\""
+        );
+    }
+
+    #[test]
+    fn content_file_missing_is_input_error() {
+        let path = std::env::temp_dir().join("njalla-missing-content-file.txt");
+        assert!(matches!(
+            read_content_file(&path, None),
+            Err(NjallaError::Input { .. })
+        ));
+    }
+
+    fn add_params(name: &str, record_type: RecordType, content: &str) -> AddRecordParams {
+        AddRecordParams {
+            domain: "example.com".to_string(),
+            record_type,
+            name: name.to_string(),
+            content: Some(content.to_string()),
+            ttl: None,
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        }
+    }
+
+    fn missing_add_flags(params: &AddRecordParams) -> Option<String> {
+        match check_add_flags(params) {
+            Ok(()) => None,
+            Err(NjallaError::Input { message }) => Some(message),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn add_requires_content_for_address_alias_and_text_records() {
+        for record_type in [
+            RecordType::A,
+            RecordType::Aaaa,
+            RecordType::Cname,
+            RecordType::Txt,
+        ] {
+            let mut params = add_params("www", record_type, "x");
+            assert_eq!(missing_add_flags(&params), None);
+            params.content = None;
+            assert_eq!(
+                missing_add_flags(&params),
+                Some(format!("{record_type} records need --content"))
+            );
+        }
+    }
+
+    #[test]
+    fn add_lists_every_missing_mx_and_srv_flag() {
+        let mut mx = add_params("@", RecordType::Mx, "mail.example.com");
+        assert_eq!(
+            missing_add_flags(&mx).as_deref(),
+            Some("MX records need --priority")
+        );
+        mx.priority = Some(10);
+        assert_eq!(missing_add_flags(&mx), None);
+
+        let mut srv = add_params("_sip._tcp", RecordType::Srv, "sip.example.com");
+        srv.content = None;
+        srv.weight = Some(5);
+        assert_eq!(
+            missing_add_flags(&srv).as_deref(),
+            Some("SRV records need --content, --priority, --port")
+        );
+        srv.content = Some("sip.example.com".to_string());
+        srv.priority = Some(10);
+        srv.port = Some(5060);
+        assert_eq!(missing_add_flags(&srv), None);
+    }
+
+    #[test]
+    fn add_requires_sshfp_and_service_binding_fields() {
+        let mut sshfp = add_params("host", RecordType::Sshfp, "abc123");
+        assert_eq!(
+            missing_add_flags(&sshfp).as_deref(),
+            Some("SSHFP records need --ssh-algorithm, --ssh-type")
+        );
+        sshfp.ssh_algorithm = Some(4);
+        sshfp.ssh_type = Some(2);
+        assert_eq!(missing_add_flags(&sshfp), None);
+
+        for record_type in [RecordType::Https, RecordType::Svcb] {
+            let mut params = add_params("@", record_type, "");
+            params.content = None;
+            assert_eq!(
+                missing_add_flags(&params),
+                Some(format!("{record_type} records need --priority, --target"))
+            );
+            params.priority = Some(1);
+            params.target = Some(".".to_string());
+            assert_eq!(missing_add_flags(&params), None);
+        }
+    }
+
+    #[test]
+    fn add_leaves_other_types_to_the_api() {
+        let mut params = add_params("home", RecordType::Dynamic, "");
+        params.content = None;
+        assert_eq!(missing_add_flags(&params), None);
+    }
+
+    #[test]
+    fn qualify_target_appends_domain_to_relative_names() {
+        assert_eq!(
+            qualify_target("blog", "example.com").as_deref(),
+            Some("blog.example.com")
+        );
+        assert_eq!(
+            qualify_target("@", "example.com").as_deref(),
+            Some("example.com")
+        );
+
+        let mut params = add_params("www", RecordType::Cname, "web");
+        qualify_targets(&mut params);
+        assert_eq!(params.content.as_deref(), Some("web.example.com"));
+
+        let mut params = add_params("www", RecordType::Txt, "web");
+        qualify_targets(&mut params);
+        assert_eq!(params.content.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn qualify_target_leaves_fqdns_alone() {
+        assert_eq!(qualify_target("ghs.google.com.", "example.com"), None);
+        assert_eq!(qualify_target("mail.example.com", "example.com"), None);
+        assert_eq!(qualify_target("example.com", "example.com."), None);
+    }
+
+    #[test]
+    fn verify_created_warns_on_coerced_ttl() {
+        let mut params = add_params("www", RecordType::Txt, "v=spf1 -all");
+        params.ttl = Some(300);
+        let mut created = record("www", RecordType::Txt);
+        created.content = Some("v=spf1 -all".to_string());
+        created.ttl = Some(3600);
+
+        let warnings = verify_created(&params, &created);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("ttl '300'"));
+        assert!(warnings[0].contains("'3600'"));
+
+        created.ttl = Some(300);
+        assert!(verify_created(&params, &created).is_empty());
+    }
+
+    #[test]
+    fn verify_created_compares_hostname_content_normalized() {
+        let params = add_params("www", RecordType::Cname, "Target.example.com");
+        let mut created = record("www", RecordType::Cname);
+        created.content = Some("target.example.com.".to_string());
+        assert!(verify_created(&params, &created).is_empty());
+
+        created.content = Some("other.example.com.".to_string());
+        assert_eq!(verify_created(&params, &created).len(), 1);
+    }
+
+    #[test]
+    fn aname_target_must_be_hostname() {
+        assert!(validate_aname_target(Some("lb.example.net")).is_ok());
+        assert!(validate_aname_target(Some("lb.example.net.")).is_ok());
+        assert!(matches!(
+            validate_aname_target(Some("192.0.2.1")),
+            Err(NjallaError::Input { .. })
+        ));
+        assert!(validate_aname_target(Some("bad host")).is_err());
+        assert!(validate_aname_target(Some("-bad.example")).is_err());
+        assert!(validate_aname_target(None).is_err());
+    }
+
+    #[test]
+    fn aname_coexisting_with_address_records_warns() {
+        let existing = vec![
+            record("@", RecordType::A),
+            record("@", RecordType::Mx),
+            record("www", RecordType::Aaaa),
+        ];
+
+        let warnings = add_warnings(
+            &add_params("@", RecordType::Aname, "lb.example.net"),
+            &existing,
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("ANAME and A/AAAA"));
+
+        let warnings = add_warnings(
+            &add_params("mail", RecordType::Aname, "lb.example.net"),
+            &existing,
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn address_record_next_to_aname_warns() {
+        let existing = vec![record("@", RecordType::Aname)];
+        let warnings = add_warnings(&add_params("@", RecordType::A, "192.0.2.1"), &existing);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn add_lists_the_zone_only_when_a_check_uses_it() {
+        let plain = add_params("www", RecordType::A, "192.0.2.1");
+        assert!(!add_checks_listing(&plain, false));
+        assert!(add_checks_listing(&plain, true));
+        assert!(add_checks_listing(
+            &add_params("*", RecordType::Aaaa, "2001:db8::1"),
+            false
+        ));
+        assert!(add_checks_listing(
+            &add_params("@", RecordType::Aname, "lb.example.net"),
+            false
+        ));
+        assert!(!add_checks_listing(
+            &add_params("@", RecordType::Txt, "v=spf1 -all"),
+            true
+        ));
+    }
+
+    #[test]
+    fn wildcard_name_derivation() {
+        assert_eq!(wildcard_name("@"), "*");
+        assert_eq!(wildcard_name(""), "*");
+        assert_eq!(wildcard_name("api"), "*.api");
+        assert_eq!(wildcard_name("*.api"), "*.api");
+        assert_eq!(wildcard_name("eu.api."), "*.eu.api");
+    }
+
+    #[test]
+    fn default_record_name_is_apex_only_for_common_types() {
+        assert_eq!(default_record_name(RecordType::A).unwrap(), "@");
+        assert_eq!(default_record_name(RecordType::Mx).unwrap(), "@");
+        assert!(matches!(
+            default_record_name(RecordType::Cname),
+            Err(NjallaError::Input { message }) if message == "CNAME records need --name or --wildcard"
+        ));
+    }
+
+    #[test]
+    fn wildcard_detection() {
+        assert!(is_wildcard("*"));
+        assert!(is_wildcard("*.api"));
+        assert!(!is_wildcard("www"));
+        assert!(!is_wildcard("a*b"));
+    }
+
+    #[test]
+    fn zone_wildcard_excludes_all_named_records() {
+        let records = vec![
+            record("@", RecordType::A),
+            record("www", RecordType::A),
+            record("mail", RecordType::Mx),
+            record("www", RecordType::Aaaa),
+            record("*", RecordType::A),
+        ];
+        assert_eq!(
+            names_excluded_from_wildcard("*", &records),
+            vec!["mail".to_string(), "www".to_string()]
+        );
+    }
+
+    #[test]
+    fn subtree_wildcard_only_matches_its_subtree() {
+        let records = vec![
+            record("www", RecordType::A),
+            record("api", RecordType::A),
+            record("v1.api", RecordType::A),
+            record("legacy.v1.api", RecordType::A),
+            record("myapi", RecordType::A),
+        ];
+        assert_eq!(
+            names_excluded_from_wildcard("*.api", &records),
+            vec!["legacy.v1.api".to_string(), "v1.api".to_string()]
+        );
+    }
+
+    #[test]
+    fn ptr_params_name_the_address_inside_the_reverse_zone() {
+        let mut www = add_params("www", RecordType::A, "192.0.2.10");
+        www.ttl = Some(300);
+        let ptr = ptr_params(&www, "2.0.192.in-addr.arpa.").unwrap();
+        assert_eq!(ptr.domain, "2.0.192.in-addr.arpa");
+        assert_eq!(ptr.record_type, RecordType::Ptr);
+        assert_eq!(ptr.name, "10");
+        assert_eq!(ptr.content.as_deref(), Some("www.example.com"));
+        assert_eq!(ptr.ttl, Some(300));
+
+        let apex = add_params("@", RecordType::Aaaa, "2001:db8::1");
+        let ptr = ptr_params(&apex, "8.b.d.0.1.0.0.2.ip6.arpa").unwrap();
+        assert_eq!(ptr.name, format!("1{}", ".0".repeat(23)));
+        assert_eq!(ptr.content.as_deref(), Some("example.com"));
+
+        assert!(matches!(
+            ptr_params(&www, "51.198.in-addr.arpa"),
+            Err(NjallaError::Input { message }) if message.contains("not inside")
+        ));
+        assert!(ptr_params(&add_params("www", RecordType::Cname, "a.example"), "arpa").is_err());
+        assert!(ptr_params(&add_params("www", RecordType::A, "nope"), "arpa").is_err());
+    }
+
+    fn add_record_mock(record_type: &str, response: ResponseTemplate) -> Mock {
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "add-record",
+                "params": { "type": record_type }
+            })))
+            .respond_with(response)
+    }
+
+    fn ptr_companion_fixture(server: &MockServer, ptr_response: ResponseTemplate) -> Record {
+        let forward = serde_json::json!({
+            "id": "fwd", "name": "www", "type": "A", "content": "192.0.2.10"
+        });
+        RT.block_on(
+            add_record_mock(
+                "A",
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "result": forward })),
+            )
+            .expect(1)
+            .mount(server),
+        );
+        RT.block_on(add_record_mock("PTR", ptr_response).expect(1).mount(server));
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        client
+            .add_record(&add_params("www", RecordType::A, "192.0.2.10"))
+            .unwrap()
+    }
+
+    #[test]
+    fn with_ptr_creates_forward_record_then_ptr() {
+        let server = RT.block_on(MockServer::start());
+        let ptr = serde_json::json!({
+            "id": "rev", "name": "10", "type": "PTR", "content": "www.example.com"
+        });
+        let forward = ptr_companion_fixture(
+            &server,
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "result": ptr })),
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let params = ptr_params(
+            &add_params("www", RecordType::A, "192.0.2.10"),
+            "2.0.192.in-addr.arpa",
+        )
+        .unwrap();
+
+        let created = add_ptr_companion(&client, "example.com", &forward, &params, true).unwrap();
+
+        assert_eq!(created.id, "rev");
+        let requests = RT.block_on(server.received_requests()).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["params"]["domain"], "2.0.192.in-addr.arpa");
+        assert_eq!(body["params"]["name"], "10");
+        assert_eq!(body["params"]["content"], "www.example.com");
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn atomic_with_ptr_removes_forward_record_when_ptr_fails() {
+        let server = RT.block_on(MockServer::start());
+        let forward = ptr_companion_fixture(
+            &server,
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "error": { "message": "Domain not found" }
+            })),
+        );
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({
+                    "method": "remove-record",
+                    "params": { "domain": "example.com", "id": "fwd" }
+                })))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "result": {} })),
+                )
+                .expect(1)
+                .mount(&server),
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let params = ptr_params(
+            &add_params("www", RecordType::A, "192.0.2.10"),
+            "2.0.192.in-addr.arpa",
+        )
+        .unwrap();
+
+        let result = add_ptr_companion(&client, "example.com", &forward, &params, true);
+
+        assert!(
+            matches!(result, Err(NjallaError::Api { message }) if message == "Domain not found")
+        );
+        RT.block_on(server.verify());
+    }
+
+    /// Serve `records` from `list-records`, echoing `www` back from
+    /// `add-record` and `edit-record`, each expected `adds` and `edits` times.
+    fn replace_existing_server(records: &serde_json::Value, adds: u64, edits: u64) -> MockServer {
+        let server = RT.block_on(MockServer::start());
+        let respond = |method_name: &str, result: serde_json::Value| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": method_name }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": result })),
+                )
+        };
+        let www = serde_json::json!({
+            "id": "new", "name": "www", "type": "A", "content": "192.0.2.10"
+        });
+        RT.block_on(
+            respond("list-records", serde_json::json!({ "records": records })).mount(&server),
+        );
+        RT.block_on(
+            respond("add-record", www.clone())
+                .expect(adds)
+                .mount(&server),
+        );
+        RT.block_on(respond("edit-record", www).expect(edits).mount(&server));
+        server
+    }
+
+    #[test]
+    fn replace_existing_adds_when_no_record_matches() {
+        let server = replace_existing_server(
+            &serde_json::json!([
+                { "id": "mx", "name": "www", "type": "MX", "content": "mail.example.com", "prio": 10 }
+            ]),
+            1,
+            0,
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let record =
+            add_or_replace(&client, &add_params("www", RecordType::A, "192.0.2.10")).unwrap();
+
+        assert_eq!(record.id, "new");
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn replace_existing_edits_the_single_matching_record() {
+        let server = replace_existing_server(
+            &serde_json::json!([
+                { "id": "old", "name": "WWW", "type": "A", "content": "192.0.2.1" }
+            ]),
+            0,
+            1,
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        add_or_replace(&client, &add_params("www", RecordType::A, "192.0.2.10")).unwrap();
+
+        let requests = RT.block_on(server.received_requests()).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["method"], "edit-record");
+        assert_eq!(body["params"]["id"], "old");
+        assert_eq!(body["params"]["content"], "192.0.2.10");
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn replace_existing_refuses_several_matching_records() {
+        let server = replace_existing_server(
+            &serde_json::json!([
+                { "id": "a", "name": "www", "type": "A", "content": "192.0.2.1" },
+                { "id": "b", "name": "www", "type": "A", "content": "192.0.2.2" }
+            ]),
+            0,
+            0,
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let result = add_or_replace(&client, &add_params("www", RecordType::A, "192.0.2.10"));
+
+        assert!(matches!(
+            result,
+            Err(NjallaError::Input { message }) if message.starts_with("2 A records named www exist")
+        ));
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn changed_compares_live_zone_with_snapshot() {
+        let server = RT.block_on(MockServer::start());
+        let list_records = |records: serde_json::Value| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "list-records" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "records": records }
+                })))
+        };
+        RT.block_on(
+            list_records(serde_json::json!([
+                { "id": "www", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 3600 },
+                { "id": "mx", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 10 },
+                { "id": "old", "name": "old", "type": "CNAME", "content": "example.com" }
+            ]))
+            .up_to_n_times(1)
+            .mount(&server),
+        );
+        RT.block_on(
+            list_records(serde_json::json!([
+                { "id": "mx", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 10 },
+                { "id": "www2", "name": "www", "type": "A", "content": "192.0.2.9", "ttl": 3600 },
+                { "id": "api", "name": "api", "type": "A", "content": "192.0.2.5" }
+            ]))
+            .mount(&server),
+        );
+        let path =
+            std::env::temp_dir().join(format!("njalla-dns-snapshot-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let client = || NjallaClient::with_base_url("token", &server.uri());
+
+        assert!(matches!(
+            zone_changes(&client(), "example.com", &path),
+            Err(NjallaError::Input { .. })
+        ));
+        let saved = save_snapshot(&client(), "example.com", 1_700_000_000, &path).unwrap();
+        let changes = zone_changes(&client(), "example.com", &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved["records"], 3);
+        assert_eq!(changes["snapshot_timestamp"], 1_700_000_000);
+        assert_eq!(changes["changed"], true);
+        assert_eq!(changes["added"], serde_json::json!(["A api -> 192.0.2.5"]));
+        assert_eq!(
+            changes["removed"],
+            serde_json::json!(["CNAME old -> example.com"])
+        );
+        assert_eq!(
+            changes["modified"],
+            serde_json::json!(["A www -> 192.0.2.1 (ttl=3600) => A www -> 192.0.2.9 (ttl=3600)"])
+        );
+        assert_eq!(changes["unchanged"], 1);
+    }
+
+    #[test]
+    fn guarded_edit_aborts_when_fingerprint_is_stale() {
+        let server = RT.block_on(MockServer::start());
+        let list_records = |content: &str| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "list-records" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "records": [
+                        { "id": "rec1", "name": "www", "type": "A", "content": content }
+                    ] }
+                })))
+        };
+        RT.block_on(list_records("192.0.2.2").mount(&server));
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "edit-record" }),
+                ))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0)
+                .mount(&server),
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let params = EditRecordParams {
+            domain: "example.com".to_string(),
+            id: "rec1".to_string(),
+            name: None,
+            content: Some("192.0.2.3".to_string()),
+            ttl: None,
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        };
+
+        let listed: Record = serde_json::from_value(serde_json::json!(
+            { "id": "rec1", "name": "www", "type": "A", "content": "192.0.2.1" }
+        ))
+        .unwrap();
+        let stale = listed.fingerprint();
+
+        let result = guarded_edit(&client, &params, &stale);
+
+        assert!(matches!(result, Err(NjallaError::RecordChanged { id }) if id == "rec1"));
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn list_comments_match_records_by_id_then_content() {
+        let path =
+            std::env::temp_dir().join(format!("njalla-list-comments-{}.json", std::process::id()));
+        let mut comments = RecordComments::default();
+        comments.set("example.com", &record("www", RecordType::A), "for staging");
+        let mut mx = record("@", RecordType::Mx);
+        mx.id = "mx1".to_string();
+        comments.set("example.com", &mx, "primary mail");
+        comments.save(&path).unwrap();
+
+        let mut reissued = record("www", RecordType::A);
+        reissued.id = "new-id".to_string();
+        mx.content = Some("other.example.net".to_string());
+        let shown = list_comments(&path, "example.com", &[reissued, mx]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            shown,
+            BTreeMap::from([
+                ("new-id".to_string(), "for staging".to_string()),
+                ("mx1".to_string(), "primary mail".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn listed_record_round_trips_through_edit_json() {
+        let server = RT.block_on(MockServer::start());
+        let mx = serde_json::json!({
+            "id": "rec1", "name": "@", "type": "MX", "content": "mail.example.com",
+            "ttl": 10800, "prio": 10
+        });
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "list-records" }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "records": [mx] } })),
+                )
+                .mount(&server),
+        );
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({
+                    "method": "edit-record",
+                    "params": {
+                        "domain": "example.com", "id": "rec1", "name": "@",
+                        "content": "mail.example.com", "ttl": 10800, "prio": 10
+                    }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": mx
+                })))
+                .expect(1)
+                .mount(&server),
+        );
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let listed: serde_json::Value = serde_json::from_str(
+            &format_records(
+                &client.list_records("example.com").unwrap(),
+                &BTreeMap::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(listed[0]["ttl_default"], true);
+        let params = parse_record_json("example.com", &listed[0].to_string()).unwrap();
+        let edited = client.edit_record(&params).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&edited).unwrap(),
+            serde_json::to_value(serde_json::from_value::<Record>(mx).unwrap()).unwrap()
+        );
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn record_json_needs_id_and_consistent_fields() {
+        let parse = |json: serde_json::Value| parse_record_json("example.com", &json.to_string());
+        let message = |json: serde_json::Value| match parse(json) {
+            Err(NjallaError::Input { message }) => message,
+            other => panic!("expected an input error, got {other:?}"),
+        };
+
+        assert!(
+            message(serde_json::json!({ "name": "@", "type": "A", "content": "192.0.2.1" }))
+                .contains("no \"id\"")
+        );
+        assert!(
+            message(serde_json::json!([{ "id": "rec1", "name": "@", "type": "A" }]))
+                .contains("single record object")
+        );
+        assert_eq!(
+            message(
+                serde_json::json!({ "id": "rec1", "name": "@", "type": "MX", "content": "mx" })
+            ),
+            "MX records need \"prio\""
+        );
+        assert_eq!(
+            message(serde_json::json!({
+                "id": "rec1", "name": "www", "type": "A", "content": "192.0.2.1", "port": 80
+            })),
+            "A records have no \"port\" field"
+        );
+        assert_eq!(
+            message(serde_json::json!({ "id": "rec1", "name": "www", "type": "CNAME" })),
+            "CNAME records need \"content\""
+        );
+
+        let srv = parse(serde_json::json!({
+            "id": "rec2", "name": "_sip._tcp", "type": "SRV", "content": "sip.example.com",
+            "prio": 10, "weight": 5, "port": 5060
+        }))
+        .unwrap();
+        assert_eq!(srv.id, "rec2");
+        assert_eq!(srv.name.as_deref(), Some("_sip._tcp"));
+        assert_eq!(
+            (srv.priority, srv.weight, srv.port),
+            (Some(10), Some(5), Some(5060))
+        );
+    }
+
+    #[test]
+    fn rebase_hostname_rewrites_names_under_source() {
+        assert_eq!(
+            rebase_hostname("source.com", "source.com", "dest.com").as_deref(),
+            Some("dest.com")
+        );
+        assert_eq!(
+            rebase_hostname("mail.Source.com.", "source.com", "dest.com").as_deref(),
+            Some("mail.dest.com.")
+        );
+        assert!(rebase_hostname("notsource.com", "source.com", "dest.com").is_none());
+        assert!(rebase_hostname("ghs.google.com", "source.com", "dest.com").is_none());
+    }
+
+    #[test]
+    fn plan_copy_rewrites_targets_and_skips_system_records() {
+        let mut apex_ns = record("@", RecordType::Ns);
+        apex_ns.content = Some("1-you.njalla.no".to_string());
+        let mut www = record("www", RecordType::Cname);
+        www.content = Some("source.com".to_string());
+        let mut mx = record("@", RecordType::Mx);
+        mx.content = Some("mail.source.com".to_string());
+        let mut txt = record("@", RecordType::Txt);
+        txt.content = Some("source.com verification".to_string());
+        let source = [apex_ns, www, mx, txt, record("mail", RecordType::A)];
+
+        let existing = record("mail", RecordType::A);
+        let stale = record("old", RecordType::A);
+        let plan = plan_copy(&source, &[existing, stale], "source.com", "dest.com", true);
+
+        assert_eq!(plan.skipped_system, 1);
+        assert_eq!(plan.skipped_existing, 1);
+        let added: Vec<String> = plan.add.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            added,
+            [
+                "CNAME www -> dest.com (ttl=3600)",
+                "MX @ -> mail.dest.com (ttl=3600)",
+                "TXT @ -> source.com verification (ttl=3600)"
+            ]
+        );
+        assert!(plan.add.iter().all(|p| p.domain == "dest.com"));
+        let removed: Vec<&str> = plan.remove.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(removed, ["old"]);
+    }
+
+    fn records(json: serde_json::Value) -> Vec<Record> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn plan_replace_keeps_edits_adds_and_removes() {
+        let live = records(serde_json::json!([
+            { "id": "ns", "name": "@", "type": "NS", "content": "1-you.njalla.no" },
+            { "id": "www", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 3600 },
+            { "id": "mx", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 10 },
+            { "id": "api", "name": "api", "type": "A", "content": "192.0.2.5", "ttl": 3600 },
+            { "id": "old", "name": "old", "type": "CNAME", "content": "example.com" }
+        ]));
+        let desired = records(serde_json::json!([
+            { "id": "", "name": "@", "type": "NS", "content": "1-you.njalla.no" },
+            { "id": "", "name": "www", "type": "A", "content": "192.0.2.1" },
+            { "id": "", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 20 },
+            { "id": "", "name": "api", "type": "A", "content": "192.0.2.5", "ttl": 300 },
+            { "id": "", "name": "new", "type": "A", "content": "192.0.2.9" }
+        ]));
+
+        let plan = plan_replace(&live, &desired, "example.com", None);
+
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.skipped_system, 1);
+        let added: Vec<String> = plan.add.iter().map(ToString::to_string).collect();
+        assert_eq!(added, ["A new -> 192.0.2.9"]);
+        let edited: Vec<(&str, Option<i32>, Option<i32>)> = plan
+            .edit
+            .iter()
+            .map(|(record, params)| (record.id.as_str(), params.priority, params.ttl))
+            .collect();
+        assert_eq!(edited, [("mx", Some(20), None), ("api", None, Some(300))]);
+        let removed: Vec<&str> = plan.remove.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(removed, ["old"]);
+    }
+
+    #[test]
+    fn plan_replace_raises_ttls_below_the_floor() {
+        let live = records(serde_json::json!([
+            { "id": "www", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 300 }
+        ]));
+        let desired = records(serde_json::json!([
+            { "id": "", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 60 },
+            { "id": "", "name": "api", "type": "A", "content": "192.0.2.5", "ttl": 3600 },
+            { "id": "", "name": "new", "type": "A", "content": "192.0.2.9" }
+        ]));
+
+        let plan = plan_replace(&live, &desired, "example.com", Some(300));
+
+        assert_eq!(plan.unchanged, 1);
+        let added: Vec<(String, Option<i32>)> = plan
+            .add
+            .iter()
+            .map(|params| (params.to_string(), params.ttl))
+            .collect();
+        assert_eq!(
+            added,
+            [
+                ("A api -> 192.0.2.5 (ttl=3600)".to_string(), Some(3600)),
+                ("A new -> 192.0.2.9".to_string(), None)
+            ]
+        );
+        assert_eq!(plan.raised_ttl, ["A www -> 192.0.2.1 (ttl=300): 60 -> 300"]);
+    }
+
+    #[test]
+    fn plan_replace_of_identical_zone_is_empty() {
+        let live = records(serde_json::json!([
+            { "id": "a", "name": "@", "type": "A", "content": "192.0.2.1", "ttl": 3600 },
+            { "id": "b", "name": "@", "type": "A", "content": "192.0.2.2", "ttl": 3600 }
+        ]));
+
+        let plan = plan_replace(&live, &live, "example.com", None);
+
+        assert_eq!(plan.unchanged, 2);
+        assert!(plan.add.is_empty() && plan.edit.is_empty() && plan.remove.is_empty());
+    }
+
+    fn ttl_config(min: Option<i32>, max: Option<i32>) -> Config {
+        Config {
+            dns_ttl_min: min,
+            dns_ttl_max: max,
+            ..Config::default()
+        }
+    }
+
+    fn input_message<T: std::fmt::Debug>(result: Result<T>) -> String {
+        match result {
+            Err(NjallaError::Input { message }) => message,
+            other => panic!("expected input error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn ttl_policy_rejects_out_of_range_ttls_citing_the_source() {
+        let policy =
+            TtlPolicy::new(&ttl_config(Some(300), Some(86_400)), TtlLimits::default()).unwrap();
+
+        assert!(policy.check(300).is_ok());
+        assert!(policy.check(3600).is_ok());
+        assert!(policy.check(86_400).is_ok());
+        assert_eq!(
+            input_message(policy.check(60)),
+            "TTL 60 is below the minimum of 300 set by dns_ttl_min in config"
+        );
+        assert_eq!(
+            input_message(policy.check(604_800)),
+            "TTL 604800 is above the maximum of 86400 set by dns_ttl_max in config"
+        );
+        assert!(TtlPolicy::default().check(1).is_ok());
+    }
+
+    #[test]
+    fn ttl_flags_tighten_but_never_loosen_the_config() {
+        let config = ttl_config(Some(300), Some(86_400));
+
+        let tightened = TtlPolicy::new(
+            &config,
+            TtlLimits {
+                min: Some(600),
+                max: Some(3600),
+            },
+        )
+        .unwrap();
+        assert!(tightened.check(3600).is_ok());
+        assert!(input_message(tightened.check(7200)).contains("--dns-ttl-max"));
+
+        let loosened = TtlPolicy::new(
+            &config,
+            TtlLimits {
+                min: Some(60),
+                max: None,
+            },
+        );
+        assert!(input_message(loosened).contains("would loosen dns_ttl_min in config = 300"));
+
+        let flags_only = TtlPolicy::new(
+            &Config::default(),
+            TtlLimits {
+                min: Some(60),
+                max: None,
+            },
+        )
+        .unwrap();
+        assert!(flags_only.check(30).is_err());
+    }
+
+    #[test]
+    fn plan_replace_ignores_hostname_case_and_trailing_dots() {
+        let live = records(serde_json::json!([
+            { "id": "a", "name": "www", "type": "CNAME", "content": "example.com.", "ttl": 3600 },
+            { "id": "b", "name": "@", "type": "TXT", "content": "Hello.", "ttl": 3600 }
+        ]));
+        let desired = records(serde_json::json!([
+            { "id": "x", "name": "WWW", "type": "CNAME", "content": "Example.com" },
+            { "id": "y", "name": "@", "type": "TXT", "content": "hello." }
+        ]));
+
+        let plan = plan_replace(&live, &desired, "example.com", None);
+
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.edit.len(), 1);
+        assert_eq!(plan.edit[0].0.id, "b");
+    }
+
+    #[test]
+    fn records_json_accepts_list_output_without_ids() {
+        let json = serde_json::json!({
+            "_schema_version": 1,
+            "data": [
+                { "name": "www.example.com.", "type": "A", "content": "192.0.2.1", "ttl_default": true },
+                { "id": "rec2", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 10 }
+            ]
+        });
+
+        let parsed = parse_records_json("example.com", &json.to_string()).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "www");
+        assert_eq!(parsed[0].id, "");
+        assert_eq!(parsed[1].id, "rec2");
+
+        let bad = serde_json::json!([{ "name": "@", "type": "MX", "content": "mail" }]);
+        assert!(matches!(
+            parse_records_json("example.com", &bad.to_string()),
+            Err(NjallaError::Input { message }) if message == "Record at index 0: MX records need \"prio\""
+        ));
+    }
+
+    #[test]
+    fn copy_replace_keeps_destination_records_when_an_add_fails() {
+        let server = RT.block_on(MockServer::start());
+        let list_records = |domain: &str, records: serde_json::Value| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({
+                    "method": "list-records",
+                    "params": { "domain": domain }
+                })))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "records": records } })),
+                )
+        };
+        RT.block_on(
+            list_records(
+                "source.com",
+                serde_json::json!([{ "id": "a", "name": "www", "type": "A", "content": "192.0.2.1" }]),
+            )
+            .mount(&server),
+        );
+        RT.block_on(
+            list_records(
+                "dest.com",
+                serde_json::json!([{ "id": "b", "name": "old", "type": "A", "content": "192.0.2.9" }]),
+            )
+            .mount(&server),
+        );
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "add-record" }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(
+                        serde_json::json!({ "error": { "message": "Invalid record" } }),
+                    ),
+                )
+                .expect(1)
+                .mount(&server),
+        );
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "remove-record" }),
+                ))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0)
+                .mount(&server),
+        );
+        let options = ClientOptions {
+            token: Some("token".to_string()),
+            base_url: Some(server.uri()),
+            ..ClientOptions::default()
+        };
+
+        let output = run_copy(
+            "source.com",
+            "dest.com",
+            false,
+            true,
+            ErrorMode::FailFast,
+            &options,
+        );
+
+        assert!(matches!(output, Ok(CommandOutput::Failed(..))));
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn replace_adds_before_editing_and_removing() {
+        let server = RT.block_on(MockServer::start());
+        let respond = |name: &str, result: serde_json::Value| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(serde_json::json!({ "method": name })))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": result })),
+                )
+        };
+        let old =
+            serde_json::json!({ "id": "old", "name": "old", "type": "A", "content": "192.0.2.1" });
+        let www =
+            serde_json::json!({ "id": "www", "name": "www", "type": "A", "content": "192.0.2.2" });
+        RT.block_on(
+            respond("list-records", serde_json::json!({ "records": [old, www] })).mount(&server),
+        );
+        RT.block_on(respond("add-record", www.clone()).expect(1).mount(&server));
+        RT.block_on(respond("edit-record", www.clone()).expect(1).mount(&server));
+        RT.block_on(
+            respond("remove-record", serde_json::json!({}))
+                .expect(1)
+                .mount(&server),
+        );
+
+        let file = std::env::temp_dir().join(format!("njalla-replace-{}.json", std::process::id()));
+        std::fs::write(
+            &file,
+            serde_json::json!([
+                { "name": "www", "type": "A", "content": "192.0.2.3" },
+                { "name": "new", "type": "A", "content": "192.0.2.4" }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+        let options = ClientOptions {
+            token: Some("token".to_string()),
+            base_url: Some(server.uri()),
+            ..ClientOptions::default()
+        };
+
+        let output = run_replace(
+            "example.com",
+            &file,
+            false,
+            None,
+            ErrorMode::FailFast,
+            &options,
+        );
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(matches!(output, Ok(CommandOutput::Text(_))));
+        let methods: Vec<String> = RT
+            .block_on(server.received_requests())
+            .unwrap()
+            .iter()
+            .map(|request| {
+                serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()["method"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            ["list-records", "add-record", "edit-record", "remove-record"]
+        );
+        RT.block_on(server.verify());
+    }
+}
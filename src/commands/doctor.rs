@@ -0,0 +1,367 @@
+//! Account-wide health report command.
+
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::concurrency::fan_out;
+use crate::error::Result;
+use crate::output::to_json;
+use crate::types::{Domain, Record, RecordType};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// TTL values accepted by the Njalla web interface.
+const STANDARD_TTLS: [i32; 7] = [60, 300, 900, 3600, 10800, 21600, 86400];
+
+/// Expiry within this many days is critical rather than a warning.
+const CRITICAL_EXPIRY_DAYS: i64 = 7;
+
+/// How urgent a finding is. Sorts most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    Warning,
+    Info,
+}
+
+/// A single problem found by `doctor`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Domain the finding applies to.
+    pub domain: String,
+    /// How urgent the finding is.
+    pub severity: Severity,
+    /// Machine-readable check name (e.g., `expiring_soon`).
+    pub check: &'static str,
+    /// Human-readable explanation.
+    pub message: String,
+}
+
+/// Run the doctor command.
+///
+/// Checks every domain for expiry, transfer lock, empty zones and DNS
+/// record problems, and prints findings ordered by severity.
+pub fn run(expiry_days: i64, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let domains = client.list_domains()?;
+    let today = today();
+
+    let mut findings: Vec<Finding> = fan_out(&domains, client.max_concurrency(), |d| {
+        check_domain(&client, &d.name, today, expiry_days)
+    })
+    .into_iter()
+    .flatten()
+    .collect();
+    findings.sort_by(|a, b| (a.severity, &a.domain).cmp(&(b.severity, &b.domain)));
+
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "domains_checked": domains.len(),
+        "summary": {
+            "critical": count(Severity::Critical),
+            "warning": count(Severity::Warning),
+            "info": count(Severity::Info),
+        },
+        "findings": findings,
+    }))?))
+}
+
+/// Fetch one domain's details and records and run all checks on them.
+///
+/// API failures are reported as findings so one bad domain doesn't hide
+/// the rest of the report.
+fn check_domain(client: &NjallaClient, name: &str, today: i64, expiry_days: i64) -> Vec<Finding> {
+    let fetched = client
+        .get_domain(name)
+        .and_then(|domain| Ok((domain, client.list_records(name)?)));
+
+    match fetched {
+        Ok((domain, records)) => {
+            let mut findings = domain_findings(&domain, today, expiry_days);
+            if records.is_empty() {
+                findings.push(finding(
+                    name,
+                    Severity::Warning,
+                    "empty_zone",
+                    "No DNS records configured".to_string(),
+                ));
+            }
+            findings.extend(lint_records(name, &records));
+            findings
+        }
+        Err(e) => vec![finding(
+            name,
+            Severity::Critical,
+            "check_failed",
+            format!("Could not check domain: {e}"),
+        )],
+    }
+}
+
+/// Checks on the domain itself: expiry and transfer lock.
+fn domain_findings(domain: &Domain, today: i64, expiry_days: i64) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let name = domain.name.as_str();
+
+    if domain.is_expired() {
+        findings.push(finding(
+            name,
+            Severity::Critical,
+            "expired",
+            format!("Domain status is {}", domain.status_kind()),
+        ));
+    } else if let Some(days) = domain.days_until_expiry(today) {
+        if days < 0 {
+            findings.push(finding(
+                name,
+                Severity::Critical,
+                "expired",
+                format!("Expired {} days ago", -days),
+            ));
+        } else if days <= expiry_days {
+            let severity = if days <= CRITICAL_EXPIRY_DAYS {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            };
+            findings.push(finding(
+                name,
+                severity,
+                "expiring_soon",
+                format!("Expires in {days} days"),
+            ));
+        }
+    }
+
+    if domain.locked == Some(false) {
+        findings.push(finding(
+            name,
+            Severity::Warning,
+            "unlocked",
+            "Domain is not locked against transfer".to_string(),
+        ));
+    }
+
+    findings
+}
+
+/// Lint DNS records for common mistakes.
+fn lint_records(domain: &str, records: &[Record]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let cname_names: BTreeSet<&str> = records
+        .iter()
+        .filter(|r| r.record_type == RecordType::Cname)
+        .map(|r| r.name.as_str())
+        .collect();
+
+    for name in &cname_names {
+        if *name == "@" {
+            findings.push(finding(
+                domain,
+                Severity::Critical,
+                "apex_cname",
+                "CNAME at the zone apex; use ANAME instead".to_string(),
+            ));
+        }
+        if records
+            .iter()
+            .any(|r| r.name == *name && r.record_type != RecordType::Cname)
+        {
+            findings.push(finding(
+                domain,
+                Severity::Critical,
+                "cname_conflict",
+                format!("CNAME at '{name}' coexists with other records"),
+            ));
+        }
+    }
+
+    for record in records {
+        let content = record.content.as_deref().unwrap_or_default();
+        let label = format!("{} {} (id {})", record.record_type, record.name, record.id);
+
+        match record.record_type {
+            RecordType::A if content.parse::<std::net::Ipv4Addr>().is_err() => {
+                findings.push(finding(
+                    domain,
+                    Severity::Critical,
+                    "invalid_address",
+                    format!("{label}: '{content}' is not an IPv4 address"),
+                ));
+            }
+            RecordType::Aaaa if content.parse::<std::net::Ipv6Addr>().is_err() => {
+                findings.push(finding(
+                    domain,
+                    Severity::Critical,
+                    "invalid_address",
+                    format!("{label}: '{content}' is not an IPv6 address"),
+                ));
+            }
+            RecordType::Mx if content.parse::<std::net::IpAddr>().is_ok() => {
+                findings.push(finding(
+                    domain,
+                    Severity::Warning,
+                    "mx_points_to_ip",
+                    format!("{label}: MX must point to a hostname, not an IP"),
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(ttl) = record.ttl {
+            if !STANDARD_TTLS.contains(&ttl) {
+                findings.push(finding(
+                    domain,
+                    Severity::Info,
+                    "nonstandard_ttl",
+                    format!("{label}: TTL {ttl} is not a standard Njalla value"),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+fn finding(domain: &str, severity: Severity, check: &'static str, message: String) -> Finding {
+    Finding {
+        domain: domain.to_string(),
+        severity,
+        check,
+        message,
+    }
+}
+
+/// Current date as days since the Unix epoch.
+fn today() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    i64::try_from(secs / 86_400).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(json: serde_json::Value) -> Domain {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn record(id: &str, name: &str, record_type: RecordType, content: &str) -> Record {
+        Record {
+            id: id.to_string(),
+            name: name.to_string(),
+            record_type,
+            content: Some(content.to_string()),
+            ttl: Some(3600),
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        }
+    }
+
+    fn checks(findings: &[Finding]) -> Vec<(&'static str, Severity)> {
+        findings.iter().map(|f| (f.check, f.severity)).collect()
+    }
+
+    // 2027-01-15 is day 20833.
+    const EXPIRY_DAY: i64 = 20_833;
+
+    #[test]
+    fn expiring_soon_is_warning_then_critical() {
+        let d = domain(serde_json::json!({
+            "name": "example.com", "status": "active",
+            "expiry": "2027-01-15T00:00:00Z", "locked": true
+        }));
+
+        assert!(domain_findings(&d, EXPIRY_DAY - 60, 30).is_empty());
+        assert_eq!(
+            checks(&domain_findings(&d, EXPIRY_DAY - 20, 30)),
+            vec![("expiring_soon", Severity::Warning)]
+        );
+        assert_eq!(
+            checks(&domain_findings(&d, EXPIRY_DAY - 3, 30)),
+            vec![("expiring_soon", Severity::Critical)]
+        );
+    }
+
+    #[test]
+    fn past_expiry_date_reads_as_expired() {
+        let d = domain(serde_json::json!({
+            "name": "example.com", "status": "active",
+            "expiry": "2027-01-15T00:00:00Z", "locked": true
+        }));
+
+        let findings = domain_findings(&d, EXPIRY_DAY + 4, 30);
+        assert_eq!(checks(&findings), vec![("expired", Severity::Critical)]);
+        assert_eq!(findings[0].message, "Expired 4 days ago");
+    }
+
+    #[test]
+    fn expired_and_unlocked_domains_are_flagged() {
+        let d = domain(serde_json::json!({
+            "name": "old.com", "status": "redemption", "locked": false
+        }));
+        assert_eq!(
+            checks(&domain_findings(&d, EXPIRY_DAY, 30)),
+            vec![
+                ("expired", Severity::Critical),
+                ("unlocked", Severity::Warning)
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_flags_cname_problems() {
+        let records = vec![
+            record("1", "@", RecordType::Cname, "other.example."),
+            record("2", "www", RecordType::Cname, "example.com"),
+            record("3", "www", RecordType::A, "192.0.2.1"),
+        ];
+        assert_eq!(
+            checks(&lint_records("example.com", &records)),
+            vec![
+                ("apex_cname", Severity::Critical),
+                ("cname_conflict", Severity::Critical)
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_flags_bad_content_and_ttl() {
+        let mut odd_ttl = record("4", "@", RecordType::Txt, "v=spf1 -all");
+        odd_ttl.ttl = Some(1234);
+        let records = vec![
+            record("1", "@", RecordType::A, "not-an-ip"),
+            record("2", "@", RecordType::Aaaa, "2001:db8::1"),
+            record("3", "@", RecordType::Mx, "192.0.2.25"),
+            odd_ttl,
+        ];
+        assert_eq!(
+            checks(&lint_records("example.com", &records)),
+            vec![
+                ("invalid_address", Severity::Critical),
+                ("mx_points_to_ip", Severity::Warning),
+                ("nonstandard_ttl", Severity::Info)
+            ]
+        );
+    }
+
+    #[test]
+    fn severity_orders_most_urgent_first() {
+        let mut severities = vec![Severity::Info, Severity::Critical, Severity::Warning];
+        severities.sort();
+        assert_eq!(
+            severities,
+            vec![Severity::Critical, Severity::Warning, Severity::Info]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Single-domain management commands.
+
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::error::{NjallaError, Result};
+use crate::output::to_json;
+
+/// Run the authcode command.
+///
+/// Prints the EPP auth code needed to transfer a domain out of Njalla.
+pub fn run_auth_code(domain: &str, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let auth_code = auth_code(&client, domain)?;
+    Ok(CommandOutput::Text(to_json(&serde_json::json!({
+        "domain": domain,
+        "auth_code": auth_code,
+    }))?))
+}
+
+/// Fetch the auth code, refusing while the domain is transfer-locked.
+///
+/// A locked domain cannot be transferred, so its auth code is useless and
+/// the API is not asked for it.
+fn auth_code(client: &NjallaClient, domain: &str) -> Result<String> {
+    if client.get_domain(domain)?.locked == Some(true) {
+        return Err(NjallaError::DomainLocked(domain.to_string()));
+    }
+    client.get_auth_code(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+
+    /// Serve a domain with the given lock state; `get-auth-code` must be hit `calls` times.
+    fn server(locked: bool, calls: u64) -> MockServer {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "get-domain" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "name": "example.com", "status": "active", "locked": locked }
+                })))
+                .mount(&server),
+        );
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "get-auth-code" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "auth_code": "s3cret-epp" }
+                })))
+                .expect(calls)
+                .mount(&server),
+        );
+        server
+    }
+
+    #[test]
+    fn auth_code_refuses_locked_domain() {
+        let server = server(true, 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let result = auth_code(&client, "example.com");
+
+        assert!(matches!(result, Err(NjallaError::DomainLocked(d)) if d == "example.com"));
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn auth_code_returned_for_unlocked_domain() {
+        let server = server(false, 1);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        assert_eq!(auth_code(&client, "example.com").unwrap(), "s3cret-epp");
+        RT.block_on(server.verify());
+    }
+}
@@ -1,18 +1,40 @@
 //! List domains command.
 
-use crate::client::NjallaClient;
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
 use crate::error::Result;
-use crate::output::format_domains;
+use crate::filter::{retain_matching, FieldFilter};
+use crate::output::{format_domains, format_domains_porcelain, streaming, write_domains};
+use crate::types::{Domain, DomainStatus};
 
 /// Run the domains command.
 ///
-/// Lists all domains in the user's Njalla account.
-pub fn run(debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Lists all domains in the user's Njalla account, optionally filtered by
+/// normalized status and `--only` filters, as JSON or as `--porcelain` lines.
+pub fn run(
+    status: Option<&DomainStatus>,
+    expired: bool,
+    only: &[FieldFilter],
+    porcelain: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
-    let domains = client.list_domains()?;
-    let formatted = format_domains(&domains)?;
-    println!("{formatted}");
-
-    Ok(())
+    let mut domains = client.list_domains()?;
+    if let Some(status) = status {
+        domains.retain(|d| d.status_kind() == *status);
+    }
+    if expired {
+        domains.retain(Domain::is_expired);
+    }
+    retain_matching(&mut domains, only)?;
+    if porcelain {
+        return Ok(CommandOutput::Text(format_domains_porcelain(&domains)));
+    }
+    if streaming() {
+        return Ok(CommandOutput::stream(move |out| {
+            write_domains(out, &domains)
+        }));
+    }
+    Ok(CommandOutput::Text(format_domains(&domains)?))
 }
@@ -1,8 +1,167 @@
 //! CLI command implementations.
 
+pub mod bulk;
+pub mod config;
+pub mod diff;
 pub mod dns;
+pub mod doctor;
+pub mod domain;
 pub mod domains;
 pub mod register;
 pub mod search;
 pub mod status;
+pub mod tasks;
 pub mod wallet;
+
+use crate::error::{NjallaError, Result};
+use crate::pager;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Rendered result of a command, printed to stdout by `main`.
+///
+/// Commands return their output instead of printing it, so they can be
+/// embedded or tested without capturing stdout. Interactive prompts and
+/// streamed progress lines are the only direct writes.
+#[derive(Debug)]
+#[must_use]
+pub enum CommandOutput {
+    /// Text to print.
+    Text(String),
+    /// Text to print, after which the command fails (e.g. a bulk summary
+    /// that lists failed items).
+    Failed(String, NjallaError),
+    /// JSON written straight to stdout, so a large list is never held in
+    /// memory as one string.
+    Stream(JsonStream),
+}
+
+/// Writes a command's JSON output to the given writer.
+type WriteJson = dyn FnOnce(&mut dyn Write) -> Result<()>;
+
+/// Writes a command's JSON output on demand.
+pub struct JsonStream(Box<WriteJson>);
+
+impl fmt::Debug for JsonStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("JsonStream")
+    }
+}
+
+impl CommandOutput {
+    /// Text output for a summary, failing afterwards if `check` failed.
+    pub fn checked(text: String, check: Result<()>) -> Self {
+        match check {
+            Ok(()) => Self::Text(text),
+            Err(e) => Self::Failed(text, e),
+        }
+    }
+
+    /// Output streamed by `write` when printed.
+    pub fn stream(write: impl FnOnce(&mut dyn Write) -> Result<()> + 'static) -> Self {
+        Self::Stream(JsonStream(Box::new(write)))
+    }
+
+    /// The text to print. Streamed output has none, so it is never paged.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Text(text) | Self::Failed(text, _) => text,
+            Self::Stream(_) => "",
+        }
+    }
+
+    /// The full text and the error carried by `Failed`, with streamed output
+    /// written into memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if streaming the output fails.
+    pub fn into_parts(self) -> Result<(String, Option<NjallaError>)> {
+        match self {
+            Self::Text(text) => Ok((text, None)),
+            Self::Failed(text, e) => Ok((text, Some(e))),
+            Self::Stream(JsonStream(write)) => {
+                let mut buffer = Vec::new();
+                write(&mut buffer)?;
+                let text = String::from_utf8(buffer).map_err(|e| NjallaError::Config {
+                    message: format!("Failed to write output: {e}"),
+                })?;
+                Ok((text, None))
+            }
+        }
+    }
+
+    /// Print the text as a line, through `pager` if given, then return the
+    /// error, if any. If the pager cannot be started, the text is printed
+    /// directly.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error carried by `Failed`.
+    pub fn print(self, pager: Option<&str>) -> Result<()> {
+        if let Self::Stream(JsonStream(write)) = self {
+            let mut stdout = io::stdout().lock();
+            write(&mut stdout)?;
+            return writeln!(stdout).map_err(|e| NjallaError::Config {
+                message: format!("Failed to write output: {e}"),
+            });
+        }
+        match pager.map(|pager| pager::page(self.text(), pager)) {
+            Some(Ok(())) => {}
+            Some(Err(e)) => {
+                eprintln!("Warning: {e}");
+                println!("{}", self.text());
+            }
+            None => println!("{}", self.text()),
+        }
+        match self {
+            Self::Text(_) | Self::Stream(_) => Ok(()),
+            Self::Failed(_, e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_parts_collects_streamed_output() {
+        let output = CommandOutput::stream(|writer| {
+            writer
+                .write_all(b"[1, 2]")
+                .map_err(|e| NjallaError::Config {
+                    message: e.to_string(),
+                })
+        });
+        let (text, error) = output.into_parts().unwrap();
+        assert_eq!(text, "[1, 2]");
+        assert!(error.is_none());
+    }
+
+    #[test]
+    fn checked_keeps_text_and_carries_failure() {
+        let ok = CommandOutput::checked("{}".to_string(), Ok(()));
+        assert!(matches!(ok, CommandOutput::Text(ref t) if t == "{}"));
+
+        let failed = CommandOutput::checked(
+            "{}".to_string(),
+            Err(NjallaError::BatchFailed {
+                failed: 1,
+                total: 2,
+            }),
+        );
+        assert_eq!(failed.text(), "{}");
+        assert!(matches!(
+            failed,
+            CommandOutput::Failed(
+                _,
+                NjallaError::BatchFailed {
+                    failed: 1,
+                    total: 2
+                }
+            )
+        ));
+    }
+}
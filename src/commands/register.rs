@@ -1,33 +1,117 @@
 //! Register domain command.
 
-use crate::client::NjallaClient;
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::bulk::{run_batch, ErrorMode};
+use crate::commands::CommandOutput;
 use crate::error::{NjallaError, Result};
+use crate::output::{progress_writer, to_json, to_json_line};
+use crate::task_log::{self, TaskEntry, TASK_LOG_FILE};
+use crate::types::MarketDomain;
+use serde::Serialize;
 use std::io::{self, Write};
+use std::path::Path;
 use std::thread;
 use std::time::{Duration, Instant};
 
 /// Poll interval for checking task status.
 const POLL_INTERVAL_SECS: u64 = 2;
 
+/// `get-domain` lookups made after a completed task before giving up on
+/// seeing the domain, one poll interval apart.
+const VISIBILITY_ATTEMPTS: u32 = 5;
+
+/// Environment variable that answers yes to every confirmation prompt.
+pub const ASSUME_YES_ENV: &str = "NJALLA_ASSUME_YES";
+
+/// Whether `NJALLA_ASSUME_YES` approves prompts: set to a non-empty value
+/// other than `0`.
+///
+/// This is the same as passing `--confirm` everywhere, so in CI it registers
+/// (and spends wallet funds) without anyone seeing the price.
+#[must_use]
+pub fn assume_yes(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// [`assume_yes`] for the current environment.
+#[must_use]
+pub fn assume_yes_from_env() -> bool {
+    assume_yes(std::env::var(ASSUME_YES_ENV).ok().as_deref())
+}
+
+/// How a registration is approved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    /// Show the price and ask on stdin.
+    Prompt,
+    /// Register without asking (`--confirm`).
+    Confirmed,
+    /// Quote the price and print a confirmation token, without registering
+    /// (`--reserve`).
+    Reserve,
+    /// Register if the price in this token still holds (`--confirm-token`).
+    Token(String),
+}
+
+impl Approval {
+    /// Approval for the `--confirm`, `--reserve` and `--confirm-token` flags.
+    #[must_use]
+    pub fn from_flags(confirm: bool, reserve: bool, confirm_token: Option<String>) -> Self {
+        match (confirm_token, reserve, confirm) {
+            (Some(token), _, _) => Self::Token(token),
+            (None, true, _) => Self::Reserve,
+            (None, false, true) => Self::Confirmed,
+            (None, false, false) => Self::Prompt,
+        }
+    }
+}
+
 /// Run the register command.
 ///
-/// Registers a new domain through Njalla.
-#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+/// Registers new domains through Njalla. With `assume_available`, the
+/// `find-domains` availability and price check is skipped. Several domains
+/// go through [`register_many`], with `mode` deciding whether a failed
+/// registration stops the rest.
+#[allow(clippy::too_many_arguments)]
 pub fn run(
-    domain: &str,
+    domains: &[String],
     years: i32,
-    confirm: bool,
+    approval: &Approval,
     wait: bool,
     timeout: u64,
-    debug: bool,
-) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
-
-    // Check domain availability and get price
-    let search_results = client.find_domains(domain)?;
-    let domain_info = search_results.iter().find(|d| d.name == domain);
+    assume_available: bool,
+    mode: ErrorMode,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+    let log_path = Path::new(TASK_LOG_FILE);
+    if let [domain] = domains {
+        return register(
+            &client,
+            domain,
+            years,
+            approval,
+            wait,
+            timeout,
+            assume_available,
+            log_path,
+        );
+    }
+    if assume_available {
+        return Err(NjallaError::Input {
+            message: "--assume-available takes a single domain".to_string(),
+        });
+    }
+    register_many(
+        &client, domains, years, approval, wait, timeout, mode, log_path,
+    )
+}
 
-    let Some(info) = domain_info else {
+/// Look up `domain` in search results and return its yearly price.
+///
+/// Fails unless the domain is listed as available.
+fn available_price(client: &NjallaClient, domain: &str) -> Result<i32> {
+    let Some(info) = search_exact(client, domain)? else {
         return Err(NjallaError::DomainNotAvailable(format!(
             "{domain} not found in search results"
         )));
@@ -43,82 +127,958 @@ pub fn run(
         return Err(NjallaError::DomainNotAvailable(reason));
     }
 
-    let total_price = info.price * years;
+    Ok(info.price)
+}
 
-    // Show confirmation unless --confirm flag is set
-    if !confirm {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&serde_json::json!({
-                "domain": domain,
-                "price_per_year": info.price,
-                "years": years,
-                "total_price": total_price
-            }))?
-        );
-        print!("Proceed with registration? [y/N] ");
-        let _ = io::stdout().flush();
+/// The search result for exactly `domain`.
+///
+/// Results are ranked by relevance and may leave out the very name searched
+/// for, so a miss is retried with the bare label (`example` for
+/// `example.com`), which lists that label under every TLD. There is no
+/// dedicated availability method to ask instead.
+fn search_exact(client: &NjallaClient, domain: &str) -> Result<Option<MarketDomain>> {
+    let label = domain.split_once('.').map(|(label, _)| label);
+    for query in std::iter::once(domain).chain(label) {
+        let results = client.find_domains(query)?;
+        if let Some(info) = results
+            .into_iter()
+            .find(|d| d.name.eq_ignore_ascii_case(domain))
+        {
+            return Ok(Some(info));
+        }
+    }
+    Ok(None)
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).ok();
-        if !input.trim().eq_ignore_ascii_case("y") {
-            println!("Registration cancelled.");
-            return Ok(());
+/// Confirmation token for a quote, as `<price>-<years>-<checksum>`.
+///
+/// The checksum (FNV-1a over the domain, price and years) ties the token to
+/// its domain and catches edited or mistyped tokens. It is not a secret
+/// signature: anyone can mint a token, just as anyone can pass `--confirm`.
+fn reservation_token(domain: &str, price: i32, years: i32) -> String {
+    format!(
+        "{price}-{years}-{:016x}",
+        quote_checksum(domain, price, years)
+    )
+}
+
+fn quote_checksum(domain: &str, price: i32, years: i32) -> u64 {
+    format!("{}|{price}|{years}", domain.to_ascii_lowercase())
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The yearly price quoted in `token`, checked against `domain` and `years`.
+fn quoted_price(token: &str, domain: &str, years: i32) -> Result<i32> {
+    let invalid = || NjallaError::Input {
+        message: format!("Invalid confirmation token for {domain}; run --reserve again"),
+    };
+    let mut parts = token.trim().splitn(3, '-');
+    let (Some(price), Some(token_years), Some(checksum)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let price: i32 = price.parse().map_err(|_| invalid())?;
+    let token_years: i32 = token_years.parse().map_err(|_| invalid())?;
+    if checksum != format!("{:016x}", quote_checksum(domain, price, token_years)) {
+        return Err(invalid());
+    }
+    if token_years != years {
+        return Err(NjallaError::Input {
+            message: format!(
+                "Confirmation token is for {token_years} year(s); pass --years {token_years}"
+            ),
+        });
+    }
+    Ok(price)
+}
+
+/// Check, approve and register `domain`; the shared flow behind [`run`].
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+pub(super) fn register(
+    client: &NjallaClient,
+    domain: &str,
+    years: i32,
+    approval: &Approval,
+    wait: bool,
+    timeout: u64,
+    assume_available: bool,
+    log_path: &Path,
+) -> Result<CommandOutput> {
+    let quoted = match approval {
+        Approval::Token(token) => Some(quoted_price(token, domain, years)?),
+        _ => None,
+    };
+    if assume_available && matches!(approval, Approval::Reserve | Approval::Token(_)) {
+        return Err(NjallaError::Input {
+            message: "--reserve and --confirm-token need the price check".to_string(),
+        });
+    }
+
+    // Check domain availability and get price, unless the user overrides it
+    let price = if assume_available {
+        None
+    } else {
+        Some(available_price(client, domain)?)
+    };
+    let summary = match price {
+        None => serde_json::json!({
+            "domain": domain,
+            "years": years,
+            "availability_check": "skipped"
+        }),
+        Some(price) => serde_json::json!({
+            "domain": domain,
+            "price_per_year": price,
+            "years": years,
+            "total_price": price * years
+        }),
+    };
+
+    if let (Approval::Reserve, Some(price)) = (approval, price) {
+        let token = reservation_token(domain, price, years);
+        return Ok(CommandOutput::Text(to_json(&serde_json::json!({
+            "domain": domain,
+            "price_per_year": price,
+            "years": years,
+            "total_price": price * years,
+            "confirm_token": token,
+            "message": format!(
+                "Run 'njalla register {domain} --years {years} --confirm-token {token}' to register at this price"
+            ),
+        }))?));
+    }
+    if let (Some(quoted), Some(current)) = (quoted, price) {
+        if quoted != current {
+            return Err(NjallaError::PriceChanged {
+                domain: domain.to_string(),
+                quoted,
+                current,
+            });
         }
     }
 
-    // Register the domain
-    let task_id = client.register_domain(domain, years)?;
+    // Show confirmation unless the registration is already approved
+    if *approval == Approval::Prompt && !prompt_to_proceed(&summary)? {
+        return Ok(CommandOutput::Text("Registration cancelled.".to_string()));
+    }
+
+    let task_id = submit_registration(client, domain, years, log_path)?;
 
     if !wait {
         // Output task ID and exit
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&serde_json::json!({
-                "domain": domain,
-                "task_id": task_id,
-                "status": "pending"
-            }))?
-        );
-        return Ok(());
+        return Ok(CommandOutput::Text(to_json(&serde_json::json!({
+            "domain": domain,
+            "task_id": task_id,
+            "status": "pending"
+        }))?));
     }
 
-    // Poll for completion
     eprintln!("Waiting for registration to complete...");
+    wait_for_task(
+        client,
+        domain,
+        &task_id,
+        Duration::from_secs(timeout),
+        Duration::from_secs(POLL_INTERVAL_SECS),
+        log_path,
+        &mut progress_writer(),
+    )
+}
+
+/// Print `summary` and ask on stdin whether to register.
+///
+/// The summary and question go to [`progress_writer`], so they stay off
+/// stdout under `--json-envelope`.
+fn prompt_to_proceed(summary: &serde_json::Value) -> Result<bool> {
+    let mut out = progress_writer();
+    let _ = writeln!(out, "{}", to_json(summary)?);
+    let _ = write!(out, "Proceed with registration? [y/N] ");
+    let _ = out.flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok();
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Submit the registration of `domain` and log its task, returning the task id.
+fn submit_registration(
+    client: &NjallaClient,
+    domain: &str,
+    years: i32,
+    log_path: &Path,
+) -> Result<String> {
+    let task_id = client.register_domain(domain, years)?;
+    let entry = TaskEntry {
+        id: task_id.clone(),
+        kind: "register".to_string(),
+        target: domain.to_string(),
+    };
+    if let Err(e) = task_log::record(log_path, entry) {
+        eprintln!("Warning: could not record task {task_id}: {e}");
+    }
+    Ok(task_id)
+}
+
+/// One domain's share of a [`MultiQuote`].
+#[derive(Debug, Serialize)]
+struct QuotedDomain {
+    domain: String,
+    price_per_year: i32,
+    total_price: i32,
+}
+
+/// Prices for registering several domains for the same number of years.
+#[derive(Debug, Serialize)]
+struct MultiQuote {
+    domains: Vec<QuotedDomain>,
+    years: i32,
+    total_price: i32,
+}
+
+/// Check that every domain is available and add up what registering all of
+/// them for `years` costs.
+///
+/// # Errors
+///
+/// Returns `NjallaError::DomainNotAvailable` for the first domain that
+/// cannot be registered, so nothing is bought unless all of them can be,
+/// and `NjallaError::Input` before anything is quoted if a domain is named
+/// twice, so it is neither counted twice in the total nor registered twice.
+fn quote_all(client: &NjallaClient, domains: &[String], years: i32) -> Result<MultiQuote> {
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = domains
+        .iter()
+        .find(|domain| !seen.insert(domain.to_lowercase()))
+    {
+        return Err(NjallaError::Input {
+            message: format!("{duplicate} is listed more than once"),
+        });
+    }
+    let domains = domains
+        .iter()
+        .map(|domain| {
+            let price = available_price(client, domain)?;
+            Ok(QuotedDomain {
+                domain: domain.clone(),
+                price_per_year: price,
+                total_price: price * years,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let total_price = domains.iter().map(|d| d.total_price).sum();
+    Ok(MultiQuote {
+        domains,
+        years,
+        total_price,
+    })
+}
+
+/// Register several domains with one price check and one confirmation.
+///
+/// Every domain must be available and the wallet must cover the combined
+/// total before anything is registered. Each registration (and, with
+/// `wait`, its task) is then reported in a batch summary next to the quote.
+#[allow(clippy::too_many_arguments)]
+fn register_many(
+    client: &NjallaClient,
+    domains: &[String],
+    years: i32,
+    approval: &Approval,
+    wait: bool,
+    timeout: u64,
+    mode: ErrorMode,
+    log_path: &Path,
+) -> Result<CommandOutput> {
+    if matches!(approval, Approval::Reserve | Approval::Token(_)) {
+        return Err(NjallaError::Input {
+            message: "--reserve and --confirm-token take a single domain".to_string(),
+        });
+    }
+
+    let quote = quote_all(client, domains, years)?;
+    let balance = client.get_balance()?.balance;
+    if balance < quote.total_price {
+        return Err(NjallaError::Api {
+            message: format!(
+                "Insufficient funds: wallet has {balance} €, registering all {} domains costs {} €",
+                domains.len(),
+                quote.total_price
+            ),
+        });
+    }
+    if *approval == Approval::Prompt && !prompt_to_proceed(&serde_json::to_value(&quote)?)? {
+        return Ok(CommandOutput::Text("Registration cancelled.".to_string()));
+    }
+
+    let mut tasks = Vec::new();
+    let summary = run_batch(domains, mode, Clone::clone, |domain| {
+        let task_id = submit_registration(client, domain, years, log_path)?;
+        tasks.push(serde_json::json!({ "domain": domain, "task_id": task_id }));
+        if !wait {
+            return Ok(());
+        }
+        eprintln!("Waiting for registration of {domain} to complete...");
+        match wait_for_task(
+            client,
+            domain,
+            &task_id,
+            Duration::from_secs(timeout),
+            Duration::from_secs(POLL_INTERVAL_SECS),
+            log_path,
+            &mut progress_writer(),
+        )? {
+            CommandOutput::Failed(_, e) => Err(e),
+            _ => Ok(()),
+        }
+    });
+    let result = serde_json::json!({
+        "quote": quote,
+        "tasks": tasks,
+        "summary": summary,
+    });
+    Ok(CommandOutput::checked(to_json(&result)?, summary.check()))
+}
+
+/// Poll a registration task until it finishes or `timeout` passes.
+///
+/// Streams a `{task_id, status, elapsed_s}` JSON line per poll to `out`, and
+/// returns the final `{domain, task_id, status}` line once the task has
+/// completed or failed. A completed registration also reports the
+/// `domain_status` from [`await_domain`], or `null` if the domain has not
+/// shown up yet.
+fn wait_for_task(
+    client: &NjallaClient,
+    domain: &str,
+    task_id: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+    log_path: &Path,
+    out: &mut dyn Write,
+) -> Result<CommandOutput> {
     let start = Instant::now();
-    let timeout_duration = Duration::from_secs(timeout);
 
     loop {
-        if start.elapsed() > timeout_duration {
+        client.time_left()?;
+        if start.elapsed() > timeout {
             return Err(NjallaError::RegistrationTimeout {
                 domain: domain.to_string(),
-                timeout_secs: timeout,
+                timeout_secs: timeout.as_secs(),
             });
         }
 
-        let status = client.check_task(&task_id)?;
-
-        match status.status.as_str() {
-            "completed" => {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&serde_json::json!({
-                        "domain": domain,
-                        "task_id": task_id,
-                        "status": "completed"
-                    }))?
-                );
-                return Ok(());
+        let status = client.check_task(task_id)?;
+        let progress = serde_json::json!({
+            "task_id": task_id,
+            "status": status.status,
+            "elapsed_s": start.elapsed().as_secs(),
+        });
+        let _ = writeln!(out, "{}", to_json_line(&progress)?);
+
+        if task_log::is_finished(&status.status) {
+            let _ = task_log::forget(log_path, task_id);
+            let _ = out.flush();
+            let mut result = serde_json::json!({
+                "domain": domain,
+                "task_id": task_id,
+                "status": status.status,
+            });
+
+            if status.status == "failed" {
+                return Ok(CommandOutput::Failed(
+                    to_json_line(&result)?,
+                    NjallaError::Api {
+                        message: format!("Registration failed for {domain}"),
+                    },
+                ));
             }
-            "failed" => {
-                return Err(NjallaError::Api {
-                    message: format!("Registration failed for {domain}"),
-                });
+
+            let domain_status = await_domain(client, domain, VISIBILITY_ATTEMPTS, poll_interval);
+            if domain_status.is_none() {
+                eprintln!(
+                    "Warning: task {task_id} completed but {domain} is not listed yet; \
+                     check 'njalla status {domain}' in a few minutes"
+                );
             }
-            _ => {
-                // Still pending/processing, wait and retry
-                thread::sleep(Duration::from_secs(POLL_INTERVAL_SECS));
+            result["domain_status"] = domain_status.into();
+            return Ok(CommandOutput::Text(to_json_line(&result)?));
+        }
+
+        // Still pending/processing, wait and retry
+        let wait = match client.time_left()? {
+            Some(left) => poll_interval.min(left),
+            None => poll_interval,
+        };
+        thread::sleep(wait);
+    }
+}
+
+/// Look the domain up with `get-domain` until it is `active` or `pending`,
+/// at most `attempts` times, and return its status.
+///
+/// A completed registration task can come before the domain is visible, so
+/// any failed lookup means "not yet" and is retried. The registration has
+/// already succeeded, so this never fails: it returns `None` if the domain
+/// never showed up or the deadline ran out first.
+fn await_domain(
+    client: &NjallaClient,
+    domain: &str,
+    attempts: u32,
+    poll_interval: Duration,
+) -> Option<String> {
+    for attempt in 1..=attempts {
+        match client.get_domain(domain) {
+            Ok(info)
+                if ["active", "pending"]
+                    .iter()
+                    .any(|s| info.status.eq_ignore_ascii_case(s)) =>
+            {
+                return Some(info.status);
             }
+            Err(NjallaError::Deadline { .. }) => return None,
+            Ok(_) | Err(_) => {}
+        }
+        if attempt < attempts {
+            let wait = match client.time_left() {
+                Ok(Some(left)) => poll_interval.min(left),
+                Ok(None) => poll_interval,
+                Err(_) => return None,
+            };
+            thread::sleep(wait);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+
+    /// Mount a response for `api_method` that must be hit exactly `calls` times.
+    fn mount(server: &MockServer, api_method: &str, calls: u64) {
+        let body = serde_json::json!({ "result": { "domains": [], "task": "task-1" } });
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": api_method }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(calls)
+                .mount(server),
+        );
+    }
+
+    #[test]
+    fn assume_available_skips_find_domains() {
+        let server = RT.block_on(MockServer::start());
+        mount(&server, "find-domains", 0);
+        mount(&server, "register-domain", 1);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let log_path =
+            std::env::temp_dir().join(format!("njalla-register-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&log_path);
+
+        let output = register(
+            &client,
+            "obscure.tld",
+            1,
+            &Approval::Confirmed,
+            false,
+            300,
+            true,
+            &log_path,
+        )
+        .unwrap();
+
+        RT.block_on(server.verify());
+        let CommandOutput::Text(text) = output else {
+            panic!("registration should not fail: {output:?}");
+        };
+        let result: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(result["task_id"], "task-1");
+        assert_eq!(result["status"], "pending");
+        let log = crate::task_log::TaskLog::load(&log_path).unwrap();
+        std::fs::remove_file(&log_path).unwrap();
+        assert_eq!(log.tasks[0].id, "task-1");
+        assert_eq!(log.tasks[0].target, "obscure.tld");
+    }
+
+    #[test]
+    fn exact_name_missing_from_ranked_results_is_searched_by_label() {
+        let server = RT.block_on(MockServer::start());
+        for (query, domains) in [
+            (
+                "example.com",
+                serde_json::json!([{ "name": "example.net", "status": "available", "price": 30 }]),
+            ),
+            (
+                "example",
+                serde_json::json!([
+                    { "name": "example.net", "status": "available", "price": 30 },
+                    { "name": "example.com", "status": "available", "price": 15 }
+                ]),
+            ),
+        ] {
+            RT.block_on(
+                Mock::given(method("POST"))
+                    .and(body_partial_json(serde_json::json!({
+                        "method": "find-domains",
+                        "params": { "query": query }
+                    })))
+                    .respond_with(
+                        ResponseTemplate::new(200)
+                            .set_body_json(serde_json::json!({ "result": { "domains": domains } })),
+                    )
+                    .expect(1)
+                    .mount(&server),
+            );
+        }
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        assert_eq!(available_price(&client, "example.com").unwrap(), 15);
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn missing_search_result_refuses_registration() {
+        let server = RT.block_on(MockServer::start());
+        mount(&server, "find-domains", 2);
+        mount(&server, "register-domain", 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let path = std::env::temp_dir().join("njalla-register-unused.json");
+        let result = register(
+            &client,
+            "obscure.tld",
+            1,
+            &Approval::Confirmed,
+            false,
+            300,
+            false,
+            &path,
+        );
+
+        assert!(matches!(result, Err(NjallaError::DomainNotAvailable(_))));
+        RT.block_on(server.verify());
+    }
+
+    /// Serve `domain` as available at `price`; `register-domain` must be hit `calls` times.
+    fn priced_server(domain: &str, price: i32, calls: u64) -> MockServer {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "find-domains" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "result": { "domains": [{ "name": domain, "status": "available", "price": price }] }
+                })))
+                .mount(&server),
+        );
+        mount(&server, "register-domain", calls);
+        server
+    }
+
+    /// Serve each `(domain, price)` as available and the wallet `balance`.
+    fn multi_server(prices: &[(&str, i32)], balance: i32) -> MockServer {
+        let server = RT.block_on(MockServer::start());
+        for &(domain, price) in prices {
+            RT.block_on(
+                Mock::given(method("POST"))
+                    .and(body_partial_json(serde_json::json!({
+                        "method": "find-domains",
+                        "params": { "query": domain }
+                    })))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "result": { "domains": [{ "name": domain, "status": "available", "price": price }] }
+                    })))
+                    .mount(&server),
+            );
         }
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "get-balance" }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": { "balance": balance } })),
+                )
+                .mount(&server),
+        );
+        server
+    }
+
+    fn domains(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn multi_domain_quote_adds_up_every_price() {
+        let server = multi_server(&[("example.com", 15), ("example.org", 30)], 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let quote = quote_all(&client, &domains(&["example.com", "example.org"]), 2).unwrap();
+
+        assert_eq!(quote.domains[0].total_price, 30);
+        assert_eq!(quote.domains[1].total_price, 60);
+        assert_eq!(quote.total_price, 90);
+    }
+
+    #[test]
+    fn duplicate_domains_are_rejected_before_quoting() {
+        let server = RT.block_on(MockServer::start());
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let result = quote_all(&client, &domains(&["example.com", "Example.com"]), 1);
+
+        assert!(
+            matches!(result, Err(NjallaError::Input { message }) if message.contains("more than once"))
+        );
+        assert!(RT
+            .block_on(server.received_requests())
+            .is_some_and(|requests| requests.is_empty()));
+    }
+
+    #[test]
+    fn multi_domain_registration_needs_the_combined_total() {
+        let server = multi_server(&[("example.com", 15), ("example.org", 30)], 44);
+        mount(&server, "register-domain", 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let path = std::env::temp_dir().join("njalla-register-unused.json");
+
+        let result = register_many(
+            &client,
+            &domains(&["example.com", "example.org"]),
+            1,
+            &Approval::Confirmed,
+            false,
+            300,
+            ErrorMode::ContinueOnError,
+            &path,
+        );
+
+        assert!(
+            matches!(result, Err(NjallaError::Api { message }) if message.contains("costs 45 €"))
+        );
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn multi_domain_registration_reports_partial_failure() {
+        let names = ["example.com", "example.net", "example.org"];
+        let server = multi_server(&[(names[0], 15), (names[1], 15), (names[2], 15)], 100);
+        for domain in names {
+            let response = if domain == "example.net" {
+                serde_json::json!({ "error": { "message": "Registry unavailable" } })
+            } else {
+                serde_json::json!({ "result": { "task": format!("task-{domain}") } })
+            };
+            RT.block_on(
+                Mock::given(method("POST"))
+                    .and(body_partial_json(serde_json::json!({
+                        "method": "register-domain",
+                        "params": { "domain": domain }
+                    })))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(response))
+                    .expect(1)
+                    .mount(&server),
+            );
+        }
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let log_path =
+            std::env::temp_dir().join(format!("njalla-register-many-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&log_path);
+
+        let output = register_many(
+            &client,
+            &domains(&names),
+            1,
+            &Approval::Confirmed,
+            false,
+            300,
+            ErrorMode::ContinueOnError,
+            &log_path,
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&log_path);
+
+        RT.block_on(server.verify());
+        let CommandOutput::Failed(
+            text,
+            NjallaError::BatchFailed {
+                failed: 1,
+                total: 3,
+            },
+        ) = output
+        else {
+            panic!("one registration should fail: {output:?}");
+        };
+        let result: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(result["quote"]["total_price"], 45);
+        assert_eq!(result["summary"]["succeeded"], 2);
+        assert_eq!(result["summary"]["items"][1]["status"], "failed");
+        assert_eq!(
+            result["summary"]["items"][1]["error"],
+            "API error: Registry unavailable"
+        );
+        assert_eq!(result["tasks"].as_array().unwrap().len(), 2);
+        assert_eq!(result["tasks"][1]["task_id"], "task-example.org");
+    }
+
+    #[test]
+    fn reservation_token_round_trips() {
+        let token = reservation_token("example.com", 15, 2);
+        assert_eq!(quoted_price(&token, "example.com", 2).unwrap(), 15);
+        assert_eq!(quoted_price(&token, "EXAMPLE.com", 2).unwrap(), 15);
+
+        assert!(matches!(
+            quoted_price(&token, "example.org", 2),
+            Err(NjallaError::Input { .. })
+        ));
+        assert!(matches!(
+            quoted_price(&token.replacen("15", "5", 1), "example.com", 2),
+            Err(NjallaError::Input { .. })
+        ));
+        assert!(matches!(
+            quoted_price(&token, "example.com", 1),
+            Err(NjallaError::Input { message }) if message.contains("--years 2")
+        ));
+        assert!(quoted_price("garbage", "example.com", 2).is_err());
+    }
+
+    #[test]
+    fn assume_yes_accepts_non_empty_values_other_than_zero() {
+        assert!(assume_yes(Some("1")));
+        assert!(assume_yes(Some("true")));
+        assert!(!assume_yes(Some("0")));
+        assert!(!assume_yes(Some("")));
+        assert!(!assume_yes(None));
+    }
+
+    #[test]
+    fn assume_yes_registers_without_prompting() {
+        let server = priced_server("example.com", 15, 1);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let log_path = std::env::temp_dir().join(format!(
+            "njalla-register-assume-yes-{}.json",
+            std::process::id()
+        ));
+        let approval = Approval::from_flags(assume_yes(Some("1")), false, None);
+
+        let output = register(
+            &client,
+            "example.com",
+            1,
+            &approval,
+            false,
+            300,
+            false,
+            &log_path,
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&log_path);
+
+        RT.block_on(server.verify());
+        assert_eq!(approval, Approval::Confirmed);
+        let result: serde_json::Value = serde_json::from_str(output.text()).unwrap();
+        assert_eq!(result["task_id"], "task-1");
+    }
+
+    #[test]
+    fn reserve_quotes_without_registering() {
+        let server = priced_server("example.com", 15, 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let path = std::env::temp_dir().join("njalla-register-reserve-unused.json");
+
+        let output = register(
+            &client,
+            "example.com",
+            2,
+            &Approval::Reserve,
+            false,
+            300,
+            false,
+            &path,
+        )
+        .unwrap();
+
+        RT.block_on(server.verify());
+        let result: serde_json::Value = serde_json::from_str(output.text()).unwrap();
+        assert_eq!(result["total_price"], 30);
+        assert_eq!(
+            result["confirm_token"],
+            reservation_token("example.com", 15, 2)
+        );
+    }
+
+    #[test]
+    fn confirm_token_refuses_price_drift() {
+        let server = priced_server("example.com", 18, 0);
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let path = std::env::temp_dir().join("njalla-register-drift-unused.json");
+        let approval = Approval::Token(reservation_token("example.com", 15, 1));
+
+        let result = register(
+            &client,
+            "example.com",
+            1,
+            &approval,
+            false,
+            300,
+            false,
+            &path,
+        );
+
+        assert!(matches!(
+            result,
+            Err(NjallaError::PriceChanged {
+                quoted: 15,
+                current: 18,
+                ..
+            })
+        ));
+        RT.block_on(server.verify());
+    }
+
+    #[test]
+    fn wait_emits_a_line_per_poll_then_the_result() {
+        let server = RT.block_on(MockServer::start());
+        let check_task = |status: &str| {
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "check-task" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({ "result": { "id": "task-1", "status": status } }),
+                ))
+        };
+        RT.block_on(check_task("pending").up_to_n_times(2).mount(&server));
+        RT.block_on(check_task("completed").mount(&server));
+        RT.block_on(get_domain(Some("active")).mount(&server));
+        let client = NjallaClient::with_base_url("token", &server.uri());
+        let path = std::env::temp_dir().join("njalla-register-wait-unused.json");
+
+        let mut out = Vec::new();
+        let result = wait_for_task(
+            &client,
+            "example.com",
+            "task-1",
+            Duration::from_secs(30),
+            Duration::ZERO,
+            &path,
+            &mut out,
+        )
+        .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["status"], "pending");
+        assert_eq!(lines[2]["status"], "completed");
+        assert!(lines[2]["elapsed_s"].is_u64());
+
+        let CommandOutput::Text(result) = result else {
+            panic!("completed task should not fail: {result:?}");
+        };
+        assert!(!result.contains('\n'), "{result}");
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["domain"], "example.com");
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["domain_status"], "active");
+    }
+
+    /// `get-domain` answering with the domain in `status`, or "not found".
+    fn get_domain(status: Option<&str>) -> Mock {
+        let body = match status {
+            Some(status) => serde_json::json!({
+                "result": { "name": "example.com", "status": status }
+            }),
+            None => serde_json::json!({ "error": { "message": "Domain not found" } }),
+        };
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "get-domain" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    }
+
+    #[test]
+    fn completed_task_waits_for_domain_to_appear() {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(get_domain(None).up_to_n_times(2).expect(2).mount(&server));
+        RT.block_on(get_domain(Some("Active")).expect(1).mount(&server));
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let status = await_domain(&client, "example.com", 5, Duration::ZERO);
+
+        RT.block_on(server.verify());
+        assert_eq!(status.as_deref(), Some("Active"));
+    }
+
+    #[test]
+    fn domain_lookup_gives_up_after_bounded_attempts() {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(get_domain(None).expect(3).mount(&server));
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let status = await_domain(&client, "example.com", 3, Duration::ZERO);
+
+        RT.block_on(server.verify());
+        assert_eq!(status, None);
+    }
+
+    #[test]
+    fn domain_lookup_treats_any_failure_as_not_yet() {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": "get-domain" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+                .up_to_n_times(1)
+                .mount(&server),
+        );
+        RT.block_on(get_domain(Some("active")).mount(&server));
+        let client = NjallaClient::with_base_url("token", &server.uri());
+
+        let status = await_domain(&client, "example.com", 3, Duration::ZERO);
+
+        assert_eq!(status.as_deref(), Some("active"));
+    }
+
+    #[test]
+    fn domain_lookup_past_the_deadline_gives_up_quietly() {
+        let server = RT.block_on(MockServer::start());
+        RT.block_on(get_domain(Some("active")).expect(0).mount(&server));
+        let options = ClientOptions {
+            base_url: Some(server.uri()),
+            deadline: Some(crate::client::Deadline::after_secs(0)),
+            ..ClientOptions::default()
+        };
+        let client = NjallaClient::with_token("token", &options);
+
+        let status = await_domain(&client, "example.com", 3, Duration::ZERO);
+
+        RT.block_on(server.verify());
+        assert_eq!(status, None);
     }
 }
@@ -1,18 +1,242 @@
 //! Search domains command.
 
-use crate::client::NjallaClient;
-use crate::error::Result;
-use crate::output::format_market_domains;
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::register::{self, Approval};
+use crate::commands::CommandOutput;
+use crate::error::{NjallaError, Result};
+use crate::filter::{retain_matching, FieldFilter};
+use crate::output::{
+    format_market_domains, format_market_domains_affordable, format_market_domains_with_summary,
+};
+use crate::task_log::TASK_LOG_FILE;
+use crate::types::MarketDomain;
+use std::path::Path;
+
+/// How search results must match the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MatchMode {
+    /// Name equals the query.
+    Exact,
+    /// Name contains the query.
+    Contains,
+    /// Name starts with the query.
+    Prefix,
+}
 
 /// Run the search command.
 ///
-/// Searches for available domains matching the query.
-pub fn run(query: &str, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Searches for available domains matching the query. With `match_mode`
+/// and `only`, results the API returned are filtered client-side. With
+/// `summary`, the results are wrapped in an object with price totals. With
+/// `affordable`, the wallet balance is fetched and each result is flagged
+/// with whether it covers a year's registration.
+pub fn run(
+    query: &str,
+    match_mode: Option<MatchMode>,
+    only: &[FieldFilter],
+    summary: bool,
+    affordable: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let mut results = client.find_domains(query)?;
+    if let Some(mode) = match_mode {
+        results.retain(|d| matches_query(d, query, mode));
+    }
+    retain_matching(&mut results, only)?;
+    let formatted = if affordable {
+        let balance = client.get_balance()?.balance;
+        format_market_domains_affordable(&results, balance)?
+    } else if summary {
+        format_market_domains_with_summary(&results)?
+    } else {
+        format_market_domains(&results)?
+    };
+    Ok(CommandOutput::Text(formatted))
+}
+
+/// Run `search --register-first`.
+///
+/// Picks the first available result that matches `match_mode` and is within
+/// `max_price` and `suffix`, reports the choice on stderr, and registers it
+/// through the normal register flow (price check, prompt unless `confirm`).
+#[allow(clippy::too_many_arguments)]
+pub fn run_register_first(
+    query: &str,
+    match_mode: Option<MatchMode>,
+    max_price: Option<i32>,
+    suffix: Option<&str>,
+    years: i32,
+    confirm: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    let mut results = client.find_domains(query)?;
+    if let Some(mode) = match_mode {
+        results.retain(|d| matches_query(d, query, mode));
+    }
+    let Some(chosen) = first_available(&results, max_price, suffix) else {
+        let constraints: String = [
+            max_price.map(|max| format!(" at or under {max} € per year")),
+            suffix.map(|s| format!(" ending in .{}", bare_suffix(s))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        return Err(NjallaError::DomainNotAvailable(format!(
+            "no search result for '{query}' is available{constraints}"
+        )));
+    };
+
+    eprintln!(
+        "Selected {} ({} € per year) from {} result(s)",
+        chosen.name,
+        chosen.price,
+        results.len()
+    );
+    let approval = if confirm {
+        Approval::Confirmed
+    } else {
+        Approval::Prompt
+    };
+    register::register(
+        &client,
+        &chosen.name,
+        years,
+        &approval,
+        false,
+        0,
+        false,
+        Path::new(TASK_LOG_FILE),
+    )
+}
+
+/// The first available result costing at most `max_price` per year and
+/// ending in `suffix` (e.g. `.com` or `com`), in the API's order.
+fn first_available<'a>(
+    results: &'a [MarketDomain],
+    max_price: Option<i32>,
+    suffix: Option<&str>,
+) -> Option<&'a MarketDomain> {
+    let suffix = suffix.map(|s| format!(".{}", bare_suffix(s)));
+    results.iter().find(|d| {
+        d.status == "available"
+            && max_price.is_none_or(|max| d.price <= max)
+            && suffix
+                .as_deref()
+                .is_none_or(|s| d.name.to_ascii_lowercase().ends_with(s))
+    })
+}
+
+/// A TLD suffix without its leading dot, lowercased.
+fn bare_suffix(suffix: &str) -> String {
+    suffix.trim().trim_start_matches('.').to_ascii_lowercase()
+}
+
+/// Whether a result matches `query` under `mode`, ignoring case.
+///
+/// A query with a dot (`example.com`) is compared with the full name; a bare
+/// keyword (`example`) is compared with the label before the TLD.
+fn matches_query(domain: &MarketDomain, query: &str, mode: MatchMode) -> bool {
+    let name = domain.name.to_ascii_lowercase();
+    let query = query.trim().to_ascii_lowercase();
+    let target = if query.contains('.') {
+        name.as_str()
+    } else {
+        name.split('.').next().unwrap_or_default()
+    };
+
+    match mode {
+        MatchMode::Exact => target == query,
+        MatchMode::Contains => target.contains(&query),
+        MatchMode::Prefix => target.starts_with(&query),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<MarketDomain> {
+        [
+            "bitcoin.com",
+            "bitcoin.net",
+            "bitcoins.org",
+            "mybitcoin.io",
+            "bit.coin",
+        ]
+        .into_iter()
+        .map(|name| MarketDomain {
+            name: name.to_string(),
+            status: "available".to_string(),
+            price: 15,
+        })
+        .collect()
+    }
+
+    fn names(query: &str, mode: MatchMode) -> Vec<String> {
+        results()
+            .into_iter()
+            .filter(|d| matches_query(d, query, mode))
+            .map(|d| d.name)
+            .collect()
+    }
+
+    fn market(entries: &[(&str, &str, i32)]) -> Vec<MarketDomain> {
+        entries
+            .iter()
+            .map(|&(name, status, price)| MarketDomain {
+                name: name.to_string(),
+                status: status.to_string(),
+                price,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_available_skips_taken_and_overpriced_results() {
+        let results = market(&[
+            ("bitcoin.com", "taken", 15),
+            ("bitcoin.io", "available", 45),
+            ("bitcoin.net", "available", 15),
+            ("bitcoin.org", "available", 12),
+        ]);
+        let name = |max_price, suffix| {
+            first_available(&results, max_price, suffix).map(|d| d.name.as_str())
+        };
+
+        assert_eq!(name(None, None), Some("bitcoin.io"));
+        assert_eq!(name(Some(20), None), Some("bitcoin.net"));
+        assert_eq!(name(Some(20), Some(".org")), Some("bitcoin.org"));
+        assert_eq!(name(None, Some("NET")), Some("bitcoin.net"));
+        assert_eq!(name(None, Some(".com")), None);
+        assert_eq!(name(Some(10), None), None);
+    }
+
+    #[test]
+    fn exact_matches_label_or_full_name() {
+        assert_eq!(
+            names("bitcoin", MatchMode::Exact),
+            ["bitcoin.com", "bitcoin.net"]
+        );
+        assert_eq!(names("Bitcoin.NET", MatchMode::Exact), ["bitcoin.net"]);
+    }
 
-    let results = client.find_domains(query)?;
-    let formatted = format_market_domains(&results)?;
-    println!("{formatted}");
+    #[test]
+    fn contains_matches_substrings_of_the_label() {
+        assert_eq!(
+            names("bitcoin", MatchMode::Contains),
+            ["bitcoin.com", "bitcoin.net", "bitcoins.org", "mybitcoin.io"]
+        );
+    }
 
-    Ok(())
+    #[test]
+    fn prefix_matches_start_of_the_label() {
+        assert_eq!(
+            names("bitcoin", MatchMode::Prefix),
+            ["bitcoin.com", "bitcoin.net", "bitcoins.org"]
+        );
+    }
 }
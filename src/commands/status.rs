@@ -1,24 +1,113 @@
 //! Domain status command.
 
-use crate::client::NjallaClient;
-use crate::error::Result;
-use crate::output::format_domain_status;
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::error::{NjallaError, Result};
+use crate::output::{format_domain_status, to_json};
+use crate::types::{normalize_record_name, ApiMethod, GetDomainResult, RecordsResult};
 
 /// Run the status command.
 ///
-/// Shows detailed status for a domain.
-pub fn run(domain: &str, show_dns: bool, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
-
-    let info = client.get_domain(domain)?;
-    let records = if show_dns {
-        Some(client.list_records(domain)?)
-    } else {
-        None
+/// Shows detailed status for a domain. With `show_dns`, the domain and its
+/// records are fetched concurrently, within `--max-concurrency`. With `raw`,
+/// the `get-domain` result is printed unchanged, in the API's field order.
+pub fn run(
+    domain: &str,
+    show_dns: bool,
+    raw: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
+
+    if raw {
+        return Ok(CommandOutput::Text(to_json(
+            &client.get_domain_raw(domain)?,
+        )?));
+    }
+
+    if !show_dns {
+        let info = client.get_domain(domain)?;
+        return Ok(CommandOutput::Text(format_domain_status(&info, None)?));
+    }
+
+    let params = serde_json::json!({ "domain": domain });
+    let results = client.batch(&[
+        (ApiMethod::GetDomain, params.clone()),
+        (ApiMethod::ListRecords, params),
+    ]);
+    let Ok([info, records]) = <[_; 2]>::try_from(results) else {
+        return Err(NjallaError::Api {
+            message: "batch did not return one result per call".to_string(),
+        });
     };
+    let info = serde_json::from_value::<GetDomainResult>(info?)?.into_domain();
+    let mut records: RecordsResult = serde_json::from_value(records?)?;
+    // Named as `list_records` names them, so "" and "example.com." show as "@".
+    for record in &mut records.records {
+        record.name = normalize_record_name(&record.name, domain);
+    }
+
+    let formatted = format_domain_status(&info, Some(&records.records))?;
+    Ok(CommandOutput::Text(formatted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+
+    fn mount(server: &MockServer, api_method: &str, result: &serde_json::Value) {
+        RT.block_on(
+            Mock::given(method("POST"))
+                .and(body_partial_json(
+                    serde_json::json!({ "method": api_method }),
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "result": result })),
+                )
+                .mount(server),
+        );
+    }
+
+    #[test]
+    fn dns_records_are_named_like_dns_list() {
+        let server = RT.block_on(MockServer::start());
+        mount(
+            &server,
+            "get-domain",
+            &serde_json::json!({ "name": "example.com", "status": "active" }),
+        );
+        mount(
+            &server,
+            "list-records",
+            &serde_json::json!({ "records": [
+                { "id": "rec1", "name": "", "type": "A", "content": "192.0.2.1" },
+                { "id": "rec2", "name": "www.example.com.", "type": "A", "content": "192.0.2.2" }
+            ] }),
+        );
+        let options = ClientOptions {
+            token: Some("token".to_string()),
+            base_url: Some(server.uri()),
+            ..ClientOptions::default()
+        };
 
-    let formatted = format_domain_status(&info, records.as_deref())?;
-    println!("{formatted}");
+        let output = run("example.com", true, false, &options).unwrap();
 
-    Ok(())
+        let CommandOutput::Text(text) = output else {
+            panic!("status should not fail: {output:?}");
+        };
+        let status: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(status["dns_records"][0]["name"], "@");
+        assert_eq!(status["dns_records"][1]["name"], "www");
+    }
 }
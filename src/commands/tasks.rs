@@ -0,0 +1,41 @@
+//! Tasks command: refresh tasks the CLI has submitted.
+
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::error::Result;
+use crate::output::to_json;
+use crate::task_log::{is_finished, TaskLog, TASK_LOG_FILE};
+use std::path::Path;
+
+/// Run the tasks command.
+///
+/// Checks every logged task, prints its current status, and drops tasks
+/// that have completed or failed from the log.
+pub fn run(options: &ClientOptions) -> Result<CommandOutput> {
+    let path = Path::new(TASK_LOG_FILE);
+    let mut log = TaskLog::load(path)?;
+    if log.tasks.is_empty() {
+        return Ok(CommandOutput::Text(to_json(&log.tasks)?));
+    }
+
+    let client = NjallaClient::new(options)?;
+    let mut report = Vec::with_capacity(log.tasks.len());
+    for task in log.tasks.clone() {
+        let status = match client.check_task(&task.id) {
+            Ok(status) => status.status,
+            Err(e) => format!("unknown ({e})"),
+        };
+        if is_finished(&status) {
+            log.prune(&task.id);
+        }
+        report.push(serde_json::json!({
+            "id": task.id,
+            "kind": task.kind,
+            "target": task.target,
+            "status": status,
+        }));
+    }
+
+    log.save(path)?;
+    Ok(CommandOutput::Text(to_json(&report)?))
+}
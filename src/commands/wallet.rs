@@ -1,58 +1,251 @@
 //! Wallet management commands.
 
-use crate::client::NjallaClient;
-use crate::error::Result;
-use crate::output::{format_payment, format_transactions, format_wallet_balance};
-use crate::types::PaymentMethod;
+use crate::balance_log::{self, BalanceLog, BalancePoint, BALANCE_LOG_FILE};
+use crate::client::{ClientOptions, NjallaClient};
+use crate::commands::CommandOutput;
+use crate::config::Config;
+use crate::error::{NjallaError, Result};
+use crate::export;
+use crate::filter::{retain_matching, FieldFilter};
+use crate::output::{
+    format_balance_history, format_exact_btc, format_payment, format_transactions,
+    format_wallet_balance, format_wallet_balance_affordable,
+};
+use crate::types::{Payment, PaymentMethod, TransactionStatus};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Run the balance command.
 ///
-/// Shows the current wallet balance.
-pub fn run_balance(debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Shows the current wallet balance, and records it in the balance ledger
+/// when `track_balance` is set in the config. With `affordable_at`, also
+/// shows how many domains at that yearly price the balance pays for.
+pub fn run_balance(affordable_at: Option<i32>, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
     let balance = client.get_balance()?;
-    let formatted = format_wallet_balance(&balance)?;
-    println!("{formatted}");
+    if Config::load()?.track_balance == Some(true) {
+        let point = BalancePoint {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            balance: balance.balance,
+        };
+        if let Err(e) = balance_log::record(Path::new(BALANCE_LOG_FILE), point) {
+            eprintln!("Warning: could not record balance: {e}");
+        }
+    }
+    let formatted = match affordable_at {
+        Some(price) => format_wallet_balance_affordable(&balance, price)?,
+        None => format_wallet_balance(&balance)?,
+    };
+    Ok(CommandOutput::Text(formatted))
+}
 
-    Ok(())
+/// Run the history command.
+///
+/// Shows the balances recorded by `wallet balance`, without calling the API.
+pub fn run_history() -> Result<CommandOutput> {
+    let log = BalanceLog::load(Path::new(BALANCE_LOG_FILE))?;
+    if log.points.is_empty() && Config::load()?.track_balance != Some(true) {
+        eprintln!("No balances recorded; set track_balance = true in config.toml to start");
+    }
+    let formatted = format_balance_history(&log.points)?;
+    Ok(CommandOutput::Text(formatted))
 }
 
 /// Run the add-payment command.
 ///
-/// Creates a new payment to refill the wallet.
-pub fn run_add_payment(amount: i32, via: PaymentMethod, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Creates a new payment to refill the wallet. With `exact_btc`, only the
+/// BTC amount is printed. With `open`, the payment URI is handed to the
+/// desktop's default handler (see [`open_payment_uri`]).
+pub fn run_add_payment(
+    amount: i32,
+    via: PaymentMethod,
+    exact_btc: bool,
+    open: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
     let payment = client.add_payment(amount, via)?;
-    let formatted = format_payment(&payment)?;
-    println!("{formatted}");
-
-    Ok(())
+    if open {
+        open_payment_uri(&payment, has_desktop(), launch_uri_handler);
+    }
+    let formatted = if exact_btc {
+        format_exact_btc(&payment)?
+    } else {
+        format_payment(&payment)?
+    };
+    Ok(CommandOutput::Text(formatted))
 }
 
 /// Run the get-payment command.
 ///
-/// Gets details about a specific payment.
-pub fn run_get_payment(id: &str, debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Gets details about a specific payment, optionally opening its URI as
+/// `add-payment --open` does. An unknown id fails with
+/// `NjallaError::NotFound`, whose message points at `wallet transactions`.
+pub fn run_get_payment(id: &str, open: bool, options: &ClientOptions) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
     let payment = client.get_payment(id)?;
+    if open {
+        open_payment_uri(&payment, has_desktop(), launch_uri_handler);
+    }
     let formatted = format_payment(&payment)?;
-    println!("{formatted}");
+    Ok(CommandOutput::Text(formatted))
+}
+
+/// Open the payment's URI (e.g. `bitcoin:...`) with `launch`.
+///
+/// Nothing is launched without a desktop or when the payment has no URI; if
+/// the handler cannot be started, the URI is printed on stderr to pay by
+/// hand. Returns whether the handler was started.
+fn open_payment_uri<F>(payment: &Payment, desktop: bool, launch: F) -> bool
+where
+    F: FnOnce(&str) -> Result<()>,
+{
+    let Some(uri) = payment.uri.as_deref().filter(|uri| !uri.is_empty()) else {
+        eprintln!("Payment has no URI to open");
+        return false;
+    };
+    if !desktop {
+        eprintln!("No desktop to open the payment in; pay to {uri}");
+        return false;
+    }
+    match launch(uri) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{e}; pay to {uri}");
+            false
+        }
+    }
+}
+
+/// Whether a URI handler can be shown: stdout is a terminal and, outside
+/// macOS and Windows, an X11 or Wayland display is set.
+fn has_desktop() -> bool {
+    std::io::stdout().is_terminal()
+        && (cfg!(any(target_os = "macos", windows))
+            || ["DISPLAY", "WAYLAND_DISPLAY"]
+                .iter()
+                .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty())))
+}
 
-    Ok(())
+/// Hand `uri` to the platform's default handler and wait for it to return.
+///
+/// On Windows this goes through `url.dll` rather than `cmd /C start`, since
+/// `cmd` would treat the `&` between URI parameters as a command separator.
+fn launch_uri_handler(uri: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = command
+        .arg(uri)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| NjallaError::Config {
+            message: format!("Failed to open payment URI: {e}"),
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(NjallaError::Config {
+            message: format!("Payment URI handler exited with {status}"),
+        })
+    }
 }
 
 /// Run the transactions command.
 ///
-/// Lists transactions from the last 90 days.
-pub fn run_transactions(debug: bool) -> Result<()> {
-    let client = NjallaClient::new(debug)?;
+/// Lists transactions from the last 90 days, optionally only those in the
+/// given classified state and passing the `--only` filters. With
+/// `accounting`, they are printed as CSV for bookkeeping software instead of
+/// JSON.
+pub fn run_transactions(
+    status: Option<TransactionStatus>,
+    only: &[FieldFilter],
+    accounting: bool,
+    options: &ClientOptions,
+) -> Result<CommandOutput> {
+    let client = NjallaClient::new(options)?;
 
-    let transactions = client.list_transactions()?;
+    let mut transactions = client.list_transactions()?;
+    if let Some(status) = status {
+        transactions.retain(|tx| tx.classify() == status);
+    }
+    retain_matching(&mut transactions, only)?;
+    if accounting {
+        let csv = export::accounting::export(&transactions);
+        // Printing adds the final newline.
+        let output = csv.strip_suffix('\n').unwrap_or(&csv);
+        return Ok(CommandOutput::Text(output.to_string()));
+    }
     let formatted = format_transactions(&transactions)?;
-    println!("{formatted}");
+    Ok(CommandOutput::Text(formatted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn payment(uri: Option<&str>) -> Payment {
+        serde_json::from_value(serde_json::json!({
+            "id": "pay123",
+            "amount": 15,
+            "uri": uri,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn open_launches_handler_for_payment_uri() {
+        let launched = RefCell::new(Vec::new());
+        let opened = open_payment_uri(
+            &payment(Some("bitcoin:bc1qexample?amount=0.0003")),
+            true,
+            |uri| {
+                launched.borrow_mut().push(uri.to_string());
+                Ok(())
+            },
+        );
+
+        assert!(opened);
+        assert_eq!(launched.into_inner(), ["bitcoin:bc1qexample?amount=0.0003"]);
+    }
+
+    #[test]
+    fn open_is_a_no_op_without_uri_or_desktop() {
+        let launch = |_: &str| -> Result<()> { panic!("handler must not run") };
+
+        assert!(!open_payment_uri(&payment(None), true, launch));
+        assert!(!open_payment_uri(&payment(Some("")), true, launch));
+        assert!(!open_payment_uri(
+            &payment(Some("bitcoin:bc1qexample")),
+            false,
+            launch
+        ));
+    }
+
+    #[test]
+    fn failed_handler_is_not_an_error() {
+        let opened = open_payment_uri(&payment(Some("bitcoin:bc1qexample")), true, |_| {
+            Err(NjallaError::Config {
+                message: "Failed to open payment URI: not found".to_string(),
+            })
+        });
 
-    Ok(())
+        assert!(!opened);
+    }
 }
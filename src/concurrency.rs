@@ -0,0 +1,93 @@
+//! Bounded concurrency for fan-out operations.
+//!
+//! Every command that sends several requests at once (`status --dns`,
+//! `doctor`, `NjallaClient::batch`) goes through [`fan_out`], so the global
+//! `--max-concurrency` limit applies to all of them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Default number of concurrent API requests for fan-out operations.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Run `f` over `items` on up to `workers` threads.
+///
+/// Results are returned in the same order as `items`, regardless of which
+/// worker finished first. A `workers` of 0 is treated as 1.
+pub fn fan_out<T, R, F>(items: &[T], workers: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> =
+        Mutex::new(std::iter::repeat_with(|| None).take(items.len()).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap_or_else(std::sync::PoisonError::into_inner)[index] =
+                    Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Highest number of `f` calls in flight at once.
+    fn max_overlap(workers: usize) -> usize {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let items: Vec<u32> = (0..8).collect();
+        fan_out(&items, workers, |_| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+        peak.into_inner()
+    }
+
+    #[test]
+    fn fan_out_preserves_order() {
+        let items: Vec<u64> = (0..20).collect();
+        let results = fan_out(&items, 4, |n| {
+            thread::sleep(Duration::from_millis(20 - n));
+            n * 2
+        });
+        assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fan_out_respects_worker_limit() {
+        assert_eq!(max_overlap(1), 1);
+        assert_eq!(max_overlap(0), 1);
+        let peak = max_overlap(4);
+        assert!(peak > 1 && peak <= 4, "peak was {peak}");
+    }
+
+    #[test]
+    fn fan_out_handles_empty_input() {
+        let items: Vec<u32> = Vec::new();
+        let results: Vec<u32> = fan_out(&items, 4, |n| *n);
+        assert!(results.is_empty());
+    }
+}
@@ -1,81 +1,391 @@
 //! Configuration management for njalla-cli.
 //!
 //! Configuration is loaded from (in order of precedence):
-//! 1. Environment variable `NJALLA_API_TOKEN`
+//! 1. Environment variables `NJALLA_API_TOKEN`, `NJALLA_BASE_URL`,
+//!    `NJALLA_TIMEOUT`, `NJALLA_RETRIES` and `NJALLA_DEFAULT_TTL`
 //! 2. Config file at `./config.toml` (project directory)
 //!
+//! CLI flags override both, as does a token piped with `--token-stdin`
+//! (see [`read_token`]). Empty variables are ignored.
+//!
 //! # Config File Format
 //!
 //! ```toml
 //! api_token = "your-api-token-here"
+//! default_ttl = 3600  # optional, used when a record has no explicit TTL
+//!
+//! # Optional endpoint settings; CLI flags override these
+//! base_url = "https://staging.example/api/1/"
+//! timeout = 60  # request timeout in seconds
+//! retries = 2   # retries after a failed connection or rate limit
+//!
+//! # Optional: keep a local history of `wallet balance` results
+//! track_balance = true
+//!
+//! # Optional TTL policy for `dns add` and `dns edit`
+//! dns_ttl_min = 300
+//! dns_ttl_max = 86400
 //! ```
+//!
+//! `njalla config schema` prints every key with its documentation, rendered
+//! from [`CONFIG_KEYS`] by [`annotated_template`].
 
 use crate::error::{NjallaError, Result};
-use serde::Deserialize;
+use crate::json_file;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Write};
 use std::fs;
-use std::path::PathBuf;
+use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
 
 /// Configuration file name.
-const CONFIG_FILE: &str = "config.toml";
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Contents written by `config --init` and `config edit` for a new file.
+pub const CONFIG_TEMPLATE: &str = r#"# Njalla CLI Configuration
+# Get your API token from: https://njal.la → Settings → API
+
+api_token = ""
+"#;
+
+/// Shown when `api_token` is still the empty string from [`CONFIG_TEMPLATE`].
+pub const PLACEHOLDER_TOKEN_MESSAGE: &str =
+    "API token is still the placeholder; edit config.toml and set api_token";
+
+/// One `config.toml` key, as documented by `njalla config schema`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKey {
+    /// Key name, matching the [`Config`] field.
+    pub name: &'static str,
+    /// Example value, as TOML.
+    pub example: &'static str,
+    /// Environment variable that overrides the key, if any.
+    pub env: Option<&'static str>,
+    /// What the key does, one comment line per entry.
+    pub doc: &'static [&'static str],
+}
+
+/// Every key [`Config`] reads, in the order `config schema` prints them.
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "api_token",
+        example: "\"your-api-token-here\"",
+        env: Some("NJALLA_API_TOKEN"),
+        doc: &[
+            "Njalla API token. Get it from https://njal.la → Settings → API.",
+            "A blank token counts as missing.",
+        ],
+    },
+    ConfigKey {
+        name: "default_ttl",
+        example: "3600",
+        env: Some("NJALLA_DEFAULT_TTL"),
+        doc: &["TTL in seconds for records added without --ttl."],
+    },
+    ConfigKey {
+        name: "base_url",
+        example: "\"https://staging.example/api/1/\"",
+        env: Some("NJALLA_BASE_URL"),
+        doc: &["API endpoint, e.g. for a staging environment."],
+    },
+    ConfigKey {
+        name: "timeout",
+        example: "60",
+        env: Some("NJALLA_TIMEOUT"),
+        doc: &["Request timeout in seconds."],
+    },
+    ConfigKey {
+        name: "retries",
+        example: "2",
+        env: Some("NJALLA_RETRIES"),
+        doc: &["Retries after a failed connection or rate limit."],
+    },
+    ConfigKey {
+        name: "track_balance",
+        example: "true",
+        env: None,
+        doc: &["Record every `wallet balance` result in ./balance.json."],
+    },
+    ConfigKey {
+        name: "dns_ttl_min",
+        example: "300",
+        env: None,
+        doc: &[
+            "Lowest TTL `dns add` and `dns edit` accept.",
+            "--dns-ttl-min can only raise it.",
+        ],
+    },
+    ConfigKey {
+        name: "dns_ttl_max",
+        example: "86400",
+        env: None,
+        doc: &[
+            "Highest TTL `dns add` and `dns edit` accept.",
+            "--dns-ttl-max can only lower it.",
+        ],
+    },
+];
+
+/// A `config.toml` documenting every key in [`CONFIG_KEYS`], each commented
+/// out with an example value.
+#[must_use]
+pub fn annotated_template() -> String {
+    let mut template = String::from(
+        "# Njalla CLI Configuration\n\
+         #\n\
+         # Every supported key, commented out with an example value. Uncomment\n\
+         # the ones you need. Environment variables override this file, and\n\
+         # CLI flags override both.\n",
+    );
+    for key in CONFIG_KEYS {
+        template.push('\n');
+        for line in key.doc {
+            let _ = writeln!(template, "# {line}");
+        }
+        if let Some(env) = key.env {
+            let _ = writeln!(template, "# Environment: {env}");
+        }
+        let _ = writeln!(template, "# {} = {}", key.name, key.example);
+    }
+    template
+}
 
 /// Configuration structure.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
     /// Njalla API token.
     pub api_token: Option<String>,
+
+    /// Zone-default TTL in seconds for records added without `--ttl`.
+    #[serde(default)]
+    pub default_ttl: Option<i32>,
+
+    /// API endpoint, e.g. for a staging environment.
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Request timeout in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Number of retries after a transport failure or transient API error.
+    #[serde(default)]
+    pub retries: Option<u32>,
+
+    /// Record every `wallet balance` result in the local balance ledger.
+    #[serde(default)]
+    pub track_balance: Option<bool>,
+
+    /// Lowest TTL `dns add` and `dns edit` accept.
+    #[serde(default)]
+    pub dns_ttl_min: Option<i32>,
+
+    /// Highest TTL `dns add` and `dns edit` accept.
+    #[serde(default)]
+    pub dns_ttl_max: Option<i32>,
 }
 
 impl Config {
     /// Load configuration from file and environment.
     ///
     /// Priority:
-    /// 1. `NJALLA_API_TOKEN` environment variable (highest)
+    /// 1. `NJALLA_*` environment variables (highest)
     /// 2. Config file `./config.toml` (project directory)
     ///
     /// # Errors
     ///
-    /// Returns an error if the config file exists but cannot be read or parsed.
+    /// Returns an error if the config file exists but cannot be read or parsed,
+    /// or if an environment variable holds an invalid value.
     pub fn load() -> Result<Self> {
-        let path = PathBuf::from(CONFIG_FILE);
-
         // Start with config file (if exists)
-        let mut config = if path.exists() {
-            let contents = fs::read_to_string(&path).map_err(|e| NjallaError::Config {
-                message: format!("Failed to read config file: {e}"),
-            })?;
-            toml::from_str(&contents).map_err(|e| NjallaError::Config {
-                message: format!("Failed to parse config file: {e}"),
-            })?
-        } else {
-            Self::default()
-        };
+        let mut config = Self::load_file(Path::new(CONFIG_FILE))?;
 
-        // Override with environment variable
-        if let Ok(token) = std::env::var("NJALLA_API_TOKEN") {
-            if !token.is_empty() {
-                config.api_token = Some(token);
-            }
-        }
+        // Override with environment variables
+        config.apply_env(|name| std::env::var(name).ok())?;
 
         Ok(config)
     }
 
-    /// Get the API token, returning an error if not configured.
+    /// Override each field with its `NJALLA_*` environment variable.
+    ///
+    /// `var` looks a variable up, so tests need not touch the process
+    /// environment.
     ///
     /// # Errors
     ///
-    /// Returns `NjallaError::MissingToken` if no API token is configured.
+    /// Returns an error if a numeric variable does not parse.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        env_override(&var, "NJALLA_API_TOKEN", &mut self.api_token)?;
+        env_override(&var, "NJALLA_BASE_URL", &mut self.base_url)?;
+        env_override(&var, "NJALLA_TIMEOUT", &mut self.timeout)?;
+        env_override(&var, "NJALLA_RETRIES", &mut self.retries)?;
+        env_override(&var, "NJALLA_DEFAULT_TTL", &mut self.default_ttl)
+    }
+
+    /// Load configuration from a file only, ignoring environment variables.
+    ///
+    /// Returns the default configuration if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|e| NjallaError::Config {
+            message: format!("Failed to read config file: {e}"),
+        })?;
+        toml::from_str(&contents).map_err(|e| NjallaError::Config {
+            message: format!("Failed to parse config file: {e}"),
+        })
+    }
+
+    /// Create `path` from [`CONFIG_TEMPLATE`] unless it already exists.
+    ///
+    /// Returns whether the file was created.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn init(path: &Path) -> Result<bool> {
+        if path.exists() {
+            return Ok(false);
+        }
+        fs::write(path, CONFIG_TEMPLATE).map_err(|e| NjallaError::Config {
+            message: format!("Failed to write config file: {e}"),
+        })?;
+        Ok(true)
+    }
+
+    /// Write configuration to a file, replacing its contents.
+    ///
+    /// The file is written through a temporary file renamed over it, so a
+    /// crash mid-write leaves the old file intact. Comments and layout of an
+    /// existing file are not kept: it is rewritten from the parsed values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing fails.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string(self).map_err(|e| NjallaError::Config {
+            message: format!("Failed to serialize config: {e}"),
+        })?;
+        json_file::write_atomically(path, &contents, "config file")
+    }
+
+    /// Get the API token, trimmed, returning an error if not configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NjallaError::MissingToken` if no API token is configured, or
+    /// it is empty or whitespace (such as the unedited template's `""`).
     pub fn api_token(&self) -> Result<&str> {
         self.api_token
             .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
             .ok_or(NjallaError::MissingToken)
     }
+
+    /// Whether `api_token` is set but blank, as [`CONFIG_TEMPLATE`] leaves it.
+    #[must_use]
+    pub fn has_placeholder_token(&self) -> bool {
+        self.api_token
+            .as_deref()
+            .is_some_and(|token| token.trim().is_empty())
+    }
+}
+
+/// Set `field` from the variable `name`, if it is set and not empty.
+fn env_override<T>(
+    var: impl Fn(&str) -> Option<String>,
+    name: &str,
+    field: &mut Option<T>,
+) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let Some(value) = var(name).filter(|v| !v.trim().is_empty()) else {
+        return Ok(());
+    };
+    let parsed = value.trim().parse().map_err(|e| NjallaError::Config {
+        message: format!("Invalid {name} '{value}': {e}"),
+    })?;
+    *field = Some(parsed);
+    Ok(())
+}
+
+/// Read an API token from the first line of `reader` (for `--token-stdin`).
+///
+/// # Errors
+///
+/// Returns an error if reading fails or the line is empty.
+pub fn read_token<R: BufRead>(mut reader: R) -> Result<String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| NjallaError::Input {
+            message: format!("Failed to read token from stdin: {e}"),
+        })?;
+    let token = line.trim();
+    if token.is_empty() {
+        return Err(NjallaError::Input {
+            message: "No token on stdin".to_string(),
+        });
+    }
+    Ok(token.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn annotated_template_parses_with_and_without_examples() {
+        let template = annotated_template();
+        let config: Config = toml::from_str(&template).unwrap();
+        assert!(config.api_token.is_none());
+
+        let uncommented: String = template
+            .lines()
+            .map(|line| {
+                let key = CONFIG_KEYS
+                    .iter()
+                    .any(|key| line.starts_with(&format!("# {} = ", key.name)));
+                if key {
+                    line.trim_start_matches("# ")
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let table: toml::Table = toml::from_str(&uncommented).unwrap();
+        assert_eq!(table.len(), CONFIG_KEYS.len());
+        let config: Config = toml::from_str(&uncommented).unwrap();
+        assert_eq!(config.api_token.as_deref(), Some("your-api-token-here"));
+        assert_eq!(config.dns_ttl_max, Some(86400));
+    }
+
+    #[test]
+    fn config_keys_cover_every_config_field() {
+        let fields = serde_json::to_value(Config::default()).unwrap();
+        let mut fields: Vec<&str> = fields
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut keys: Vec<&str> = CONFIG_KEYS.iter().map(|key| key.name).collect();
+        fields.sort_unstable();
+        keys.sort_unstable();
+        assert_eq!(keys, fields);
+    }
+
     #[test]
     fn default_config_has_no_token() {
         let config = Config::default();
@@ -86,13 +396,159 @@ mod tests {
     fn api_token_returns_error_when_missing() {
         let config = Config::default();
         assert!(matches!(config.api_token(), Err(NjallaError::MissingToken)));
+        assert!(!config.has_placeholder_token());
+    }
+
+    #[test]
+    fn blank_api_token_is_missing() {
+        for token in ["", "  \t\n"] {
+            let config = Config {
+                api_token: Some(token.to_string()),
+                ..Config::default()
+            };
+            assert!(matches!(config.api_token(), Err(NjallaError::MissingToken)));
+            assert!(config.has_placeholder_token());
+        }
+
+        let padded = Config {
+            api_token: Some(" token \n".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(padded.api_token().unwrap(), "token");
+    }
+
+    #[test]
+    fn unedited_template_has_placeholder_token() {
+        let config: Config = toml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert!(config.has_placeholder_token());
     }
 
     #[test]
     fn api_token_returns_token_when_present() {
         let config = Config {
             api_token: Some("test-token".to_string()),
+            ..Config::default()
         };
         assert_eq!(config.api_token().unwrap(), "test-token");
     }
+
+    #[test]
+    fn save_then_load_file_round_trips() {
+        let path = std::env::temp_dir().join(format!(
+            "njalla-config-roundtrip-{}.toml",
+            std::process::id()
+        ));
+        let config = Config {
+            api_token: Some("saved-token".to_string()),
+            default_ttl: Some(600),
+            ..Config::default()
+        };
+        config.save(&path).unwrap();
+
+        let loaded = Config::load_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.api_token.as_deref(), Some("saved-token"));
+        assert_eq!(loaded.default_ttl, Some(600));
+    }
+
+    #[test]
+    fn read_token_takes_first_trimmed_line() {
+        let token = read_token("  piped-token \nignored\n".as_bytes()).unwrap();
+        assert_eq!(token, "piped-token");
+        assert!(matches!(
+            read_token("\n".as_bytes()),
+            Err(NjallaError::Input { .. })
+        ));
+    }
+
+    #[test]
+    fn load_file_missing_returns_default() {
+        let path = std::env::temp_dir().join("njalla-config-does-not-exist.toml");
+        let config = Config::load_file(&path).unwrap();
+        assert!(config.api_token.is_none());
+    }
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| (*value).to_string())
+        }
+    }
+
+    #[test]
+    fn apply_env_overrides_each_field() {
+        let mut config = Config {
+            api_token: Some("file-token".to_string()),
+            timeout: Some(10),
+            ..Config::default()
+        };
+        config
+            .apply_env(env(&[
+                ("NJALLA_API_TOKEN", "env-token"),
+                ("NJALLA_BASE_URL", "https://staging.example/api/1/"),
+                ("NJALLA_TIMEOUT", "60"),
+                ("NJALLA_RETRIES", " 3 "),
+                ("NJALLA_DEFAULT_TTL", "300"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.api_token.as_deref(), Some("env-token"));
+        assert_eq!(
+            config.base_url.as_deref(),
+            Some("https://staging.example/api/1/")
+        );
+        assert_eq!(config.timeout, Some(60));
+        assert_eq!(config.retries, Some(3));
+        assert_eq!(config.default_ttl, Some(300));
+    }
+
+    #[test]
+    fn apply_env_keeps_file_values_for_unset_or_empty_vars() {
+        let mut config = Config {
+            api_token: Some("file-token".to_string()),
+            retries: Some(1),
+            ..Config::default()
+        };
+        config.apply_env(env(&[("NJALLA_API_TOKEN", "")])).unwrap();
+
+        assert_eq!(config.api_token.as_deref(), Some("file-token"));
+        assert_eq!(config.retries, Some(1));
+    }
+
+    #[test]
+    fn apply_env_rejects_invalid_numbers() {
+        let result = Config::default().apply_env(env(&[("NJALLA_TIMEOUT", "soon")]));
+        assert!(matches!(
+            result,
+            Err(NjallaError::Config { message }) if message.contains("NJALLA_TIMEOUT")
+        ));
+    }
+
+    #[test]
+    fn cli_flags_override_env() {
+        use crate::client::ClientOptions;
+
+        let mut config = Config::default();
+        config
+            .apply_env(env(&[
+                ("NJALLA_BASE_URL", "https://env.example/api/1/"),
+                ("NJALLA_TIMEOUT", "60"),
+                ("NJALLA_RETRIES", "3"),
+            ]))
+            .unwrap();
+        let cli = ClientOptions {
+            base_url: Some("https://flag.example/api/1/".to_string()),
+            timeout_secs: Some(5),
+            ..ClientOptions::default()
+        };
+
+        let options = cli.with_config(&config);
+        assert_eq!(
+            options.base_url.as_deref(),
+            Some("https://flag.example/api/1/")
+        );
+        assert_eq!(options.timeout_secs, Some(5));
+        assert_eq!(options.retries, Some(3));
+    }
 }
@@ -0,0 +1,175 @@
+//! Deprecated flag spellings that keep working.
+//!
+//! A renamed flag keeps its old spelling here rather than in clap, so the old
+//! name stays out of `--help`. Before parsing, old spellings are rewritten to
+//! their replacements and each one used is warned about once on stderr.
+
+use clap::Command;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+
+/// Renamed long flags as `(subcommand, old, new)`, e.g.
+/// `("register", "--timeout", "--wait-timeout")`. Nested subcommands are
+/// written as their path, e.g. `"dns add"`.
+///
+/// Add an entry when renaming a flag, and drop it a few releases later.
+pub const DEPRECATED_FLAGS: &[(&str, &str, &str)] = &[("register", "--timeout", "--wait-timeout")];
+
+/// Replace deprecated flags in `args` with their replacements.
+///
+/// The subcommand is resolved through `command`, the CLI definition, so a
+/// flag is only rewritten after the subcommand it belongs to: a global flag
+/// of the same name, or a positional argument that happens to match a
+/// subcommand name, is left alone. Both `--old` and `--old=value` are
+/// rewritten; arguments after `--` and non-UTF-8 arguments are passed
+/// through. `warn` is called once per deprecated flag, however often it
+/// appears.
+pub fn rewrite_args<I, F>(
+    args: I,
+    mut command: Command,
+    deprecated: &[(&str, &str, &str)],
+    mut warn: F,
+) -> Vec<OsString>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+    F: FnMut(&str, &str),
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    command.build();
+    let Some((path, start)) = subcommand_path(&command, &args) else {
+        return args;
+    };
+    let mut warned = BTreeSet::new();
+
+    for arg in args.iter_mut().skip(start) {
+        let Some(text) = arg.to_str() else {
+            continue;
+        };
+        if text == "--" {
+            break;
+        }
+        let (flag, value) = match text.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (text, None),
+        };
+        let Some(&(_, old, new)) = deprecated
+            .iter()
+            .find(|(subcommand, old, _)| *old == flag && *subcommand == path)
+        else {
+            continue;
+        };
+        if warned.insert(old) {
+            warn(old, new);
+        }
+        *arg = match value {
+            Some(value) => format!("{new}={value}").into(),
+            None => new.into(),
+        };
+    }
+
+    args
+}
+
+/// The subcommand path in `args` (e.g. `dns add`), and the index of the
+/// first argument after it.
+///
+/// Options before each subcommand are skipped along with their values.
+/// Returns `None` if no complete subcommand path is found.
+fn subcommand_path(command: &Command, args: &[OsString]) -> Option<(String, usize)> {
+    let mut current = command;
+    let mut path = Vec::new();
+    let mut index = 1;
+
+    while current.has_subcommands() {
+        let text = args.get(index)?.to_str()?;
+        index += 1;
+        if text == "--" {
+            return None;
+        }
+        if text.starts_with('-') && text != "-" {
+            if !text.contains('=') && takes_value(current, text) {
+                index += 1;
+            }
+            continue;
+        }
+        current = current.find_subcommand(text)?;
+        path.push(current.get_name());
+    }
+
+    Some((path.join(" "), index))
+}
+
+/// Whether `flag` (`--long` or `-s`) of `command` is followed by a value.
+fn takes_value(command: &Command, flag: &str) -> bool {
+    let arg = if let Some(long) = flag.strip_prefix("--") {
+        command.get_arguments().find(|arg| {
+            arg.get_long() == Some(long)
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long))
+        })
+    } else {
+        let mut shorts = flag.chars().skip(1);
+        match (shorts.next(), shorts.next()) {
+            (Some(short), None) => command
+                .get_arguments()
+                .find(|arg| arg.get_short() == Some(short)),
+            _ => None,
+        }
+    };
+    arg.is_some_and(|arg| arg.get_action().takes_values())
+}
+
+/// Print the warning for a deprecated flag.
+pub fn warn_deprecated(old: &str, new: &str) {
+    eprintln!("Warning: {old} is deprecated and will be removed; use {new} instead");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    const RENAMED: &[(&str, &str, &str)] = &[("cmd", "--old-name", "--new-name")];
+
+    fn rewrite(args: &[&str]) -> (Vec<OsString>, Vec<String>) {
+        let command = Command::new("njalla")
+            .arg(Arg::new("old-name").long("old-name"))
+            .subcommand(Command::new("cmd").arg(Arg::new("new-name").long("new-name")))
+            .subcommand(Command::new("other").arg(Arg::new("query")));
+        let mut warnings = Vec::new();
+        let args = rewrite_args(args.iter().copied(), command, RENAMED, |old, new| {
+            warnings.push(format!("{old} -> {new}"));
+        });
+        (args, warnings)
+    }
+
+    #[test]
+    fn rewrites_each_use_but_warns_once() {
+        let (args, warnings) = rewrite(&["njalla", "cmd", "--old-name", "a", "--old-name=b"]);
+        assert_eq!(args, ["njalla", "cmd", "--new-name", "a", "--new-name=b"]);
+        assert_eq!(warnings, ["--old-name -> --new-name"]);
+    }
+
+    #[test]
+    fn leaves_other_args_and_args_after_double_dash_alone() {
+        let (args, warnings) = rewrite(&["njalla", "cmd", "--old-names", "--", "--old-name"]);
+        assert_eq!(args, ["njalla", "cmd", "--old-names", "--", "--old-name"]);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn leaves_flags_before_the_subcommand_alone() {
+        let (args, warnings) = rewrite(&["njalla", "--old-name", "cmd", "cmd", "--new-name"]);
+        assert_eq!(args, ["njalla", "--old-name", "cmd", "cmd", "--new-name"]);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn leaves_other_subcommands_alone_even_with_a_matching_positional() {
+        let (args, warnings) = rewrite(&["njalla", "other", "cmd", "--old-name", "5"]);
+        assert_eq!(args, ["njalla", "other", "cmd", "--old-name", "5"]);
+        assert!(warnings.is_empty());
+    }
+}
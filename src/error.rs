@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+use crate::types::ApiMethod;
+
 /// All errors that can occur in njalla-cli.
 #[derive(Debug)]
 pub enum NjallaError {
@@ -20,6 +22,19 @@ pub enum NjallaError {
     /// Domain is not available for registration.
     DomainNotAvailable(String),
 
+    /// Domain is locked for transfer, so no auth code can be issued.
+    DomainLocked(String),
+
+    /// The price quoted by `register --reserve` no longer holds.
+    PriceChanged {
+        /// Domain being registered.
+        domain: String,
+        /// Yearly price in the confirmation token.
+        quoted: i32,
+        /// Yearly price now.
+        current: i32,
+    },
+
     /// Registration timed out waiting for completion.
     RegistrationTimeout {
         /// Domain being registered.
@@ -28,6 +43,12 @@ pub enum NjallaError {
         timeout_secs: u64,
     },
 
+    /// The command ran past its `--deadline`.
+    Deadline {
+        /// The deadline in seconds.
+        secs: u64,
+    },
+
     /// JSON parsing failed.
     Parse(serde_json::Error),
 
@@ -36,6 +57,191 @@ pub enum NjallaError {
         /// Error message.
         message: String,
     },
+
+    /// Invalid user input (arguments or input files).
+    Input {
+        /// Error message.
+        message: String,
+    },
+
+    /// A record changed between reading it and editing it.
+    RecordChanged {
+        /// Record ID.
+        id: String,
+    },
+
+    /// One or more operations in a bulk command failed.
+    BatchFailed {
+        /// Number of failed operations.
+        failed: usize,
+        /// Total number of operations in the batch.
+        total: usize,
+    },
+
+    /// The API has nothing under the given id.
+    NotFound {
+        /// What was looked up.
+        kind: ResourceKind,
+        /// The id that was not found.
+        id: String,
+    },
+}
+
+/// Kinds of resource a [`NjallaError::NotFound`] can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// A wallet payment.
+    Payment,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Payment => write!(f, "payment"),
+        }
+    }
+}
+
+/// Lowercase fragments of API error messages that report a transient failure.
+const TRANSIENT_API_MESSAGES: &[&str] = &[
+    "rate limit",
+    "too many requests",
+    "temporarily",
+    "temporary failure",
+    "try again later",
+    "service unavailable",
+];
+
+/// Whether a lowercased API error message reports a temporary failure.
+fn is_transient(message: &str) -> bool {
+    TRANSIENT_API_MESSAGES
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
+
+impl NjallaError {
+    /// A short tip on how to fix the error, printed under it by `--explain`.
+    ///
+    /// API errors only have one when the message is recognized.
+    #[must_use]
+    pub fn remediation(&self) -> Option<&'static str> {
+        match self {
+            Self::MissingToken => Some(
+                "Run 'njalla config --init' and set api_token in ./config.toml, \
+                 or export NJALLA_API_TOKEN",
+            ),
+            Self::Request(_) => Some(
+                "Check your network connection and --base-url; \
+                 --retries N retries failed connections",
+            ),
+            Self::Api { message } => {
+                let message = message.to_lowercase();
+                if message.contains("invalid token") || message.contains("unauthorized") {
+                    Some(
+                        "Check the token at https://njal.la → Settings → API, \
+                         then run 'njalla config rotate --new <token>'",
+                    )
+                } else if message.contains("insufficient funds") {
+                    Some(
+                        "Top up the wallet with \
+                         'njalla wallet add-payment --amount <EUR> --via bitcoin'",
+                    )
+                } else if message.contains("not found") {
+                    Some("Check the name with 'njalla domains' or 'njalla dns list <domain>'")
+                } else if is_transient(&message) {
+                    Some("The API is busy; try again shortly, or pass --retries N")
+                } else {
+                    None
+                }
+            }
+            Self::DomainNotAvailable(_) => {
+                Some("Try 'njalla search <name>' to find an available alternative")
+            }
+            Self::DomainLocked(_) => Some(
+                "Unlock the domain at https://njal.la, then run 'njalla domain authcode' again",
+            ),
+            Self::PriceChanged { .. } => {
+                Some("Run 'njalla register <domain> --reserve' again for a token at the new price")
+            }
+            Self::RegistrationTimeout { .. } => {
+                Some("The registration may still finish; check it later with 'njalla tasks'")
+            }
+            Self::Deadline { .. } => Some("Raise --deadline, or drop it to wait for every request"),
+            Self::Parse(_) => {
+                Some("The API response was not understood; rerun with --debug to see it")
+            }
+            Self::Config { .. } => {
+                Some("Check ./config.toml; 'njalla config schema' lists every supported key")
+            }
+            Self::Input { .. } => Some("Run the command with --help to see its arguments"),
+            Self::RecordChanged { .. } => {
+                Some("Run 'njalla dns list <domain>' to see the current record, then edit again")
+            }
+            Self::BatchFailed { .. } => {
+                Some("The summary above lists each failed item; rerun the command for those")
+            }
+            Self::NotFound {
+                kind: ResourceKind::Payment,
+                ..
+            } => Some("Run 'njalla wallet transactions' to see recent payment ids"),
+        }
+    }
+
+    /// The message `main` prints for the error, with its
+    /// [`remediation`](Self::remediation) tip when `explain` is set.
+    #[must_use]
+    pub fn report(&self, explain: bool) -> String {
+        match self.remediation().filter(|_| explain) {
+            Some(tip) => format!("Error: {self}\nHint: {tip}"),
+            None => format!("Error: {self}"),
+        }
+    }
+
+    /// Whether the request failed before it reached the server, e.g. because
+    /// the connection was refused, so the server cannot have acted on it.
+    #[must_use]
+    pub fn is_unsent(&self) -> bool {
+        matches!(
+            self,
+            Self::Request(bitreq::Error::IoError(e)) if matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::AddrNotAvailable
+                    | std::io::ErrorKind::NotConnected
+            )
+        )
+    }
+
+    /// Whether a failed `method` request is worth sending again.
+    ///
+    /// Transport failures and API errors reporting a rate limit or temporary
+    /// failure are; everything else, including definitive API errors such as
+    /// an invalid token or an unknown domain, fails the same way again. A
+    /// method that is not idempotent is only retried when the request never
+    /// reached the server (see [`NjallaError::is_unsent`]), since repeating
+    /// it after the server acted would register, pay or change records twice.
+    #[must_use]
+    pub fn is_retryable(&self, method: ApiMethod) -> bool {
+        if !method.is_idempotent() {
+            return self.is_unsent();
+        }
+        match self {
+            Self::Request(_) => true,
+            Self::Api { message } => is_transient(&message.to_lowercase()),
+            Self::MissingToken
+            | Self::DomainNotAvailable(_)
+            | Self::DomainLocked(_)
+            | Self::PriceChanged { .. }
+            | Self::RegistrationTimeout { .. }
+            | Self::Deadline { .. }
+            | Self::Parse(_)
+            | Self::Config { .. }
+            | Self::Input { .. }
+            | Self::RecordChanged { .. }
+            | Self::BatchFailed { .. }
+            | Self::NotFound { .. } => false,
+        }
+    }
 }
 
 impl fmt::Display for NjallaError {
@@ -48,12 +254,33 @@ impl fmt::Display for NjallaError {
             Self::Request(e) => write!(f, "Request failed: {e}"),
             Self::Api { message } => write!(f, "API error: {message}"),
             Self::DomainNotAvailable(s) => write!(f, "Domain not available: {s}"),
+            Self::DomainLocked(s) => write!(
+                f,
+                "Domain {s} is locked for transfer; unlock it in the Njalla web interface first"
+            ),
+            Self::PriceChanged {
+                domain,
+                quoted,
+                current,
+            } => write!(
+                f,
+                "Price of {domain} changed from {quoted} € to {current} € per year since it was reserved; run --reserve again"
+            ),
             Self::RegistrationTimeout {
                 domain,
                 timeout_secs,
             } => write!(f, "Registration timeout for {domain} after {timeout_secs}s"),
+            Self::Deadline { secs } => write!(f, "Command exceeded its --deadline of {secs}s"),
             Self::Parse(e) => write!(f, "Failed to parse response: {e}"),
             Self::Config { message } => write!(f, "Config error: {message}"),
+            Self::Input { message } => write!(f, "Invalid input: {message}"),
+            Self::RecordChanged { id } => {
+                write!(f, "Record {id} changed since it was read; edit aborted")
+            }
+            Self::BatchFailed { failed, total } => {
+                write!(f, "{failed} of {total} operations failed")
+            }
+            Self::NotFound { kind, id } => write!(f, "No such {kind}: {id}"),
         }
     }
 }
@@ -110,6 +337,28 @@ mod tests {
         assert_eq!(err.to_string(), "Domain not available: example.com");
     }
 
+    #[test]
+    fn error_display_domain_locked() {
+        let err = NjallaError::DomainLocked("example.com".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Domain example.com is locked for transfer; unlock it in the Njalla web interface first"
+        );
+    }
+
+    #[test]
+    fn error_display_price_changed() {
+        let err = NjallaError::PriceChanged {
+            domain: "example.com".to_string(),
+            quoted: 15,
+            current: 18,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Price of example.com changed from 15 € to 18 € per year since it was reserved; run --reserve again"
+        );
+    }
+
     #[test]
     fn error_display_timeout() {
         let err = NjallaError::RegistrationTimeout {
@@ -121,4 +370,206 @@ mod tests {
             "Registration timeout for example.com after 300s"
         );
     }
+
+    #[test]
+    fn error_display_deadline() {
+        let err = NjallaError::Deadline { secs: 10 };
+        assert_eq!(err.to_string(), "Command exceeded its --deadline of 10s");
+    }
+
+    #[test]
+    fn error_display_input() {
+        let err = NjallaError::Input {
+            message: "content file is empty".to_string(),
+        };
+        assert_eq!(err.to_string(), "Invalid input: content file is empty");
+    }
+
+    #[test]
+    fn error_display_batch_failed() {
+        let err = NjallaError::BatchFailed {
+            failed: 1,
+            total: 3,
+        };
+        assert_eq!(err.to_string(), "1 of 3 operations failed");
+    }
+
+    #[test]
+    fn error_display_record_changed() {
+        let err = NjallaError::RecordChanged {
+            id: "rec123".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Record rec123 changed since it was read; edit aborted"
+        );
+    }
+
+    #[test]
+    fn error_display_not_found() {
+        let err = NjallaError::NotFound {
+            kind: ResourceKind::Payment,
+            id: "pay123".to_string(),
+        };
+        assert_eq!(err.to_string(), "No such payment: pay123");
+    }
+
+    #[test]
+    fn transient_api_errors_are_retryable() {
+        for message in [
+            "Rate limit exceeded",
+            "Too Many Requests",
+            "Temporary failure, try again later",
+            "Service temporarily unavailable",
+        ] {
+            let err = NjallaError::Api {
+                message: message.to_string(),
+            };
+            assert!(err.is_retryable(ApiMethod::ListDomains), "{message}");
+            assert!(!err.is_retryable(ApiMethod::AddRecord), "{message}");
+        }
+    }
+
+    #[test]
+    fn definitive_errors_are_not_retryable() {
+        let api = |message: &str| NjallaError::Api {
+            message: message.to_string(),
+        };
+        let errors = [
+            NjallaError::MissingToken,
+            api("Invalid token"),
+            api("Domain not found"),
+            NjallaError::DomainNotAvailable("example.com".to_string()),
+            NjallaError::DomainLocked("example.com".to_string()),
+            NjallaError::PriceChanged {
+                domain: "example.com".to_string(),
+                quoted: 15,
+                current: 18,
+            },
+            NjallaError::RegistrationTimeout {
+                domain: "example.com".to_string(),
+                timeout_secs: 300,
+            },
+            NjallaError::Deadline { secs: 30 },
+            NjallaError::Parse(serde_json::from_str::<u8>("x").unwrap_err()),
+            NjallaError::Config {
+                message: "bad".to_string(),
+            },
+            NjallaError::Input {
+                message: "bad".to_string(),
+            },
+            NjallaError::RecordChanged {
+                id: "rec123".to_string(),
+            },
+            NjallaError::BatchFailed {
+                failed: 1,
+                total: 3,
+            },
+            NjallaError::NotFound {
+                kind: ResourceKind::Payment,
+                id: "pay123".to_string(),
+            },
+        ];
+        for err in errors {
+            assert!(!err.is_retryable(ApiMethod::ListDomains), "{err}");
+        }
+    }
+
+    #[test]
+    fn remediation_points_at_the_fix() {
+        let api = |message: &str| NjallaError::Api {
+            message: message.to_string(),
+        };
+        let cases = [
+            (NjallaError::MissingToken, "config --init"),
+            (api("Invalid token"), "config rotate"),
+            (
+                api("Insufficient funds"),
+                "add-payment --amount <EUR> --via bitcoin",
+            ),
+            (api("Domain not found"), "njalla domains"),
+            (api("Rate limit exceeded"), "--retries"),
+            (
+                NjallaError::DomainNotAvailable("example.com".to_string()),
+                "njalla search",
+            ),
+            (
+                NjallaError::DomainLocked("example.com".to_string()),
+                "Unlock",
+            ),
+            (
+                NjallaError::PriceChanged {
+                    domain: "example.com".to_string(),
+                    quoted: 15,
+                    current: 18,
+                },
+                "--reserve",
+            ),
+            (
+                NjallaError::RegistrationTimeout {
+                    domain: "example.com".to_string(),
+                    timeout_secs: 300,
+                },
+                "njalla tasks",
+            ),
+            (NjallaError::Deadline { secs: 30 }, "--deadline"),
+            (
+                NjallaError::Parse(serde_json::from_str::<u8>("x").unwrap_err()),
+                "--debug",
+            ),
+            (
+                NjallaError::Config {
+                    message: "bad".to_string(),
+                },
+                "config schema",
+            ),
+            (
+                NjallaError::Input {
+                    message: "bad".to_string(),
+                },
+                "--help",
+            ),
+            (
+                NjallaError::RecordChanged {
+                    id: "rec123".to_string(),
+                },
+                "dns list",
+            ),
+            (
+                NjallaError::BatchFailed {
+                    failed: 1,
+                    total: 3,
+                },
+                "failed item",
+            ),
+            (
+                NjallaError::NotFound {
+                    kind: ResourceKind::Payment,
+                    id: "pay123".to_string(),
+                },
+                "wallet transactions",
+            ),
+        ];
+        for (err, expected) in cases {
+            let tip = err.remediation().unwrap_or_default();
+            assert!(tip.contains(expected), "{err}: {tip}");
+        }
+        assert_eq!(api("Something odd").remediation(), None);
+    }
+
+    #[test]
+    fn report_adds_the_tip_only_when_explaining() {
+        let err = NjallaError::MissingToken;
+        let plain = err.report(false);
+        assert_eq!(plain, format!("Error: {err}"));
+
+        let explained = err.report(true);
+        assert!(explained.starts_with(&plain));
+        assert!(explained.ends_with(&format!("\nHint: {}", err.remediation().unwrap())));
+
+        let unknown = NjallaError::Api {
+            message: "Something odd".to_string(),
+        };
+        assert_eq!(unknown.report(true), "Error: API error: Something odd");
+    }
 }
@@ -0,0 +1,82 @@
+//! Wallet transactions as CSV for bookkeeping software.
+//!
+//! Unlike the record exporters, this has a fixed column set meant for import
+//! into accounting tools: `date,description,amount_eur,amount_btc,
+//! invoice_url,status`. Dates are ISO 8601 (`2026-02-01`), euro amounts have
+//! two decimals, and the invoice PDF URL is the reference.
+
+use super::csv::quote;
+use crate::types::{parse_iso_date, Transaction, TransactionStatus};
+
+/// Header row.
+const HEADER: &str = "date,description,amount_eur,amount_btc,invoice_url,status";
+
+/// Convert transactions to accounting CSV, one row each.
+///
+/// Pending transactions have no date or invoice yet, so those fields are
+/// left empty.
+#[must_use]
+pub fn export(transactions: &[Transaction]) -> String {
+    let mut output = String::from(HEADER);
+    output.push('\n');
+
+    for tx in transactions {
+        let date = tx
+            .completed
+            .as_deref()
+            .filter(|date| parse_iso_date(date).is_some())
+            .map(|date| date[..10].to_string());
+        let status = match tx.classify() {
+            TransactionStatus::Completed => "completed",
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Failed => "failed",
+            TransactionStatus::Other => "other",
+        };
+        let fields = [
+            date.unwrap_or_default(),
+            tx.status.clone(),
+            format!("{}.00", tx.amount),
+            tx.amount_btc.clone().unwrap_or_default(),
+            tx.pdf.clone().unwrap_or_default(),
+            status.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| quote(f)).collect();
+        output.push_str(&row.join(","));
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_completed_and_pending_rows() {
+        let transactions: Vec<Transaction> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "IKSE",
+                "amount": 210,
+                "status": "Added 210 € via Bitcoin",
+                "completed": "2026-02-01",
+                "pdf": "https://njal.la/invoice/IKSE/"
+            },
+            {
+                "id": "4S4I",
+                "amount": 15,
+                "status": "Waiting for transaction of 15 €, via Bitcoin",
+                "currency": "EUR",
+                "amount_btc": "0.0002539"
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            export(&transactions),
+            "date,description,amount_eur,amount_btc,invoice_url,status\n\
+             2026-02-01,Added 210 € via Bitcoin,210.00,,https://njal.la/invoice/IKSE/,completed\n\
+             ,\"Waiting for transaction of 15 €, via Bitcoin\",15.00,0.0002539,,pending\n"
+        );
+    }
+}
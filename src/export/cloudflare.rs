@@ -0,0 +1,149 @@
+//! Cloudflare DNS record JSON.
+//!
+//! Produces an array of record objects in the shape of Cloudflare's
+//! "create DNS record" request body, ready for its batch endpoint or
+//! record-by-record import scripts.
+
+use super::{fqdn, Export};
+use crate::error::Result;
+use crate::output::to_json;
+use crate::types::{Record, RecordType};
+
+/// Lowest TTL Cloudflare accepts, apart from 1 ("automatic").
+const MIN_TTL: i32 = 60;
+
+/// Highest TTL Cloudflare accepts.
+const MAX_TTL: i32 = 86_400;
+
+/// Convert records to Cloudflare record objects.
+pub(super) fn export(domain: &str, records: &[Record]) -> Result<Export> {
+    let mut converted = Vec::with_capacity(records.len());
+    let mut skipped = Vec::new();
+
+    for record in records {
+        match convert(domain, record) {
+            Ok(value) => converted.push(value),
+            Err(reason) => {
+                skipped.push(format!("{} {}: {reason}", record.name, record.record_type));
+            }
+        }
+    }
+
+    Ok(Export {
+        output: to_json(&converted)?,
+        skipped,
+    })
+}
+
+/// Map one record, or explain why Cloudflare cannot take it.
+fn convert(domain: &str, record: &Record) -> std::result::Result<serde_json::Value, &'static str> {
+    let content = record.content.as_deref().unwrap_or_default();
+    let record_type = match record.record_type {
+        RecordType::A
+        | RecordType::Aaaa
+        | RecordType::Cname
+        | RecordType::Mx
+        | RecordType::Ns
+        | RecordType::Ptr
+        | RecordType::Srv
+        | RecordType::Txt => record.record_type.to_string(),
+        // Cloudflare flattens CNAMEs at the apex, which is what ANAME does.
+        RecordType::Aname => "CNAME".to_string(),
+        RecordType::Dynamic => return Err("Cloudflare has no dynamic record type"),
+        _ => return Err("not supported by the Cloudflare exporter yet"),
+    };
+
+    let mut value = serde_json::json!({
+        "type": record_type,
+        "name": fqdn(&record.name, domain),
+        "ttl": record.ttl.map_or(1, |ttl| ttl.clamp(MIN_TTL, MAX_TTL)),
+    });
+
+    match record.record_type {
+        RecordType::Srv => {
+            value["data"] = serde_json::json!({
+                "priority": record.priority.unwrap_or_default(),
+                "weight": record.weight.unwrap_or_default(),
+                "port": record.port.unwrap_or_default(),
+                "target": content.trim_end_matches('.'),
+            });
+        }
+        RecordType::Mx => {
+            value["content"] = content.trim_end_matches('.').into();
+            value["priority"] = record.priority.unwrap_or_default().into();
+        }
+        RecordType::Cname | RecordType::Aname | RecordType::Ns | RecordType::Ptr => {
+            value["content"] = content.trim_end_matches('.').into();
+        }
+        _ => value["content"] = content.into(),
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(json: serde_json::Value) -> Vec<Record> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn exported(records: &[Record]) -> (serde_json::Value, Vec<String>) {
+        let export = export("example.com", records).unwrap();
+        (
+            serde_json::from_str(&export.output).unwrap(),
+            export.skipped,
+        )
+    }
+
+    #[test]
+    fn maps_a_cname_and_mx() {
+        let (json, skipped) = exported(&records(serde_json::json!([
+            { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1", "ttl": 3600 },
+            { "id": "2", "name": "www", "type": "CNAME", "content": "example.com.", "ttl": 10800 },
+            { "id": "3", "name": "@", "type": "MX", "content": "mail.example.com", "ttl": 300, "prio": 10 }
+        ])));
+
+        assert!(skipped.is_empty());
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "type": "A", "name": "example.com", "content": "192.0.2.1", "ttl": 3600 },
+                { "type": "CNAME", "name": "www.example.com", "content": "example.com", "ttl": 10800 },
+                { "type": "MX", "name": "example.com", "content": "mail.example.com", "ttl": 300, "priority": 10 }
+            ])
+        );
+    }
+
+    #[test]
+    fn maps_srv_into_data_and_clamps_ttl() {
+        let (json, _) = exported(&records(serde_json::json!([
+            {
+                "id": "1", "name": "_sip._tcp", "type": "SRV", "content": "sip.example.com",
+                "ttl": 30, "prio": 10, "weight": 5, "port": 5060
+            }
+        ])));
+
+        assert_eq!(json[0]["name"], "_sip._tcp.example.com");
+        assert_eq!(json[0]["ttl"], MIN_TTL);
+        assert_eq!(
+            json[0]["data"],
+            serde_json::json!({ "priority": 10, "weight": 5, "port": 5060, "target": "sip.example.com" })
+        );
+    }
+
+    #[test]
+    fn skips_dynamic_records() {
+        let (json, skipped) = exported(&records(serde_json::json!([
+            { "id": "1", "name": "home", "type": "Dynamic" },
+            { "id": "2", "name": "@", "type": "ANAME", "content": "host.example.net" }
+        ])));
+
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["type"], "CNAME");
+        assert_eq!(json[0]["ttl"], 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("home Dynamic"));
+    }
+}
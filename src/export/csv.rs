@@ -0,0 +1,71 @@
+//! Generic CSV, one row per record.
+//!
+//! Columns: `name,type,content,ttl,priority,weight,port,target,value`, with
+//! fully qualified names. Fields are quoted per RFC 4180 when needed.
+
+use super::{fqdn, Export};
+use crate::types::Record;
+
+/// Header row.
+const HEADER: &str = "name,type,content,ttl,priority,weight,port,target,value";
+
+/// Convert records to CSV. Every record type has a row, so none are skipped.
+pub(super) fn export(domain: &str, records: &[Record]) -> Export {
+    let mut output = String::from(HEADER);
+    output.push('\n');
+
+    for record in records {
+        let number = |n: Option<i32>| n.map(|n| n.to_string()).unwrap_or_default();
+        let fields = [
+            fqdn(&record.name, domain),
+            record.record_type.to_string(),
+            record.content.clone().unwrap_or_default(),
+            number(record.ttl),
+            number(record.priority),
+            number(record.weight),
+            number(record.port),
+            record.target.clone().unwrap_or_default(),
+            record.value.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| quote(f)).collect();
+        output.push_str(&row.join(","));
+        output.push('\n');
+    }
+
+    Export {
+        output,
+        skipped: Vec::new(),
+    }
+}
+
+/// Quote a field if it contains a comma, quote or line break.
+pub(super) fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_header_and_quoted_rows() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "@", "type": "MX", "content": "mail.example.com", "ttl": 300, "prio": 10 },
+            { "id": "2", "name": "@", "type": "TXT", "content": "v=spf1 a, \"quoted\"", "ttl": 3600 }
+        ]))
+        .unwrap();
+
+        let export = export("example.com", &records);
+
+        assert_eq!(
+            export.output,
+            "name,type,content,ttl,priority,weight,port,target,value\n\
+             example.com,MX,mail.example.com,300,10,,,,\n\
+             example.com,TXT,\"v=spf1 a, \"\"quoted\"\"\",3600,,,,,\n"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Exporters that convert Njalla records into other providers' import formats.
+//!
+//! Each format lives in its own module and turns a domain's records into the
+//! text the target tool ingests. Records a format cannot express are skipped
+//! and reported, never silently dropped. [`accounting`] is the exception:
+//! it exports wallet transactions, not records.
+
+pub mod accounting;
+mod cloudflare;
+mod csv;
+
+use crate::error::Result;
+use crate::types::Record;
+
+/// Target format for `dns export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// JSON array of Cloudflare DNS record objects.
+    Cloudflare,
+    /// Generic CSV with one row per record.
+    Csv,
+}
+
+/// Exported records plus the records the format could not express.
+#[derive(Debug)]
+pub struct Export {
+    /// Text to write out in the target format.
+    pub output: String,
+
+    /// Labels of skipped records, with the reason (e.g. "@ Dynamic: ...").
+    pub skipped: Vec<String>,
+}
+
+/// Convert `records` of `domain` into `format`.
+///
+/// # Errors
+///
+/// Returns an error if serialization fails.
+pub fn export(format: ExportFormat, domain: &str, records: &[Record]) -> Result<Export> {
+    match format {
+        ExportFormat::Cloudflare => cloudflare::export(domain, records),
+        ExportFormat::Csv => Ok(csv::export(domain, records)),
+    }
+}
+
+/// Fully qualified name of a record, without a trailing dot.
+///
+/// `@` is the domain itself; other names are relative to it.
+fn fqdn(name: &str, domain: &str) -> String {
+    let domain = domain.trim_end_matches('.');
+    match name {
+        "" | "@" => domain.to_string(),
+        name => format!("{name}.{domain}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fqdn_expands_relative_names() {
+        assert_eq!(fqdn("@", "example.com"), "example.com");
+        assert_eq!(fqdn("www", "example.com."), "www.example.com");
+        assert_eq!(fqdn("*", "example.com"), "*.example.com");
+        assert_eq!(fqdn("_sip._tcp", "example.com"), "_sip._tcp.example.com");
+    }
+}
@@ -0,0 +1,174 @@
+//! `--only FIELD==VALUE` filters for list commands.
+//!
+//! A filter is checked against each item as it appears in the JSON output,
+//! so any output field can be filtered on without a dedicated flag:
+//! `--only type==MX`, `--only status!=active`, `--only name~=mail`. Fields
+//! use their JSON names (`type`, `prio`), and repeated filters must all
+//! match.
+
+use crate::error::Result;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// How a filter compares a field with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    /// `==`: the field equals the value.
+    Eq,
+    /// `!=`: the field is missing or differs from the value.
+    Ne,
+    /// `~=`: the field contains the value, ignoring case.
+    Contains,
+}
+
+/// Operator spellings, all two characters long.
+const OPERATORS: [(&str, FilterOp); 3] = [
+    ("==", FilterOp::Eq),
+    ("!=", FilterOp::Ne),
+    ("~=", FilterOp::Contains),
+];
+
+/// One `--only` filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFilter {
+    /// JSON field name.
+    pub field: String,
+    /// Comparison.
+    pub op: FilterOp,
+    /// Value to compare with.
+    pub value: String,
+}
+
+impl FromStr for FieldFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (at, op) = OPERATORS
+            .iter()
+            .filter_map(|&(token, op)| s.find(token).map(|at| (at, op)))
+            .min_by_key(|&(at, _)| at)
+            .ok_or_else(|| {
+                format!("expected FIELD==VALUE, FIELD!=VALUE or FIELD~=VALUE, got '{s}'")
+            })?;
+        let field = s[..at].trim();
+        if field.is_empty() {
+            return Err(format!("missing field name in '{s}'"));
+        }
+        Ok(Self {
+            field: field.to_string(),
+            op,
+            value: s[at + 2..].to_string(),
+        })
+    }
+}
+
+impl FieldFilter {
+    /// Whether the serialized `item` passes the filter.
+    ///
+    /// Strings compare as they are, numbers and booleans as their JSON
+    /// text. A missing or `null` field only passes `!=`.
+    #[must_use]
+    pub fn matches(&self, item: &serde_json::Value) -> bool {
+        let field = match item.get(&self.field) {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::String(s)) => Some(s.clone()),
+            Some(other) => Some(other.to_string()),
+        };
+        match (self.op, field) {
+            (FilterOp::Eq, Some(field)) => field == self.value,
+            (FilterOp::Ne, field) => field.is_none_or(|field| field != self.value),
+            (FilterOp::Contains, Some(field)) => {
+                field.to_lowercase().contains(&self.value.to_lowercase())
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+/// Keep the items that pass every filter.
+///
+/// # Errors
+///
+/// Returns an error if an item cannot be serialized.
+pub fn retain_matching<T: Serialize>(items: &mut Vec<T>, filters: &[FieldFilter]) -> Result<()> {
+    let mut keep = matching(items, filters)?.into_iter();
+    items.retain(|_| keep.next().unwrap_or(false));
+    Ok(())
+}
+
+/// Whether each of `shown` passes every filter.
+///
+/// `shown` is what is printed for each item, which may carry more fields
+/// than the item itself; the result lines up with it for `Vec::retain`.
+///
+/// # Errors
+///
+/// Returns an error if an item cannot be serialized.
+pub fn matching<T: Serialize>(shown: &[T], filters: &[FieldFilter]) -> Result<Vec<bool>> {
+    if filters.is_empty() {
+        return Ok(vec![true; shown.len()]);
+    }
+    shown
+        .iter()
+        .map(|item| {
+            let value = serde_json::to_value(item)?;
+            Ok(filters.iter().all(|filter| filter.matches(&value)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Record;
+
+    fn filter(s: &str) -> FieldFilter {
+        s.parse().unwrap()
+    }
+
+    fn records() -> Vec<Record> {
+        serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1", "ttl": 3600 },
+            { "id": "2", "name": "www", "type": "CNAME", "content": "example.com" },
+            { "id": "3", "name": "@", "type": "MX", "content": "mail.example.com", "prio": 10 },
+            { "id": "4", "name": "mail", "type": "A", "content": "192.0.2.2", "ttl": 300 }
+        ]))
+        .unwrap()
+    }
+
+    fn ids(filters: &[&str]) -> Vec<String> {
+        let filters: Vec<FieldFilter> = filters.iter().map(|s| filter(s)).collect();
+        let mut records = records();
+        retain_matching(&mut records, &filters).unwrap();
+        records.into_iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn parses_operators() {
+        assert_eq!(
+            filter("type==A"),
+            FieldFilter {
+                field: "type".to_string(),
+                op: FilterOp::Eq,
+                value: "A".to_string(),
+            }
+        );
+        assert_eq!(filter("status!=active").op, FilterOp::Ne);
+        assert_eq!(filter("name~=mail").op, FilterOp::Contains);
+        assert_eq!(filter("content==a==b").value, "a==b");
+        assert!("type=A".parse::<FieldFilter>().is_err());
+        assert!("==A".parse::<FieldFilter>().is_err());
+    }
+
+    #[test]
+    fn filters_mixed_records() {
+        assert_eq!(ids(&["type==A"]), ["1", "4"]);
+        assert_eq!(ids(&["type!=A"]), ["2", "3"]);
+        assert_eq!(ids(&["content~=EXAMPLE"]), ["2", "3"]);
+        assert_eq!(ids(&["type==A", "name!=@"]), ["4"]);
+        assert_eq!(ids(&["ttl==300"]), ["4"]);
+        assert_eq!(ids(&["prio==10"]), ["3"]);
+        assert_eq!(ids(&["prio!=10"]), ["1", "2", "4"]);
+        assert!(ids(&["nope==x"]).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+//! Local JSON state files kept next to `config.toml`.
+//!
+//! The task log, balance ledger, record comments and zone snapshots are each
+//! one pretty-printed JSON document. A missing file reads as the empty
+//! default, and writes go through a temporary file renamed over the old one,
+//! so an interrupted write never truncates it. `config.toml` itself is written
+//! the same way, through [`write_atomically`].
+
+use crate::error::{NjallaError, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Load `path`, or `T::default()` if it does not exist.
+///
+/// `what` names the file in error messages, e.g. "task log".
+///
+/// # Errors
+///
+/// Returns an error if the file exists but cannot be read or parsed.
+pub fn load<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| NjallaError::Config {
+        message: format!("Failed to read {what}: {e}"),
+    })?;
+    serde_json::from_str(&contents).map_err(|e| NjallaError::Config {
+        message: format!("Failed to parse {what}: {e}"),
+    })
+}
+
+/// Write `value` to `path` through a temporary file renamed over it.
+///
+/// # Errors
+///
+/// Returns an error if serialization, writing or renaming fails.
+pub fn save<T: Serialize + ?Sized>(value: &T, path: &Path, what: &str) -> Result<()> {
+    let contents = serde_json::to_string_pretty(value)?;
+    write_atomically(path, &contents, what)
+}
+
+/// Write `contents` to `path` through `<path>.tmp` renamed over it.
+///
+/// # Errors
+///
+/// Returns an error if writing or renaming fails.
+pub fn write_atomically(path: &Path, contents: &str, what: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|e| NjallaError::Config {
+            message: format!("Failed to write {what}: {e}"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_loads_as_default() {
+        let path = std::env::temp_dir().join("njalla-json-file-does-not-exist.json");
+        let loaded: Vec<u32> = load(&path, "numbers").unwrap();
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn unparsable_file_names_what_failed() {
+        let path =
+            std::env::temp_dir().join(format!("njalla-json-file-bad-{}.json", std::process::id()));
+        fs::write(&path, "not json").unwrap();
+
+        let result: Result<Vec<u32>> = load(&path, "numbers");
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(NjallaError::Config { message }) if message.starts_with("Failed to parse numbers")
+        ));
+    }
+}
@@ -2,21 +2,35 @@
 //!
 //! This crate provides a Rust client for the Njalla domain management API.
 //!
+//! The client is blocking: every method makes its HTTP request on the calling
+//! thread and returns when the response arrives, so no async runtime is needed
+//! to use it from scripts or FFI.
+//!
 //! # Example
 //!
 //! ```no_run
-//! use njalla_cli::client::NjallaClient;
+//! use njalla_cli::client::{ClientOptions, NjallaClient};
 //!
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let client = NjallaClient::new(false)?;  // false = no debug output
+//!     let client = NjallaClient::new(&ClientOptions::default())?;
 //!     let domains = client.list_domains()?;
 //!     println!("Found {} domains", domains.len());
 //!     Ok(())
 //! }
 //! ```
 
+pub mod balance_log;
 pub mod client;
+pub mod concurrency;
 pub mod config;
+pub mod deprecated;
 pub mod error;
+pub mod export;
+pub mod filter;
+pub mod json_file;
 pub mod output;
+pub mod pager;
+pub mod record_comments;
+pub mod task_log;
 pub mod types;
+pub mod zone_snapshots;
@@ -1,13 +1,25 @@
 //! njalla-cli - Privacy-first domain management CLI for Njalla.
 
+mod balance_log;
 mod client;
 mod commands;
+mod concurrency;
 mod config;
+mod deprecated;
 mod error;
+mod export;
+mod filter;
+mod json_file;
 mod output;
+mod pager;
+mod record_comments;
+mod task_log;
 mod types;
+mod zone_snapshots;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use output::to_json;
+use std::io::IsTerminal;
 
 /// Privacy-first domain management CLI for Njalla.
 #[derive(Parser)]
@@ -26,7 +38,14 @@ CONFIGURATION:
     Option 2: Environment variable
         export NJALLA_API_TOKEN=\"your-token\"
 
-    Environment variable takes precedence over config file.
+    Option 3: Pipe it on stdin (kept out of args and env)
+        pass show njalla | njalla --token-stdin domains
+
+    Stdin takes precedence over the environment variable, which takes
+    precedence over the config file.
+
+    NJALLA_ASSUME_YES=1 approves every confirmation prompt, like --confirm.
+    Dangerous: registrations spend wallet funds without asking.
 
 EXAMPLES:
     njalla domains                      List all your domains
@@ -40,24 +59,249 @@ EXAMPLES:
 MORE INFO:
     https://github.com/gudnuf/njalla-cli
     https://njal.la/api/")]
+#[allow(clippy::struct_excessive_bools)]
 struct Cli {
     /// Enable debug mode to see raw API responses.
     #[arg(long, global = true)]
     debug: bool,
 
+    /// Append a JSON transcript of every API request to this file.
+    ///
+    /// Each line records the method, params, response text, HTTP status and
+    /// duration. The API token is never written.
+    #[arg(long, global = true, value_name = "PATH")]
+    trace_file: Option<std::path::PathBuf>,
+
+    /// Restrict output to ASCII by escaping non-ASCII characters.
+    ///
+    /// Also enabled by `NJALLA_ASCII=1` or a non-UTF-8 locale.
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Print JSON on a single line with no extra whitespace.
+    ///
+    /// List commands (domains, dns list, wallet transactions) always emit a
+    /// bare top-level array; `status` emits an object.
+    #[arg(long, global = true)]
+    compact: bool,
+
+    /// Make payment links (`uri`, `url`, `pdf`) clickable OSC 8 hyperlinks.
+    ///
+    /// Only applies when stdout is a terminal, since the escapes are not
+    /// valid JSON.
+    #[arg(long, global = true)]
+    hyperlinks: bool,
+
+    /// Page output through `$NJALLA_PAGER`, `$PAGER` or `less`.
+    ///
+    /// Without it, only non-JSON output taller than the terminal is paged
+    /// (when stdout is a terminal); this pages JSON and short output too.
+    #[arg(long, global = true, conflicts_with = "no_pager")]
+    pager: bool,
+
+    /// Never page output.
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Wrap list and status JSON as `{"_schema_version": N, "data": ...}`.
+    ///
+    /// The version is bumped whenever the shape of that output changes, so
+    /// scripts can detect incompatible releases.
+    #[arg(long, global = true)]
+    with_schema: bool,
+
+    /// Read the API token from the first line of stdin.
+    ///
+    /// Takes precedence over `NJALLA_API_TOKEN` and the config file, and keeps
+    /// the token out of process arguments and the environment.
+    #[arg(long, global = true)]
+    token_stdin: bool,
+
+    /// Skip TLS certificate verification (debugging behind a proxy).
+    ///
+    /// Not supported by the current HTTP client; commands fail with an error
+    /// instead of silently verifying. Cannot be set in the config file.
+    #[arg(long, global = true, visible_alias = "insecure")]
+    no_verify_tls: bool,
+
+    /// Print a tip on how to fix the error when a command fails.
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Fail on any `null` field in API responses.
+    ///
+    /// Normally a `null` quietly becomes an empty or default value. Meant
+    /// for CI checks that should catch API changes early.
+    #[arg(long, global = true)]
+    strict_json: bool,
+
+    /// Print each API request as an equivalent `curl` command on stderr.
+    ///
+    /// The request is still sent. The token appears as `$NJALLA_API_TOKEN`,
+    /// so the command can be shared and rerun from a shell with it exported.
+    #[arg(long, global = true)]
+    print_curl: bool,
+
+    /// API request timeout in seconds for all commands (default: 30).
+    ///
+    /// Commands that accept their own --timeout override this value.
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Abort the command after this many seconds in total.
+    ///
+    /// Covers every request, retry and polling wait, where --timeout only
+    /// bounds a single request.
+    #[arg(long, global = true, value_name = "SECS")]
+    deadline: Option<u64>,
+
+    /// API endpoint URL, e.g. a staging environment (overrides `base_url` in config).
+    #[arg(long, global = true, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Retries after a failed connection or rate limit (overrides `retries` in config).
+    ///
+    /// Waits 0.5s before the first retry and doubles the wait for each one
+    /// after, up to 30s, or as long as the API's `Retry-After` asks.
+    #[arg(long, global = true, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Maximum concurrent API requests for fan-out commands (default: 4).
+    ///
+    /// Applies to `status --dns` and `doctor`. Use 1 on rate-limited accounts.
+    /// Results are always collected and printed in input order, so output
+    /// is the same at any concurrency.
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_concurrency: Option<usize>,
+
+    /// Print the result as `{"ok", "command", "data", "elapsed_ms"}` on stdout.
+    ///
+    /// Failures, including command-line errors, are printed the same way,
+    /// with `"ok": false` and an `error` object instead of a message on
+    /// stderr, so there is one shape to parse. Cannot be combined with
+    /// --repeat.
+    /// Progress lines and prompts (e.g. from `register --wait`) go to stderr.
+    #[arg(long, global = true, conflicts_with_all = ["repeat", "hyperlinks"])]
+    json_envelope: bool,
+
+    /// Re-run the command every --interval seconds until it fails or Ctrl-C.
+    ///
+    /// Only for read-only commands (e.g. `domains`, `dns list`, `wallet
+    /// balance`). A `--- run N at <unix time> ---` line goes to stderr before
+    /// each run, and output is never paged.
+    #[arg(long, global = true, conflicts_with = "token_stdin")]
+    repeat: bool,
+
+    /// Seconds between runs for --repeat (default: 60).
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECS",
+        requires = "repeat",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    interval: Option<u64>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Seconds between runs for `--repeat` without `--interval`.
+const DEFAULT_REPEAT_INTERVAL: u64 = 60;
+
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// List all domains in your account.
-    Domains,
+    #[command(visible_alias = "ls", alias = "list")]
+    Domains {
+        /// Only show domains with this status (e.g., active, pending, expired).
+        #[arg(long)]
+        status: Option<types::DomainStatus>,
+
+        /// Only show expired domains, including grace and redemption periods.
+        #[arg(long, conflicts_with = "status")]
+        expired: bool,
+
+        #[command(flatten)]
+        only: OnlyArgs,
+
+        /// Print `name<TAB>status<TAB>expiry` lines instead of JSON.
+        ///
+        /// A stable format for scripts: fields keep their order across
+        /// versions and new ones are only appended.
+        #[arg(long)]
+        porcelain: bool,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
 
     /// Search for available domains.
+    #[command(visible_alias = "find")]
     Search {
         /// Domain name or keyword to search.
         query: String,
+
+        /// Only keep results matching the query exactly, as a substring, or as a prefix.
+        ///
+        /// Compares against the label (before the TLD) unless the query has a
+        /// dot. Without this flag, results are shown as the API returns them.
+        #[arg(long = "match", value_enum, value_name = "MODE")]
+        match_mode: Option<commands::search::MatchMode>,
+
+        /// Wrap results with a price summary of the available domains.
+        #[arg(long)]
+        summary: bool,
+
+        /// Flag each result with whether the wallet balance covers a year of it.
+        ///
+        /// Fetches the balance too; taken domains are never affordable.
+        #[arg(long, conflicts_with_all = ["summary", "register_first"])]
+        affordable: bool,
+
+        #[command(flatten)]
+        only: OnlyArgs,
+
+        /// Register the first available result instead of listing results.
+        ///
+        /// Results are taken in the API's order, after --match, --max-price
+        /// and --suffix. The chosen domain is printed on stderr, then the
+        /// usual register flow runs, asking for confirmation unless --confirm.
+        #[arg(long, conflicts_with_all = ["summary", "only"])]
+        register_first: bool,
+
+        /// Only pick a domain costing at most this many euros per year.
+        #[arg(long, value_name = "EUR", requires = "register_first")]
+        max_price: Option<i32>,
+
+        /// Only pick a domain under this TLD (e.g. ".com").
+        #[arg(long, value_name = "TLD", requires = "register_first")]
+        suffix: Option<String>,
+
+        /// Registration period in years for --register-first (1-10).
+        #[arg(
+            short,
+            long,
+            default_value = "1",
+            value_parser = clap::value_parser!(i32).range(1..=10),
+            requires = "register_first"
+        )]
+        years: i32,
+
+        /// Register the chosen domain without a confirmation prompt.
+        #[arg(long, requires = "register_first")]
+        confirm: bool,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
     },
 
     /// Register a new domain.
@@ -65,8 +309,12 @@ enum Commands {
     /// Requires sufficient balance in your Njalla wallet.
     /// Top up at <https://njal.la/wallet/>
     Register {
-        /// Domain name to register (e.g., example.com).
-        domain: String,
+        /// Domain names to register (e.g., example.com example.net).
+        ///
+        /// Several domains are priced together, the wallet must cover the
+        /// combined total, and one confirmation covers them all.
+        #[arg(required = true, value_name = "DOMAIN", value_parser = types::validate_domain_name)]
+        domains: Vec<String>,
 
         /// Registration period in years (1-10).
         #[arg(short, long, default_value = "1", value_parser = clap::value_parser!(i32).range(1..=10))]
@@ -76,23 +324,105 @@ enum Commands {
         #[arg(long)]
         confirm: bool,
 
+        /// Print the price and a confirmation token instead of registering.
+        ///
+        /// Pass the token to --confirm-token to register once the quote is
+        /// approved, e.g. by a human in a scripted workflow.
+        #[arg(long, conflicts_with_all = ["confirm", "confirm_token", "wait", "assume_available"])]
+        reserve: bool,
+
+        /// Register without a prompt if the price quoted by --reserve still holds.
+        ///
+        /// Refuses if the price has changed since the token was issued.
+        #[arg(long, value_name = "TOKEN", conflicts_with_all = ["confirm", "assume_available"])]
+        confirm_token: Option<String>,
+
         /// Wait for registration to complete.
+        ///
+        /// Prints newline-delimited JSON: one progress line per poll, then the
+        /// final result line.
         #[arg(long)]
         wait: bool,
 
-        /// Timeout for --wait in seconds.
-        #[arg(long, default_value = "300")]
-        timeout: u64,
+        /// How long --wait polls before giving up, in seconds.
+        #[arg(long, default_value = "300", value_name = "SECS")]
+        wait_timeout: u64,
+
+        /// Skip the search availability and price check and register directly.
+        ///
+        /// Use when search results are incomplete or lagging for a TLD.
+        #[arg(long)]
+        assume_available: bool,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
     },
 
     /// Check domain status and details.
+    #[command(visible_alias = "info")]
     Status {
         /// Domain name to check.
+        #[arg(value_parser = types::validate_domain_name)]
         domain: String,
 
         /// Include DNS records in output.
         #[arg(long)]
         dns: bool,
+
+        /// Print the API's `get-domain` result unchanged, in its field order.
+        #[arg(long, conflicts_with = "dns")]
+        raw: bool,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+
+    /// Manage a single domain (e.g. get its transfer auth code).
+    Domain {
+        #[command(subcommand)]
+        command: DomainCommands,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, global = true, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+
+    /// Compare the domains of two accounts.
+    ///
+    /// Each account is a config file with its own `api_token`. Prints the
+    /// domains only in the first, only in the second, and in both.
+    Diff {
+        /// Config file of the first account.
+        first: std::path::PathBuf,
+
+        /// Config file of the second account.
+        second: std::path::PathBuf,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+
+    /// Audit all domains for expiry, transfer lock and DNS problems.
+    Doctor {
+        /// Warn about domains expiring within this many days.
+        #[arg(long, default_value = "30")]
+        expiry_days: i64,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+
+    /// Show tasks started by this CLI (e.g. non-waiting registrations).
+    ///
+    /// Task ids are kept in ./tasks.json; each is re-checked and dropped
+    /// once it has completed or failed.
+    Tasks {
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
     },
 
     /// Show or initialize configuration.
@@ -100,25 +430,138 @@ enum Commands {
         /// Initialize config file if it doesn't exist.
         #[arg(long)]
         init: bool,
+
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
     },
 
     /// Manage DNS records for a domain.
+    #[command(visible_alias = "record")]
     Dns {
         #[command(subcommand)]
         command: DnsCommands,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, global = true, value_name = "SECS")]
+        timeout: Option<u64>,
     },
 
     /// Manage wallet and payments.
     Wallet {
         #[command(subcommand)]
         command: WalletCommands,
+
+        /// API request timeout in seconds (overrides the global --timeout).
+        #[arg(long, global = true, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+}
+
+impl Commands {
+    /// Whether the command leaves the account unchanged, so `--repeat` may
+    /// re-run it.
+    fn is_read_only(&self) -> bool {
+        match self {
+            Self::Domains { .. }
+            | Self::Status { .. }
+            | Self::Domain { .. }
+            | Self::Diff { .. }
+            | Self::Doctor { .. }
+            | Self::Tasks { .. } => true,
+            Self::Search { register_first, .. } => !register_first,
+            Self::Register { .. } => false,
+            Self::Config { init, command } => {
+                !init && matches!(command, None | Some(ConfigCommands::Schema))
+            }
+            Self::Dns { command, .. } => match command {
+                DnsCommands::List { .. }
+                | DnsCommands::Export { .. }
+                | DnsCommands::Changed { .. } => true,
+                DnsCommands::Copy { dry_run, .. } | DnsCommands::Replace { dry_run, .. } => {
+                    *dry_run
+                }
+                DnsCommands::Add { .. }
+                | DnsCommands::Edit { .. }
+                | DnsCommands::Remove { .. }
+                | DnsCommands::Snapshot { .. }
+                | DnsCommands::Comment { .. } => false,
+            },
+            Self::Wallet { command, .. } => !matches!(command, WalletCommands::AddPayment { .. }),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum DomainCommands {
+    /// Print the EPP auth code for transferring a domain out of Njalla.
+    ///
+    /// The domain must be unlocked first. The code is never shown by
+    /// --debug or written to --trace-file.
+    #[command(name = "authcode")]
+    AuthCode {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print a config.toml documenting every supported key.
+    ///
+    /// Each key is commented out with an example value, so the output can
+    /// be redirected into a file and edited.
+    Schema,
+
+    /// Open ./config.toml in $VISUAL or $EDITOR, creating it if missing.
+    ///
+    /// Falls back to vi (notepad on Windows). The file is parsed after the
+    /// editor exits and any TOML error is reported.
+    Edit,
+
+    /// Replace the API token after validating it against the API.
+    ///
+    /// The previous config is kept in ./config.toml.bak until you run
+    /// `config rotate --confirm`, or restore it with `config rotate --revert`.
+    /// The new config.toml is rewritten from its values, so comments in it
+    /// are lost (they remain in the backup).
+    Rotate {
+        /// New API token to validate and save.
+        #[arg(
+            long,
+            value_name = "TOKEN",
+            required_unless_present_any = ["revert", "confirm"],
+            conflicts_with_all = ["revert", "confirm"]
+        )]
+        new: Option<String>,
+
+        /// Restore the previous config from the backup.
+        #[arg(long, conflicts_with = "confirm")]
+        revert: bool,
+
+        /// Keep the new token and delete the backup.
+        #[arg(long)]
+        confirm: bool,
     },
 }
 
 #[derive(Subcommand)]
 enum WalletCommands {
     /// Show current wallet balance.
-    Balance,
+    ///
+    /// With `track_balance = true` in the config, the balance is also
+    /// appended to ./balance.json for `wallet history`.
+    Balance {
+        /// Also show how many domains at this yearly price (EUR) the balance
+        /// pays for, rounded down.
+        #[arg(long, value_name = "EUR", value_parser = clap::value_parser!(i32).range(1..))]
+        affordable_at: Option<i32>,
+    },
+
+    /// Show balances recorded by `wallet balance`, with a sparkline.
+    ///
+    /// Needs `track_balance = true` in the config; keeps the last 365.
+    History,
 
     /// Add payment to refill wallet.
     AddPayment {
@@ -129,47 +572,160 @@ enum WalletCommands {
         /// Payment method.
         #[arg(short, long, value_enum)]
         via: types::PaymentMethod,
+
+        /// Print only the exact BTC amount, for wallets that don't parse
+        /// `bitcoin:` URIs.
+        #[arg(long)]
+        exact_btc: bool,
+
+        /// Open the payment URI in the default wallet app.
+        ///
+        /// Only on a desktop with stdout on a terminal; otherwise, or if the
+        /// handler fails, the URI is printed on stderr.
+        #[arg(long)]
+        open: bool,
     },
 
     /// Get details about a payment.
     GetPayment {
         /// Payment ID.
         id: String,
+
+        /// Open the payment URI in the default wallet app, as for add-payment.
+        #[arg(long)]
+        open: bool,
     },
 
     /// List transactions from the last 90 days.
-    Transactions,
+    Transactions {
+        /// Only show transactions in this state.
+        #[arg(long, value_enum)]
+        status: Option<types::TransactionStatus>,
+
+        #[command(flatten)]
+        only: OnlyArgs,
+
+        /// Print CSV for bookkeeping software instead of JSON.
+        ///
+        /// Columns: `date,description,amount_eur,amount_btc,invoice_url,status`.
+        /// Dates are ISO 8601 and the invoice PDF URL is the reference, e.g.
+        /// `wallet transactions --export-accounting > ledger.csv`.
+        #[arg(long)]
+        export_accounting: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum DnsCommands {
     /// List all DNS records for a domain.
+    ///
+    /// Records with the API's default TTL (10800, also when set explicitly)
+    /// are marked `"ttl_default": true`.
+    #[command(visible_alias = "ls")]
     List {
         /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
         domain: String,
+
+        /// Only show records with this name (e.g., "@", "www").
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Only show records of this type.
+        #[arg(short = 't', long, value_enum)]
+        record_type: Option<types::RecordType>,
+
+        /// Keep the API's record order instead of sorting by name, type, content.
+        #[arg(long)]
+        unsorted: bool,
+
+        /// Group records by type or name, e.g. `{"A": [...], "CNAME": [...]}`.
+        #[arg(long, value_enum, value_name = "FIELD")]
+        group_by: Option<output::GroupBy>,
+
+        /// Leave out records Njalla manages itself (apex NS at Njalla's nameservers).
+        #[arg(long)]
+        user_records_only: bool,
+
+        /// Print the API's `list-records` result unchanged, in its field order.
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "name", "record_type", "unsorted", "group_by", "user_records_only", "only",
+            ]
+        )]
+        raw: bool,
+
+        /// Print one tab-separated line per record instead of JSON.
+        ///
+        /// Fields: `id`, `name`, `type`, `ttl`, `prio`, `weight`, `port`,
+        /// `target`, `value`, `ssh_algorithm`, `ssh_type`, `content`. A stable
+        /// format for scripts: fields keep their order across versions,
+        /// `content` stays last, and new ones are inserted just before it.
+        #[arg(long, conflicts_with_all = ["group_by", "raw"])]
+        porcelain: bool,
+
+        #[command(flatten)]
+        only: OnlyArgs,
     },
 
     /// Add a new DNS record.
     Add {
         /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
         domain: String,
 
         /// Record type.
+        ///
+        /// ANAME is an apex-safe alias: Njalla resolves the target hostname and
+        /// serves its A/AAAA addresses at this name, so unlike CNAME it can sit
+        /// at "@" alongside MX and TXT records. Don't mix it with A/AAAA records
+        /// at the same name; adding an ANAME warns if there are any.
+        ///
+        /// SOA is not a type here: Njalla manages each zone's SOA record and
+        /// the API can neither list nor edit it.
         #[arg(short = 't', long, value_enum)]
         record_type: types::RecordType,
 
-        /// Record name (e.g., "@", "www").
+        /// Record name (e.g., "@", "www"). Quote wildcards: --name '*'.
+        ///
+        /// Defaults to "@" (the apex) for A, AAAA, MX and TXT records, with a
+        /// warning. Other types, CNAME included, need --name or --wildcard.
         #[arg(short, long)]
-        name: String,
+        name: Option<String>,
+
+        /// Create a wildcard record for a subtree instead of --name.
+        ///
+        /// Without a value the wildcard covers the whole zone (`*`);
+        /// `--wildcard api` creates `*.api`. Avoids shell globbing of `*`.
+        #[arg(
+            long,
+            value_name = "SUBTREE",
+            num_args = 0..=1,
+            default_missing_value = "@",
+            conflicts_with = "name"
+        )]
+        wildcard: Option<String>,
 
         /// Record content/value.
         #[arg(short, long)]
         content: Option<String>,
 
+        /// Read the record content from a file (e.g., a DKIM key).
+        #[arg(long, value_name = "PATH", conflicts_with = "content")]
+        content_file: Option<std::path::PathBuf>,
+
         /// TTL in seconds.
         #[arg(long)]
         ttl: Option<i32>,
 
+        /// TTL to use when --ttl is not given (overrides `default_ttl` in config).
+        #[arg(long, value_name = "SECONDS")]
+        default_ttl: Option<i32>,
+
+        #[command(flatten)]
+        ttl_policy: TtlPolicyArgs,
+
         /// Priority (MX, SRV, HTTPS, SVCB).
         #[arg(short, long)]
         priority: Option<i32>,
@@ -197,16 +753,63 @@ enum DnsCommands {
         /// SSH fingerprint type (SSHFP only, 1-2: SHA-1, SHA-256).
         #[arg(long)]
         ssh_type: Option<i32>,
+
+        /// Qualify a relative CNAME/MX/NS/SRV target with the domain.
+        ///
+        /// Zone-file style: "mail" becomes "mail.example.com" and "@" the apex.
+        /// Targets with a trailing dot or already ending in the domain are
+        /// kept. Each expansion is reported on stderr.
+        #[arg(long)]
+        fqdn: bool,
+
+        /// Don't compare the created record with the request.
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Also create a PTR in this reverse zone pointing back at the record.
+        ///
+        /// For A/AAAA records whose reverse zone (e.g., 2.0.192.in-addr.arpa)
+        /// is also managed on Njalla. The PTR is named after the address
+        /// inside the zone and points at the record's full name.
+        #[arg(long, value_name = "REVERSE_DOMAIN", value_parser = types::validate_domain_name)]
+        with_ptr: Option<String>,
+
+        /// Remove the new record again if its --with-ptr PTR cannot be created.
+        #[arg(long, requires = "with_ptr")]
+        atomic: bool,
+
+        /// Edit the record with the same name and type instead of adding another.
+        ///
+        /// For singletons such as an apex A record or a CNAME. Adds the record
+        /// if none matches, and fails without changes if several do.
+        #[arg(long, conflicts_with = "atomic")]
+        replace_existing: bool,
     },
 
     /// Edit an existing DNS record.
     Edit {
         /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
         domain: String,
 
         /// Record ID.
-        #[arg(short, long)]
-        id: String,
+        #[arg(short, long, required_unless_present = "json")]
+        id: Option<String>,
+
+        /// Read the whole record as JSON from a file, or "-" for stdin.
+        ///
+        /// Takes one record object as printed by `dns list`, with its "id";
+        /// every field is sent as given, so a listed record can be edited and
+        /// fed back.
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = [
+                "id", "name", "content", "content_file", "ttl", "priority", "weight",
+                "port", "target", "value", "ssh_algorithm", "ssh_type",
+            ]
+        )]
+        json: Option<std::path::PathBuf>,
 
         /// Record name (e.g., "@", "www").
         #[arg(short, long)]
@@ -216,6 +819,10 @@ enum DnsCommands {
         #[arg(short, long)]
         content: Option<String>,
 
+        /// Read the record content from a file (e.g., a DKIM key).
+        #[arg(long, value_name = "PATH", conflicts_with = "content")]
+        content_file: Option<std::path::PathBuf>,
+
         /// TTL in seconds.
         #[arg(long)]
         ttl: Option<i32>,
@@ -247,44 +854,676 @@ enum DnsCommands {
         /// SSH fingerprint type (SSHFP only, 1-2: SHA-1, SHA-256).
         #[arg(long)]
         ssh_type: Option<i32>,
+
+        /// Abort unless the record still has this fingerprint from `dns list`.
+        ///
+        /// Best effort: the record is read once and compared right before the
+        /// edit, which narrows but cannot close the race with other editors.
+        #[arg(long, value_name = "FINGERPRINT")]
+        if_unchanged: Option<String>,
+
+        #[command(flatten)]
+        ttl_policy: TtlPolicyArgs,
+    },
+
+    /// Export DNS records in another provider's import format.
+    ///
+    /// Records the format cannot express are skipped with a warning.
+    Export {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+
+        /// Target format.
+        #[arg(long, value_enum)]
+        format: export::ExportFormat,
+
+        /// Leave out records Njalla manages itself (apex NS at Njalla's nameservers).
+        #[arg(long)]
+        user_records_only: bool,
+    },
+
+    /// Copy DNS records from one domain to another.
+    ///
+    /// Hostnames under the source domain are rewritten to the destination,
+    /// apex NS records at Njalla's nameservers are skipped, and records the
+    /// destination already has are left alone.
+    Copy {
+        /// Domain to copy records from.
+        #[arg(value_parser = types::validate_domain_name)]
+        source: String,
+
+        /// Domain to copy records to.
+        #[arg(value_parser = types::validate_domain_name)]
+        destination: String,
+
+        /// Print the planned changes without applying them.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove destination records that are not in the source, after adding.
+        #[arg(long)]
+        replace: bool,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
+    },
+
+    /// Make a domain's records match a JSON file, adding, editing and removing.
+    ///
+    /// The file is an array of records as printed by `dns list`; ids are
+    /// optional. Unchanged records are kept and records with a surviving
+    /// name and type are edited in place. Adds run before edits and removals
+    /// last. Apex NS records at Njalla's nameservers are never touched.
+    Replace {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+
+        /// Desired records as JSON, or "-" for stdin.
+        #[arg(long, value_name = "PATH")]
+        file: std::path::PathBuf,
+
+        /// Print the planned changes without applying them.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Raise TTLs in the file below this to it, noting each on stderr.
+        ///
+        /// Meant for migrations, where stray low TTLs from the old provider
+        /// would otherwise be kept. Unlike `dns_ttl_min`, nothing is rejected.
+        #[arg(long, value_name = "SECS")]
+        floor_ttl: Option<i32>,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
     },
 
-    /// Remove a DNS record.
+    /// Remove one or more DNS records.
+    #[command(visible_alias = "rm", alias = "del")]
     Remove {
         /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+
+        /// Record ID (repeat to remove several records).
+        #[arg(short, long, required = true)]
+        id: Vec<String>,
+
+        #[command(flatten)]
+        bulk: BulkArgs,
+    },
+
+    /// Save the domain's records in ./snapshots.json for `dns changed`.
+    ///
+    /// Replaces the domain's previous snapshot; the time it was taken is
+    /// stored with it.
+    Snapshot {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+    },
+
+    /// Show records added, removed or modified since the last `dns snapshot`.
+    ///
+    /// Records are matched as `dns replace` matches them; a record whose
+    /// name and type stayed but whose other fields changed is modified.
+    Changed {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
+        domain: String,
+    },
+
+    /// Note why a record exists, in ./comments.json.
+    ///
+    /// Njalla has no comment field, so comments stay local; `dns list` shows
+    /// them. A comment follows its record by name, type and content if Njalla
+    /// gives the record a new id.
+    Comment {
+        /// Domain name.
+        #[arg(value_parser = types::validate_domain_name)]
         domain: String,
 
         /// Record ID.
         #[arg(short, long)]
         id: String,
+
+        /// Comment to set, replacing any previous one.
+        #[arg(long, value_name = "TEXT", required_unless_present = "clear")]
+        set: Option<String>,
+
+        /// Remove the record's comment.
+        #[arg(long, conflicts_with = "set")]
+        clear: bool,
     },
 }
 
+/// Error handling flags shared by commands that operate on several items.
+#[derive(clap::Args)]
+struct BulkArgs {
+    /// Stop at the first failed operation.
+    #[arg(long, conflicts_with = "continue_on_error")]
+    fail_fast: bool,
+
+    /// Attempt every operation, then exit non-zero if any failed (default).
+    #[arg(long)]
+    continue_on_error: bool,
+}
+
+impl BulkArgs {
+    fn mode(&self) -> commands::bulk::ErrorMode {
+        if self.fail_fast {
+            commands::bulk::ErrorMode::FailFast
+        } else {
+            commands::bulk::ErrorMode::ContinueOnError
+        }
+    }
+}
+
+/// `--only` filters shared by the list commands.
+#[derive(clap::Args)]
+struct OnlyArgs {
+    /// Only show items whose JSON field matches, e.g. `type==MX`.
+    ///
+    /// Operators: `==` (equals), `!=` (differs or missing), `~=`
+    /// (contains, ignoring case). Repeat to require several.
+    #[arg(long, value_name = "FIELD==VALUE")]
+    only: Vec<filter::FieldFilter>,
+}
+
+impl OnlyArgs {
+    fn filters(&self) -> &[filter::FieldFilter] {
+        &self.only
+    }
+}
+
+/// TTL bounds for commands that set a TTL, on top of the config's policy.
+#[derive(clap::Args)]
+struct TtlPolicyArgs {
+    /// Reject TTLs below this; may raise, but not lower, `dns_ttl_min` in config.
+    #[arg(long, value_name = "SECONDS")]
+    dns_ttl_min: Option<i32>,
+
+    /// Reject TTLs above this; may lower, but not raise, `dns_ttl_max` in config.
+    #[arg(long, value_name = "SECONDS")]
+    dns_ttl_max: Option<i32>,
+}
+
+impl TtlPolicyArgs {
+    fn limits(&self) -> commands::dns::TtlLimits {
+        commands::dns::TtlLimits {
+            min: self.dns_ttl_min,
+            max: self.dns_ttl_max,
+        }
+    }
+}
+
 fn main() {
-    if let Err(err) = run() {
-        eprintln!("Error: {err}");
+    let args = deprecated::rewrite_args(
+        std::env::args_os(),
+        Cli::command(),
+        deprecated::DEPRECATED_FLAGS,
+        deprecated::warn_deprecated,
+    );
+    let matches = Cli::command()
+        .try_get_matches_from(&args)
+        .unwrap_or_else(|e| exit_on_parse_error(&e, &args));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| exit_on_parse_error(&e, &args));
+    if cli.json_envelope {
+        if !run_enveloped(cli, &command_path(&matches)) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let explain = cli.explain;
+    if let Err(err) = run(cli, &args) {
+        eprintln!("{}", err.report(explain));
         std::process::exit(1);
     }
 }
 
-fn run() -> error::Result<()> {
-    let cli = Cli::parse();
+fn run(cli: Cli, args: &[std::ffi::OsString]) -> error::Result<()> {
+    if cli.repeat {
+        return run_repeated(&cli, args);
+    }
+    // Exports are meant for other tools, so they are never paged.
+    let mode = if matches!(
+        cli.command,
+        Commands::Dns {
+            command: DnsCommands::Export { .. },
+            ..
+        } | Commands::Wallet {
+            command: WalletCommands::Transactions {
+                export_accounting: true,
+                ..
+            },
+            ..
+        }
+    ) {
+        pager::PagerMode::Never
+    } else {
+        pager::PagerMode::from_flags(cli.pager, cli.no_pager)
+    };
+
+    let output = dispatch(cli)?;
+    let pager = pager::pager_for(mode, std::io::stdout().is_terminal(), output.text());
+    output.print(pager.as_deref())
+}
+
+/// Run the command for `--json-envelope`, printing its output or error as
+/// one envelope on stdout. Returns whether the command succeeded.
+fn run_enveloped(cli: Cli, command: &str) -> bool {
+    let start = std::time::Instant::now();
+    let (text, error) = match dispatch(cli).and_then(commands::CommandOutput::into_parts) {
+        Ok((text, error)) => (Some(text), error),
+        Err(e) => (None, Some(e)),
+    };
+    let elapsed_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let envelope = output::envelope(command, text.as_deref(), error.as_ref(), elapsed_ms);
+    match to_json(&envelope) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("Error: {e}"),
+    }
+    error.is_none()
+}
+
+/// Exit on a command-line error, printed as an envelope with `--json-envelope`.
+fn exit_on_parse_error(error: &clap::Error, args: &[std::ffi::OsString]) -> ! {
+    let Some(envelope) = parse_error_envelope(error, args) else {
+        error.exit()
+    };
+    match to_json(&envelope) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("Error: {e}"),
+    }
+    std::process::exit(error.exit_code());
+}
+
+/// The envelope for a command-line error, or `None` to let clap print it.
+///
+/// Only used when `args` ask for `--json-envelope`. Help and version output
+/// are not errors, so they are always left to clap. The command is empty
+/// since it could not be parsed.
+fn parse_error_envelope(
+    error: &clap::Error,
+    args: &[std::ffi::OsString],
+) -> Option<serde_json::Value> {
+    use clap::error::ErrorKind;
+
+    let wants_envelope = args
+        .iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--json-envelope");
+    let shows_help = matches!(
+        error.kind(),
+        ErrorKind::DisplayHelp
+            | ErrorKind::DisplayVersion
+            | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+    );
+    if !wants_envelope || shows_help {
+        return None;
+    }
+
+    // clap's text without the usage and help lines that follow a blank line.
+    let text = error.to_string();
+    let message: Vec<&str> = text
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .map(str::trim)
+        .collect();
+    let error = error::NjallaError::Input {
+        message: message.join(" ").trim_start_matches("error: ").to_string(),
+    };
+    Some(output::envelope("", None, Some(&error), 0))
+}
+
+/// The subcommand names in `matches`, e.g. `dns list`, with aliases resolved.
+fn command_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    names.join(" ")
+}
+
+/// Run a read-only command every `--interval` seconds until it fails.
+///
+/// Each run parses `args` again, since dispatching consumes the parsed
+/// command. Ctrl-C stops the loop; every run's output is printed in full
+/// before the next wait, so nothing is left half-written.
+fn run_repeated(cli: &Cli, args: &[std::ffi::OsString]) -> error::Result<()> {
+    if !cli.command.is_read_only() {
+        return Err(error::NjallaError::Input {
+            message: "--repeat only works with read-only commands".to_string(),
+        });
+    }
+    let interval = std::time::Duration::from_secs(cli.interval.unwrap_or(DEFAULT_REPEAT_INTERVAL));
+
+    repeat(interval, std::thread::sleep, |run| {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        eprintln!("--- run {run} at {now} ---");
+        dispatch(Cli::parse_from(args))?.print(None)
+    })
+}
+
+/// Call `run_once` with run numbers 1, 2, ... and `sleep` for `interval`
+/// between calls, until a run fails.
+fn repeat(
+    interval: std::time::Duration,
+    mut sleep: impl FnMut(std::time::Duration),
+    mut run_once: impl FnMut(u64) -> error::Result<()>,
+) -> error::Result<()> {
+    let mut run = 1;
+    loop {
+        run_once(run)?;
+        sleep(interval);
+        run += 1;
+    }
+}
+
+/// Run the parsed command and return its output for `main` to print.
+#[allow(clippy::too_many_lines)]
+fn dispatch(cli: Cli) -> error::Result<commands::CommandOutput> {
+    output::set_ascii_only(cli.ascii || output::detect_ascii_only());
+    output::set_compact(cli.compact);
+    output::set_with_schema(cli.with_schema);
+    // Only terminal output is paged, and deciding needs the whole text, so
+    // lists are streamed everywhere else.
+    output::set_streaming(!std::io::stdout().is_terminal());
+    output::set_hyperlinks(cli.hyperlinks && std::io::stdout().is_terminal());
+    output::set_progress_to_stderr(cli.json_envelope);
+    let options = client_options(&cli)?;
 
     match cli.command {
-        Commands::Domains => commands::domains::run(cli.debug),
-        Commands::Search { query } => commands::search::run(&query, cli.debug),
+        Commands::Domains {
+            status,
+            expired,
+            only,
+            porcelain,
+            timeout,
+        } => commands::domains::run(
+            status.as_ref(),
+            expired,
+            only.filters(),
+            porcelain,
+            &options.with_command_timeout(timeout),
+        ),
+        Commands::Search {
+            query,
+            match_mode,
+            register_first: true,
+            max_price,
+            suffix,
+            years,
+            confirm,
+            timeout,
+            ..
+        } => commands::search::run_register_first(
+            &query,
+            match_mode,
+            max_price,
+            suffix.as_deref(),
+            years,
+            confirm || commands::register::assume_yes_from_env(),
+            &options.with_command_timeout(timeout),
+        ),
+        Commands::Search {
+            query,
+            match_mode,
+            summary,
+            affordable,
+            only,
+            timeout,
+            ..
+        } => commands::search::run(
+            &query,
+            match_mode,
+            only.filters(),
+            summary,
+            affordable,
+            &options.with_command_timeout(timeout),
+        ),
         Commands::Register {
-            domain,
+            domains,
             years,
             confirm,
+            reserve,
+            confirm_token,
             wait,
+            wait_timeout,
+            assume_available,
+            bulk,
+        } => commands::register::run(
+            &domains,
+            years,
+            &commands::register::Approval::from_flags(
+                confirm || commands::register::assume_yes_from_env(),
+                reserve,
+                confirm_token,
+            ),
+            wait,
+            wait_timeout,
+            assume_available,
+            bulk.mode(),
+            &options,
+        ),
+        Commands::Status {
+            domain,
+            dns,
+            raw,
             timeout,
-        } => commands::register::run(&domain, years, confirm, wait, timeout, cli.debug),
-        Commands::Status { domain, dns } => commands::status::run(&domain, dns, cli.debug),
-        Commands::Config { init } => run_config(init),
-        Commands::Dns { command } => match command {
-            DnsCommands::List { domain } => commands::dns::run_list(&domain, cli.debug),
-            DnsCommands::Add {
+        } => commands::status::run(&domain, dns, raw, &options.with_command_timeout(timeout)),
+        Commands::Doctor {
+            expiry_days,
+            timeout,
+        } => commands::doctor::run(expiry_days, &options.with_command_timeout(timeout)),
+        Commands::Domain { command, timeout } => match command {
+            DomainCommands::AuthCode { domain } => {
+                commands::domain::run_auth_code(&domain, &options.with_command_timeout(timeout))
+            }
+        },
+        Commands::Diff {
+            first,
+            second,
+            timeout,
+        } => commands::diff::run(&first, &second, &options.with_command_timeout(timeout)),
+        Commands::Tasks { timeout } => commands::tasks::run(&options.with_command_timeout(timeout)),
+        Commands::Config { init, command } => match command {
+            Some(ConfigCommands::Rotate {
+                new,
+                revert,
+                confirm,
+            }) => {
+                if revert {
+                    commands::config::run_revert()
+                } else if confirm {
+                    commands::config::run_confirm()
+                } else {
+                    commands::config::run_rotate(&new.unwrap_or_default(), &options)
+                }
+            }
+            Some(ConfigCommands::Edit) => commands::config::run_edit(),
+            Some(ConfigCommands::Schema) => {
+                Ok(commands::CommandOutput::Text(config::annotated_template()))
+            }
+            None => run_config(init),
+        },
+        Commands::Dns { command, timeout } => {
+            run_dns(command, &options.with_command_timeout(timeout))
+        }
+        Commands::Wallet { command, timeout } => {
+            let options = options.with_command_timeout(timeout);
+            match command {
+                WalletCommands::Balance { affordable_at } => {
+                    commands::wallet::run_balance(affordable_at, &options)
+                }
+                WalletCommands::History => commands::wallet::run_history(),
+                WalletCommands::AddPayment {
+                    amount,
+                    via,
+                    exact_btc,
+                    open,
+                } => commands::wallet::run_add_payment(amount, via, exact_btc, open, &options),
+                WalletCommands::GetPayment { id, open } => {
+                    commands::wallet::run_get_payment(&id, open, &options)
+                }
+                WalletCommands::Transactions {
+                    status,
+                    only,
+                    export_accounting,
+                } => commands::wallet::run_transactions(
+                    status,
+                    only.filters(),
+                    export_accounting,
+                    &options,
+                ),
+            }
+        }
+    }
+}
+
+/// Build client options from the global flags, reading `--token-stdin`.
+fn client_options(cli: &Cli) -> error::Result<client::ClientOptions> {
+    let token = if cli.token_stdin {
+        let assume_yes = commands::register::assume_yes_from_env();
+        if !assume_yes
+            && matches!(
+                cli.command,
+                Commands::Register {
+                    confirm: false,
+                    reserve: false,
+                    confirm_token: None,
+                    ..
+                }
+            )
+        {
+            return Err(error::NjallaError::Input {
+                message: "--token-stdin uses stdin, so register needs --confirm".to_string(),
+            });
+        }
+        if !assume_yes
+            && matches!(
+                cli.command,
+                Commands::Search {
+                    register_first: true,
+                    confirm: false,
+                    ..
+                }
+            )
+        {
+            return Err(error::NjallaError::Input {
+                message: "--token-stdin uses stdin, so search --register-first needs --confirm"
+                    .to_string(),
+            });
+        }
+        if let Commands::Dns {
+            command:
+                DnsCommands::Edit {
+                    json: Some(path), ..
+                }
+                | DnsCommands::Replace { file: path, .. },
+            ..
+        } = &cli.command
+        {
+            if path.as_os_str() == "-" {
+                return Err(error::NjallaError::Input {
+                    message: "--token-stdin uses stdin, so the records need a file, not -"
+                        .to_string(),
+                });
+            }
+        }
+        Some(config::read_token(std::io::stdin().lock())?)
+    } else {
+        None
+    };
+
+    Ok(client::ClientOptions {
+        debug: cli.debug,
+        trace_file: cli.trace_file.clone(),
+        timeout_secs: cli.timeout,
+        base_url: cli.base_url.clone(),
+        retries: cli.retries,
+        token,
+        insecure_tls: cli.no_verify_tls,
+        max_concurrency: cli.max_concurrency,
+        strict_json: cli.strict_json,
+        deadline: cli.deadline.map(client::Deadline::after_secs),
+        print_curl: cli.print_curl,
+    })
+}
+
+#[allow(clippy::too_many_lines)]
+fn run_dns(
+    command: DnsCommands,
+    options: &client::ClientOptions,
+) -> error::Result<commands::CommandOutput> {
+    match command {
+        DnsCommands::List {
+            domain,
+            name,
+            record_type,
+            unsorted,
+            group_by,
+            user_records_only,
+            raw,
+            porcelain,
+            only,
+        } => commands::dns::run_list(
+            &domain,
+            name.as_deref(),
+            record_type,
+            unsorted,
+            group_by,
+            user_records_only,
+            only.filters(),
+            raw,
+            porcelain,
+            options,
+        ),
+        DnsCommands::Add {
+            domain,
+            record_type,
+            name,
+            wildcard,
+            content,
+            content_file,
+            ttl,
+            default_ttl,
+            ttl_policy,
+            priority,
+            weight,
+            port,
+            target,
+            value,
+            ssh_algorithm,
+            ssh_type,
+            fqdn,
+            no_verify,
+            with_ptr,
+            atomic,
+            replace_existing,
+        } => {
+            let name = match (wildcard, name) {
+                (Some(subtree), _) => commands::dns::wildcard_name(&subtree),
+                (None, Some(name)) => name,
+                (None, None) => {
+                    let name = commands::dns::default_record_name(record_type)?;
+                    eprintln!(
+                        "Warning: no --name given; adding the {record_type} record at {name}"
+                    );
+                    name.to_string()
+                }
+            };
+            let content = match content_file {
+                Some(path) => Some(commands::dns::read_content_file(&path, Some(record_type))?),
+                None => content,
+            };
+            let mut params = types::AddRecordParams {
                 domain,
                 record_type,
                 name,
@@ -297,40 +1536,49 @@ fn run() -> error::Result<()> {
                 value,
                 ssh_algorithm,
                 ssh_type,
-            } => {
-                let params = types::AddRecordParams {
-                    domain,
-                    record_type,
-                    name,
-                    content,
-                    ttl,
-                    priority,
-                    weight,
-                    port,
-                    target,
-                    value,
-                    ssh_algorithm,
-                    ssh_type,
-                };
-                commands::dns::run_add(&params, cli.debug)
+            };
+            if fqdn {
+                commands::dns::qualify_targets(&mut params);
             }
-            DnsCommands::Edit {
-                domain,
-                id,
-                name,
-                content,
-                ttl,
-                priority,
-                weight,
-                port,
-                target,
-                value,
-                ssh_algorithm,
-                ssh_type,
-            } => {
-                let params = types::EditRecordParams {
+            commands::dns::run_add(
+                params,
+                default_ttl,
+                ttl_policy.limits(),
+                !no_verify,
+                with_ptr.as_deref(),
+                atomic,
+                replace_existing,
+                options,
+            )
+        }
+        DnsCommands::Edit {
+            domain,
+            id,
+            name,
+            content,
+            content_file,
+            ttl,
+            priority,
+            weight,
+            port,
+            target,
+            value,
+            ssh_algorithm,
+            ssh_type,
+            json,
+            if_unchanged,
+            ttl_policy,
+        } => {
+            let params = if let Some(path) = json {
+                commands::dns::read_record_json(&domain, &path)?
+            } else {
+                let content = match content_file {
+                    Some(path) => Some(commands::dns::read_content_file(&path, None)?),
+                    None => content,
+                };
+                types::EditRecordParams {
                     domain,
-                    id,
+                    id: id.unwrap_or_default(),
                     name,
                     content,
                     ttl,
@@ -341,92 +1589,340 @@ fn run() -> error::Result<()> {
                     value,
                     ssh_algorithm,
                     ssh_type,
-                };
-                commands::dns::run_edit(&params, cli.debug)
-            }
-            DnsCommands::Remove { domain, id } => {
-                commands::dns::run_remove(&domain, &id, cli.debug)
-            }
-        },
-        Commands::Wallet { command } => match command {
-            WalletCommands::Balance => commands::wallet::run_balance(cli.debug),
-            WalletCommands::AddPayment { amount, via } => {
-                commands::wallet::run_add_payment(amount, via, cli.debug)
-            }
-            WalletCommands::GetPayment { id } => commands::wallet::run_get_payment(&id, cli.debug),
-            WalletCommands::Transactions => commands::wallet::run_transactions(cli.debug),
-        },
+                }
+            };
+            commands::dns::run_edit(
+                &params,
+                if_unchanged.as_deref(),
+                ttl_policy.limits(),
+                options,
+            )
+        }
+        DnsCommands::Export {
+            domain,
+            format,
+            user_records_only,
+        } => commands::dns::run_export(&domain, format, user_records_only, options),
+        DnsCommands::Replace {
+            domain,
+            file,
+            dry_run,
+            floor_ttl,
+            bulk,
+        } => commands::dns::run_replace(&domain, &file, dry_run, floor_ttl, bulk.mode(), options),
+        DnsCommands::Copy {
+            source,
+            destination,
+            dry_run,
+            replace,
+            bulk,
+        } => commands::dns::run_copy(
+            &source,
+            &destination,
+            dry_run,
+            replace,
+            bulk.mode(),
+            options,
+        ),
+        DnsCommands::Remove { domain, id, bulk } => {
+            commands::dns::run_remove(&domain, &id, bulk.mode(), options)
+        }
+        DnsCommands::Snapshot { domain } => commands::dns::run_snapshot(&domain, options),
+        DnsCommands::Changed { domain } => commands::dns::run_changed(&domain, options),
+        DnsCommands::Comment {
+            domain, id, set, ..
+        } => commands::dns::run_comment(&domain, &id, set.as_deref(), options),
     }
 }
 
-fn run_config(init: bool) -> error::Result<()> {
+fn run_config(init: bool) -> error::Result<commands::CommandOutput> {
     use std::path::Path;
 
     let config_path = Path::new("config.toml");
 
     if init {
-        if config_path.exists() {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&serde_json::json!({
+        if !config::Config::init(config_path)? {
+            let placeholder = config::Config::load_file(config_path)
+                .is_ok_and(|config| config.has_placeholder_token());
+            let message = if placeholder {
+                config::PLACEHOLDER_TOKEN_MESSAGE
+            } else {
+                "Config file already exists"
+            };
+            return Ok(commands::CommandOutput::Text(to_json(
+                &serde_json::json!({
                     "status": "exists",
                     "path": "./config.toml",
-                    "message": "Config file already exists"
-                }))?
-            );
-            return Ok(());
+                    "message": message
+                }),
+            )?));
         }
 
-        let template = r#"# Njalla CLI Configuration
-# Get your API token from: https://njal.la → Settings → API
-
-api_token = ""
-"#;
-        std::fs::write(config_path, template).map_err(|e| error::NjallaError::Config {
-            message: format!("Failed to write config file: {e}"),
-        })?;
-
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&serde_json::json!({
+        return Ok(commands::CommandOutput::Text(to_json(
+            &serde_json::json!({
                 "status": "created",
                 "path": "./config.toml",
                 "message": "Config file created. Edit to add your API token from https://njal.la/settings/api/"
-            }))?
-        );
-        return Ok(());
+            }),
+        )?));
     }
 
     // Show current config status
     let config = config::Config::load()?;
 
-    let token_info = if let Ok(token) = config.api_token() {
-        // Show masked token
-        let masked = if token.len() > 8 {
-            format!("{}...{}", &token[..4], &token[token.len() - 4..])
-        } else {
-            "****".to_string()
+    let token = config.api_token.as_deref();
+    let from_env = std::env::var("NJALLA_API_TOKEN").is_ok_and(|t| !t.trim().is_empty());
+
+    Ok(commands::CommandOutput::Text(to_json(
+        &commands::config::config_status(config_path.exists(), token, from_env),
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Commands {
+        Cli::try_parse_from(std::iter::once("njalla").chain(args.iter().copied()))
+            .unwrap()
+            .command
+    }
+
+    #[test]
+    fn command_path_names_nested_subcommands() {
+        let path = |args: &[&str]| {
+            command_path(
+                &Cli::command()
+                    .try_get_matches_from(std::iter::once("njalla").chain(args.iter().copied()))
+                    .unwrap(),
+            )
         };
-        serde_json::json!({
-            "configured": true,
-            "masked_token": masked,
-            "source": if std::env::var("NJALLA_API_TOKEN").is_ok() { "env" } else { "config file" }
-        })
-    } else {
-        serde_json::json!({
-            "configured": false,
-            "message": "Run 'njalla config --init' to create a config file"
-        })
-    };
+        assert_eq!(path(&["ls"]), "domains");
+        assert_eq!(
+            path(&[
+                "--json-envelope",
+                "record",
+                "rm",
+                "example.com",
+                "--id",
+                "1"
+            ]),
+            "dns remove"
+        );
+    }
 
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&serde_json::json!({
-            "config_file": "./config.toml",
-            "file_exists": config_path.exists(),
-            "api_token": token_info
-        }))?
-    );
+    #[test]
+    fn dns_add_parses_without_name() {
+        assert!(matches!(
+            parse(&["dns", "add", "example.com", "-t", "a", "-c", "192.0.2.1"]),
+            Commands::Dns {
+                command: DnsCommands::Add { name: None, .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn top_level_aliases_dispatch_to_commands() {
+        assert!(matches!(parse(&["ls"]), Commands::Domains { .. }));
+        assert!(matches!(parse(&["list"]), Commands::Domains { .. }));
+        assert!(matches!(
+            parse(&["find", "bitcoin"]),
+            Commands::Search { .. }
+        ));
+        assert!(matches!(
+            parse(&["info", "example.com"]),
+            Commands::Status { .. }
+        ));
+    }
+
+    #[test]
+    fn dns_aliases_dispatch_to_subcommands() {
+        for group in ["dns", "record"] {
+            assert!(matches!(
+                parse(&[group, "ls", "example.com"]),
+                Commands::Dns {
+                    command: DnsCommands::List { .. },
+                    ..
+                }
+            ));
+            for remove in ["remove", "rm", "del"] {
+                assert!(matches!(
+                    parse(&[group, remove, "example.com", "--id", "1"]),
+                    Commands::Dns {
+                        command: DnsCommands::Remove { .. },
+                        ..
+                    }
+                ));
+            }
+        }
+    }
+
+    fn parse_cli(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("njalla").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn register_help_shows_only_wait_timeout() {
+        let help = Cli::command()
+            .find_subcommand_mut("register")
+            .unwrap()
+            .render_long_help()
+            .to_string();
+        assert!(help.contains("--wait-timeout"));
+        assert!(!help.contains("--timeout"));
+    }
+
+    #[test]
+    fn repeat_reruns_until_a_run_fails() {
+        let mut runs = Vec::new();
+        let mut sleeps = 0;
+        let result = repeat(
+            std::time::Duration::from_secs(5),
+            |interval| {
+                assert_eq!(interval.as_secs(), 5);
+                sleeps += 1;
+            },
+            |run| {
+                runs.push(run);
+                if run == 3 {
+                    Err(error::NjallaError::Api {
+                        message: "down".to_string(),
+                    })
+                } else {
+                    Ok(())
+                }
+            },
+        );
+
+        assert!(matches!(result, Err(error::NjallaError::Api { .. })));
+        assert_eq!(runs, [1, 2, 3]);
+        assert_eq!(sleeps, 2);
+    }
+
+    #[test]
+    fn repeat_is_only_allowed_for_read_only_commands() {
+        for args in [
+            &["domains", "--repeat", "--interval", "3600"][..],
+            &["dns", "list", "example.com", "--repeat"],
+            &["wallet", "balance", "--repeat"],
+            &["dns", "copy", "a.com", "b.com", "--dry-run", "--repeat"],
+        ] {
+            let cli = parse_cli(args).unwrap();
+            assert!(cli.repeat);
+            assert!(cli.command.is_read_only(), "{args:?}");
+        }
+
+        for args in [
+            &["register", "example.com", "--repeat"][..],
+            &["dns", "remove", "example.com", "--id", "1", "--repeat"],
+            &[
+                "wallet",
+                "add-payment",
+                "-a",
+                "15",
+                "-v",
+                "bitcoin",
+                "--repeat",
+            ],
+            &["search", "bitcoin", "--register-first", "--repeat"],
+        ] {
+            let cli = parse_cli(args).unwrap();
+            assert!(!cli.command.is_read_only(), "{args:?}");
+            let args = args
+                .iter()
+                .map(std::ffi::OsString::from)
+                .collect::<Vec<_>>();
+            assert!(matches!(
+                run_repeated(&cli, &args),
+                Err(error::NjallaError::Input { .. })
+            ));
+        }
+
+        assert!(parse_cli(&["domains", "--interval", "60"]).is_err());
+    }
+
+    #[test]
+    fn deprecated_flag_still_parses_with_one_warning() {
+        let mut warnings = 0;
+        let args = deprecated::rewrite_args(
+            [
+                "njalla",
+                "--timeout",
+                "5",
+                "register",
+                "example.com",
+                "--wait",
+                "--timeout",
+                "60",
+            ],
+            Cli::command(),
+            deprecated::DEPRECATED_FLAGS,
+            |_, _| warnings += 1,
+        );
+
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        assert_eq!(warnings, 1);
+        assert_eq!(cli.timeout, Some(5));
+        assert!(matches!(
+            cli.command,
+            Commands::Register {
+                wait_timeout: 60,
+                ..
+            }
+        ));
+    }
 
-    Ok(())
+    #[test]
+    fn json_envelope_wraps_usage_errors_but_not_help() {
+        let envelope_for = |args: &[&str]| {
+            let args: Vec<std::ffi::OsString> = std::iter::once("njalla")
+                .chain(args.iter().copied())
+                .map(Into::into)
+                .collect();
+            let Err(error) = Cli::try_parse_from(&args) else {
+                panic!("{args:?} should not parse");
+            };
+            parse_error_envelope(&error, &args)
+        };
+
+        let envelope = envelope_for(&["--json-envelope", "no-such-command"]).unwrap();
+        assert_eq!(envelope["ok"], false);
+        assert_eq!(
+            envelope["error"]["message"],
+            "Invalid input: unrecognized subcommand 'no-such-command'"
+        );
+
+        let envelope = envelope_for(&["--json-envelope", "--repeat", "domains"]).unwrap();
+        assert!(envelope["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("cannot be used with"));
+
+        let envelope = envelope_for(&["--json-envelope", "dns", "list"]).unwrap();
+        assert_eq!(
+            envelope["error"]["message"],
+            "Invalid input: the following required arguments were not provided: <DOMAIN>"
+        );
+
+        assert!(envelope_for(&["no-such-command"]).is_none());
+        assert!(envelope_for(&["--json-envelope", "--help"]).is_none());
+    }
+
+    #[test]
+    fn deprecated_flag_is_left_alone_on_other_subcommands() {
+        let args = ["njalla", "search", "register", "--timeout", "5"];
+        let mut warnings = 0;
+        let rewritten = deprecated::rewrite_args(
+            args,
+            Cli::command(),
+            deprecated::DEPRECATED_FLAGS,
+            |_, _| warnings += 1,
+        );
+
+        assert_eq!(rewritten, args);
+        assert_eq!(warnings, 0);
+        assert!(Cli::try_parse_from(rewritten).is_ok());
+    }
 }
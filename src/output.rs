@@ -1,7 +1,395 @@
 //! Output formatting for CLI commands.
 
-use crate::error::Result;
-use crate::types::{Domain, MarketDomain, Payment, Record, Transaction, WalletBalance};
+use crate::balance_log::BalancePoint;
+use crate::client::DEFAULT_RECORD_TTL;
+use crate::error::{NjallaError, Result};
+use crate::filter::{matching, FieldFilter};
+use crate::types::{
+    Domain, MarketDomain, Payment, PaymentStatus, Record, RecordType, Transaction,
+    TransactionStatus, WalletBalance,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether output must be restricted to ASCII.
+static ASCII_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Whether JSON is printed on a single line instead of pretty-printed.
+static COMPACT: AtomicBool = AtomicBool::new(false);
+
+/// Whether list and status output is wrapped with `_schema_version`.
+static WITH_SCHEMA: AtomicBool = AtomicBool::new(false);
+
+/// Version of the list and status JSON shapes.
+///
+/// Bump this whenever a field in that output is renamed, removed or
+/// changes type.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Whether list commands stream JSON to stdout instead of returning text.
+static STREAMING: AtomicBool = AtomicBool::new(false);
+
+/// Whether payment links are wrapped in OSC 8 terminal hyperlinks.
+static HYPERLINKS: AtomicBool = AtomicBool::new(false);
+
+/// Whether progress lines and prompts go to stderr instead of stdout.
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Restrict all formatted output to ASCII.
+///
+/// Used for terminals that cannot render UTF-8 (e.g. `€` from the API).
+pub fn set_ascii_only(enabled: bool) {
+    ASCII_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+/// Print JSON on a single line with no extra whitespace.
+///
+/// List commands then emit a bare top-level array on one line.
+pub fn set_compact(enabled: bool) {
+    COMPACT.store(enabled, Ordering::Relaxed);
+}
+
+/// Wrap list and status output as `{"_schema_version": N, "data": ...}`.
+pub fn set_with_schema(enabled: bool) {
+    WITH_SCHEMA.store(enabled, Ordering::Relaxed);
+}
+
+/// Stream list output straight to stdout rather than building it as a
+/// string first.
+///
+/// Paged output needs the whole text, so callers should only enable this
+/// when stdout is not a terminal.
+pub fn set_streaming(enabled: bool) {
+    STREAMING.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether list output should be streamed; see [`set_streaming`].
+#[must_use]
+pub fn streaming() -> bool {
+    STREAMING.load(Ordering::Relaxed)
+}
+
+/// Wrap payment `uri`/`url`/`pdf` values in OSC 8 hyperlinks.
+///
+/// The escape bytes make the output invalid JSON, so callers should only
+/// enable this for an interactive terminal.
+pub fn set_hyperlinks(enabled: bool) {
+    HYPERLINKS.store(enabled, Ordering::Relaxed);
+}
+
+/// Write progress lines and prompts to stderr, leaving stdout to the result.
+///
+/// Used with `--json-envelope`, whose envelope must be all there is on stdout.
+pub fn set_progress_to_stderr(enabled: bool) {
+    PROGRESS_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+/// Where progress lines (e.g. `register --wait` polls) and prompts go; see
+/// [`set_progress_to_stderr`].
+#[must_use]
+pub fn progress_writer() -> Box<dyn io::Write> {
+    if PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+/// Detect whether ASCII-only output should be used.
+///
+/// True when `NJALLA_ASCII` is set to a non-empty value other than `0`, or
+/// when the locale (`LC_ALL`, `LC_CTYPE`, `LANG`) is set to a non-UTF-8 value.
+#[must_use]
+pub fn detect_ascii_only() -> bool {
+    if let Ok(value) = std::env::var("NJALLA_ASCII") {
+        return !value.is_empty() && value != "0";
+    }
+
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+
+    locale.is_some_and(|value| {
+        let value = value.to_ascii_lowercase();
+        !value.contains("utf-8") && !value.contains("utf8")
+    })
+}
+
+/// Serialize a value as JSON, honouring ASCII-only and compact modes.
+///
+/// All command output goes through here so both modes apply everywhere.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    to_json_with(
+        value,
+        ASCII_ONLY.load(Ordering::Relaxed),
+        COMPACT.load(Ordering::Relaxed),
+    )
+}
+
+fn to_json_with<T: Serialize + ?Sized>(
+    value: &T,
+    ascii_only: bool,
+    compact: bool,
+) -> Result<String> {
+    let json = if compact {
+        serde_json::to_string(value)?
+    } else {
+        serde_json::to_string_pretty(value)?
+    };
+    if ascii_only {
+        Ok(escape_non_ascii(&json))
+    } else {
+        Ok(json)
+    }
+}
+
+/// Serialize a value as one line of newline-delimited JSON, such as a
+/// progress line, honouring ASCII-only mode and adding the schema version if
+/// enabled.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn to_json_line<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    to_json_with(
+        &versioned(value, WITH_SCHEMA.load(Ordering::Relaxed))?,
+        ASCII_ONLY.load(Ordering::Relaxed),
+        true,
+    )
+}
+
+/// Serialize list or status output, adding the schema version if enabled.
+fn to_versioned_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    to_json(&versioned(value, WITH_SCHEMA.load(Ordering::Relaxed))?)
+}
+
+fn versioned<T: Serialize + ?Sized>(value: &T, with_schema: bool) -> Result<serde_json::Value> {
+    let data = serde_json::to_value(value)?;
+    if with_schema {
+        Ok(serde_json::json!({ "_schema_version": SCHEMA_VERSION, "data": data }))
+    } else {
+        Ok(data)
+    }
+}
+
+/// The `--json-envelope` wrapper around a command's output:
+/// `{"ok", "command", "data", "error", "elapsed_ms"}`.
+///
+/// `text` is the command's normal output, kept as a string if it is not JSON
+/// (e.g. a zone file export), or `None` if the command failed before
+/// producing any. `error` is only present on failure.
+#[must_use]
+pub fn envelope(
+    command: &str,
+    text: Option<&str>,
+    error: Option<&NjallaError>,
+    elapsed_ms: u64,
+) -> serde_json::Value {
+    let data = text.map_or(serde_json::Value::Null, |text| {
+        serde_json::from_str(text).unwrap_or_else(|_| text.into())
+    });
+    let mut envelope = serde_json::json!({
+        "ok": error.is_none(),
+        "command": command,
+        "data": data,
+    });
+    if let Some(error) = error {
+        envelope["error"] = serde_json::json!({
+            "message": error.to_string(),
+            "hint": error.remediation(),
+        });
+    }
+    envelope["elapsed_ms"] = elapsed_ms.into();
+    envelope
+}
+
+/// Streamed list or status output, bare or wrapped with the schema version
+/// like [`versioned`], but serialized without building a `Value` first.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Versioned<'a, T: ?Sized> {
+    Bare(&'a T),
+    Wrapped {
+        #[serde(rename = "_schema_version")]
+        schema_version: u32,
+        data: &'a T,
+    },
+}
+
+impl<'a, T: ?Sized> Versioned<'a, T> {
+    fn new(value: &'a T, with_schema: bool) -> Self {
+        if with_schema {
+            Self::Wrapped {
+                schema_version: SCHEMA_VERSION,
+                data: value,
+            }
+        } else {
+            Self::Bare(value)
+        }
+    }
+}
+
+/// Serialize a value as JSON straight to `writer`, producing exactly the
+/// bytes [`to_json`] would return.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization or writing fails.
+pub fn write_json<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    write_json_with(
+        writer,
+        value,
+        ASCII_ONLY.load(Ordering::Relaxed),
+        COMPACT.load(Ordering::Relaxed),
+    )
+}
+
+fn write_json_with<W: io::Write, T: Serialize + ?Sized>(
+    writer: W,
+    value: &T,
+    ascii_only: bool,
+    compact: bool,
+) -> Result<()> {
+    if ascii_only {
+        let writer = AsciiWriter {
+            inner: writer,
+            pending: Vec::new(),
+        };
+        write_json_raw(writer, value, compact)
+    } else {
+        write_json_raw(writer, value, compact)
+    }
+}
+
+fn write_json_raw<W: io::Write, T: Serialize + ?Sized>(
+    writer: W,
+    value: &T,
+    compact: bool,
+) -> Result<()> {
+    let written = if compact {
+        serde_json::to_writer(writer, value)
+    } else {
+        serde_json::to_writer_pretty(writer, value)
+    };
+    written.map_err(|e| {
+        if e.is_io() {
+            NjallaError::Config {
+                message: format!("Failed to write output: {e}"),
+            }
+        } else {
+            e.into()
+        }
+    })
+}
+
+/// Stream list or status output, adding the schema version if enabled.
+fn write_versioned_json<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    write_json(
+        writer,
+        &Versioned::new(value, WITH_SCHEMA.load(Ordering::Relaxed)),
+    )
+}
+
+/// Writer applying [`escape_non_ascii`] to streamed JSON.
+struct AsciiWriter<W> {
+    inner: W,
+    /// Start of a character whose remaining bytes have not been written yet.
+    pending: Vec<u8>,
+}
+
+impl<W: io::Write> io::Write for AsciiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).unwrap_or_default();
+        self.inner.write_all(escape_non_ascii(text).as_bytes())?;
+        self.pending.drain(..valid);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Replace non-ASCII characters with JSON `\uXXXX` escapes.
+///
+/// Non-ASCII can only appear inside JSON strings, so the result is still
+/// valid JSON and decodes to the same value.
+fn escape_non_ascii(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                let _ = write!(out, "\\u{unit:04x}");
+            }
+        }
+    }
+    out
+}
+
+/// Sort key for deterministic record output: name, then type, then content.
+#[must_use]
+pub fn record_sort_key(r: &Record) -> (&str, String, &str) {
+    (
+        r.name.as_str(),
+        r.record_type.to_string(),
+        r.content.as_deref().unwrap_or_default(),
+    )
+}
+
+/// Field to group `dns list` output by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Record type (A, CNAME, ...).
+    Type,
+    /// Record name (@, www, ...).
+    Name,
+}
+
+/// Bucket records by type or name, keeping their order within each group.
+#[must_use]
+pub fn group_records(records: Vec<Record>, by: GroupBy) -> BTreeMap<String, Vec<Record>> {
+    let mut groups: BTreeMap<String, Vec<Record>> = BTreeMap::new();
+    for record in records {
+        let key = match by {
+            GroupBy::Type => record.record_type.to_string(),
+            GroupBy::Name => record.name.clone(),
+        };
+        groups.entry(key).or_default().push(record);
+    }
+    groups
+}
+
+/// Number of records of each type.
+#[must_use]
+pub fn count_by_type(records: &[Record]) -> BTreeMap<RecordType, usize> {
+    let mut counts = BTreeMap::new();
+    for record in records {
+        *counts.entry(record.record_type).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Sort records into a stable order, since the API order isn't stable.
+pub fn sort_records(records: &mut [Record]) {
+    records.sort_by(|a, b| record_sort_key(a).cmp(&record_sort_key(b)));
+}
 
 /// Format a single DNS record for output.
 ///
@@ -9,16 +397,120 @@ use crate::types::{Domain, MarketDomain, Payment, Record, Transaction, WalletBal
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_record(record: &Record) -> Result<String> {
-    Ok(serde_json::to_string_pretty(record)?)
+    to_json(record)
+}
+
+/// A record in list output, flagged when its TTL is the API default.
+#[derive(Serialize)]
+struct ListedRecord<'a> {
+    #[serde(flatten)]
+    record: &'a Record,
+
+    /// TTL is missing or equals `DEFAULT_RECORD_TTL`. The API does not say
+    /// whether a TTL was set explicitly, so an explicit 10800 is flagged too.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    ttl_default: bool,
+
+    /// Local comment from `dns comment`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'a str>,
+
+    /// [`Record::fingerprint`], for `dns edit --if-unchanged`.
+    fingerprint: String,
+}
+
+fn listed<'a>(
+    records: &'a [Record],
+    comments: &'a BTreeMap<String, String>,
+) -> Vec<ListedRecord<'a>> {
+    records
+        .iter()
+        .map(|record| ListedRecord {
+            record,
+            ttl_default: record.ttl.is_none_or(|ttl| ttl == DEFAULT_RECORD_TTL),
+            comment: comments.get(&record.id).map(String::as_str),
+            fingerprint: record.fingerprint(),
+        })
+        .collect()
+}
+
+/// Keep the records whose list output passes every `--only` filter.
+///
+/// Filters see each record as [`format_records`] prints it, so `comment`,
+/// `ttl_default` and `fingerprint` can be matched as well as API fields.
+///
+/// # Errors
+///
+/// Returns an error if a record cannot be serialized.
+pub fn retain_listed(
+    records: &mut Vec<Record>,
+    comments: &BTreeMap<String, String>,
+    filters: &[FieldFilter],
+) -> Result<()> {
+    let mut keep = matching(&listed(records, comments), filters)?.into_iter();
+    records.retain(|_| keep.next().unwrap_or(false));
+    Ok(())
 }
 
 /// Format a list of DNS records for output.
 ///
+/// Records whose TTL is the API default get `"ttl_default": true`, and
+/// records with an entry in `comments` (keyed by record id) a `"comment"`.
+/// Every record carries its `"fingerprint"`.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_records(records: &[Record], comments: &BTreeMap<String, String>) -> Result<String> {
+    to_versioned_json(&listed(records, comments))
+}
+
+/// Stream [`format_records`] output to `writer`.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization or writing fails.
+pub fn write_records<W: io::Write>(
+    writer: W,
+    records: &[Record],
+    comments: &BTreeMap<String, String>,
+) -> Result<()> {
+    write_versioned_json(writer, &listed(records, comments))
+}
+
+/// Format grouped DNS records as an object of sorted group keys.
+///
+/// Records are annotated as in [`format_records`].
+///
 /// # Errors
 ///
 /// Returns an error if JSON serialization fails.
-pub fn format_records(records: &[Record]) -> Result<String> {
-    Ok(serde_json::to_string_pretty(records)?)
+pub fn format_grouped_records(
+    groups: &BTreeMap<String, Vec<Record>>,
+    comments: &BTreeMap<String, String>,
+) -> Result<String> {
+    let groups: BTreeMap<&str, Vec<ListedRecord<'_>>> = groups
+        .iter()
+        .map(|(key, records)| (key.as_str(), listed(records, comments)))
+        .collect();
+    to_versioned_json(&groups)
+}
+
+/// Stream [`format_grouped_records`] output to `writer`.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization or writing fails.
+pub fn write_grouped_records<W: io::Write>(
+    writer: W,
+    groups: &BTreeMap<String, Vec<Record>>,
+    comments: &BTreeMap<String, String>,
+) -> Result<()> {
+    let groups: BTreeMap<&str, Vec<ListedRecord<'_>>> = groups
+        .iter()
+        .map(|(key, records)| (key.as_str(), listed(records, comments)))
+        .collect();
+    write_versioned_json(writer, &groups)
 }
 
 /// Format a list of domains for output.
@@ -27,7 +519,93 @@ pub fn format_records(records: &[Record]) -> Result<String> {
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_domains(domains: &[Domain]) -> Result<String> {
-    Ok(serde_json::to_string_pretty(domains)?)
+    to_versioned_json(domains)
+}
+
+/// Stream [`format_domains`] output to `writer`.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization or writing fails.
+pub fn write_domains<W: io::Write>(writer: W, domains: &[Domain]) -> Result<()> {
+    write_versioned_json(writer, domains)
+}
+
+/// Escape a `--porcelain` field so it cannot split a line or a field:
+/// backslash, tab, newline and carriage return become `\\`, `\t`, `\n`
+/// and `\r`.
+fn porcelain_field(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '\t' => "\\t".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// One `--porcelain` line: fields joined by tabs, missing values empty.
+fn porcelain_line(fields: &[Option<String>]) -> String {
+    fields
+        .iter()
+        .map(|field| field.as_deref().map(porcelain_field).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Format domains as `--porcelain` lines: `name`, `status`, `expiry`.
+///
+/// The porcelain format is a stable contract: the fields and their order
+/// never change, and new fields are only ever appended.
+#[must_use]
+pub fn format_domains_porcelain(domains: &[Domain]) -> String {
+    domains
+        .iter()
+        .map(|d| {
+            porcelain_line(&[
+                Some(d.name.clone()),
+                Some(d.status.clone()),
+                d.expiry.clone(),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format records as `--porcelain` lines: `id`, `name`, `type`, `ttl`,
+/// `prio`, `weight`, `port`, `target`, `value`, `ssh_algorithm`,
+/// `ssh_type`, `content`.
+///
+/// Stable like [`format_domains_porcelain`], except that `content` always
+/// stays the last field, since it is the one most likely to be long (and
+/// `cut -f12-` keeps working if it ever holds an escaped tab). New fields are
+/// inserted just before `content` rather than appended.
+#[must_use]
+pub fn format_records_porcelain(records: &[Record]) -> String {
+    let number = |n: Option<i32>| n.map(|n| n.to_string());
+    records
+        .iter()
+        .map(|r| {
+            porcelain_line(&[
+                Some(r.id.clone()),
+                Some(r.name.clone()),
+                Some(r.record_type.to_string()),
+                number(r.ttl),
+                number(r.priority),
+                number(r.weight),
+                number(r.port),
+                r.target.clone(),
+                r.value.clone(),
+                number(r.ssh_algorithm),
+                number(r.ssh_type),
+                r.content.clone(),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// Format market domain search results.
@@ -36,11 +614,103 @@ pub fn format_domains(domains: &[Domain]) -> Result<String> {
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_market_domains(domains: &[MarketDomain]) -> Result<String> {
-    Ok(serde_json::to_string_pretty(domains)?)
+    to_versioned_json(domains)
+}
+
+/// A search result flagged with whether the wallet balance covers it.
+#[derive(Serialize)]
+struct AffordableMarketDomain<'a> {
+    #[serde(flatten)]
+    domain: &'a MarketDomain,
+
+    /// Available and priced at or under the balance.
+    affordable: bool,
+}
+
+/// Format search results for `search --affordable`, flagging each result
+/// the `balance` (EUR) can pay a year of. Taken domains are never affordable.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_market_domains_affordable(domains: &[MarketDomain], balance: i32) -> Result<String> {
+    let annotated: Vec<AffordableMarketDomain<'_>> = domains
+        .iter()
+        .map(|domain| AffordableMarketDomain {
+            domain,
+            affordable: domain.status == "available" && domain.price <= balance,
+        })
+        .collect();
+    to_versioned_json(&annotated)
+}
+
+/// Price totals over the available results of a domain search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchSummary {
+    /// Number of search results.
+    pub results: usize,
+    /// Number of results available for registration.
+    pub available: usize,
+    /// Sum of yearly prices (EUR) of available results.
+    pub available_total: i32,
+    /// Cheapest available yearly price (EUR).
+    pub cheapest: Option<i32>,
+    /// Most expensive available yearly price (EUR).
+    pub priciest: Option<i32>,
+    /// Human-readable one-line summary.
+    pub text: String,
+}
+
+impl SearchSummary {
+    /// Summarize search results; taken domains are excluded from totals.
+    #[must_use]
+    pub fn from_results(domains: &[MarketDomain]) -> Self {
+        let prices: Vec<i32> = domains
+            .iter()
+            .filter(|d| d.status == "available")
+            .map(|d| d.price)
+            .collect();
+
+        let available_total = prices.iter().sum();
+        let cheapest = prices.iter().min().copied();
+        let priciest = prices.iter().max().copied();
+
+        let mut text = format!("{} of {} available", prices.len(), domains.len());
+        if let (Some(min), Some(max)) = (cheapest, priciest) {
+            let _ = write!(
+                text,
+                ". Available total: €{available_total} (cheapest €{min}, priciest €{max})"
+            );
+        }
+
+        Self {
+            results: domains.len(),
+            available: prices.len(),
+            available_total,
+            cheapest,
+            priciest,
+            text,
+        }
+    }
+}
+
+/// Format market domain search results with a price summary.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_market_domains_with_summary(domains: &[MarketDomain]) -> Result<String> {
+    to_json(&serde_json::json!({
+        "domains": domains,
+        "summary": SearchSummary::from_results(domains),
+    }))
 }
 
 /// Format a single domain status.
 ///
+/// Unlike the list formatters, this is an object
+/// (`{domain, dns_records, record_counts}`), not a bare array.
+///
 /// # Errors
 ///
 /// Returns an error if JSON serialization fails.
@@ -51,8 +721,9 @@ pub fn format_domain_status(
     let result = serde_json::json!({
         "domain": domain,
         "dns_records": records,
+        "record_counts": records.map(count_by_type),
     });
-    Ok(serde_json::to_string_pretty(&result)?)
+    to_versioned_json(&result)
 }
 
 /// Format wallet balance.
@@ -61,31 +732,321 @@ pub fn format_domain_status(
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_wallet_balance(balance: &WalletBalance) -> Result<String> {
-    Ok(serde_json::to_string_pretty(balance)?)
+    to_json(balance)
+}
+
+/// How many domains at `price` euros a year `balance` euros pays for.
+///
+/// Rounds down; a negative balance affords none.
+#[must_use]
+pub fn affordable_count(balance: i32, price: i32) -> i32 {
+    if price <= 0 {
+        return 0;
+    }
+    balance.max(0) / price
+}
+
+/// Format wallet balance with the number of domains it affords at `price`.
+///
+/// Gives `{"balance": 150, "price": 15, "affordable": 10}`.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_wallet_balance_affordable(balance: &WalletBalance, price: i32) -> Result<String> {
+    to_json(&serde_json::json!({
+        "balance": balance.balance,
+        "price": price,
+        "affordable": affordable_count(balance.balance, price),
+    }))
+}
+
+/// Bars of a sparkline, lowest to highest.
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Format recorded balances with a sparkline and range.
+///
+/// Gives `{"points": [...], "sparkline": "▁▃█", "min": .., "max": ..}`, with
+/// points oldest first; `min` and `max` are null when nothing is recorded.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_balance_history(points: &[BalancePoint]) -> Result<String> {
+    let balances: Vec<i32> = points.iter().map(|p| p.balance).collect();
+    to_versioned_json(&serde_json::json!({
+        "points": points,
+        "sparkline": sparkline(&balances),
+        "min": balances.iter().min(),
+        "max": balances.iter().max(),
+    }))
+}
+
+/// One bar per value, scaled between the lowest and highest value.
+fn sparkline(values: &[i32]) -> String {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let range = i64::from(max) - i64::from(min);
+    values
+        .iter()
+        .map(|&value| {
+            // 0..=7, one step per bar above the lowest.
+            let level = if range == 0 {
+                0
+            } else {
+                (i64::from(value) - i64::from(min)) * 7 / range
+            };
+            SPARK_BARS[usize::try_from(level).unwrap_or_default()]
+        })
+        .collect()
+}
+
+/// Currency Njalla quotes wallet amounts in when a payment carries no code.
+const DEFAULT_CURRENCY: &str = "EUR";
+
+/// A payment in output, with its classified state when it has a status.
+#[derive(Serialize)]
+struct ShownPayment<'a> {
+    #[serde(flatten)]
+    payment: &'a Payment,
+
+    /// See [`Payment::classify`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<PaymentStatus>,
 }
 
 /// Format payment information.
 ///
+/// The fiat `amount` is always paired with a `currency`: the payment's own
+/// code when present, otherwise EUR. Crypto amount and address are kept as-is.
+/// A payment with a `status` (from get-payment) also gets a `"state"` of
+/// `pending`, `confirmed`, `failed` or `unknown`.
+///
 /// # Errors
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_payment(payment: &Payment) -> Result<String> {
-    Ok(serde_json::to_string_pretty(payment)?)
+    let mut payment = payment.clone();
+    payment
+        .currency
+        .get_or_insert_with(|| DEFAULT_CURRENCY.to_string());
+    let links: Vec<&str> = [&payment.uri, &payment.url]
+        .into_iter()
+        .filter_map(Option::as_deref)
+        .collect();
+    let shown = ShownPayment {
+        payment: &payment,
+        state: payment.status.is_some().then(|| payment.classify()),
+    };
+    Ok(with_hyperlinks(
+        to_json(&shown)?,
+        &links,
+        HYPERLINKS.load(Ordering::Relaxed),
+    ))
+}
+
+/// Format the exact BTC amount of a payment as a bare string.
+///
+/// For wallets that do not parse `bitcoin:` URIs, so the amount can be
+/// pasted without transcription errors.
+///
+/// # Errors
+///
+/// Returns an error if the payment has no BTC amount.
+pub fn format_exact_btc(payment: &Payment) -> Result<String> {
+    payment.amount_btc.clone().ok_or_else(|| NjallaError::Api {
+        message: "Payment has no amount_btc".to_string(),
+    })
+}
+
+/// A transaction in list output, with its classified state.
+#[derive(Serialize)]
+struct ListedTransaction<'a> {
+    #[serde(flatten)]
+    transaction: &'a Transaction,
+
+    /// See [`Transaction::classify`].
+    state: TransactionStatus,
 }
 
 /// Format a list of transactions.
 ///
+/// Each transaction gets a `"state"` of `completed`, `pending`, `failed` or
+/// `other`, so consumers need not parse the free-form status text.
+///
 /// # Errors
 ///
 /// Returns an error if JSON serialization fails.
 pub fn format_transactions(transactions: &[Transaction]) -> Result<String> {
-    Ok(serde_json::to_string_pretty(transactions)?)
+    let links: Vec<&str> = transactions
+        .iter()
+        .flat_map(|tx| [&tx.uri, &tx.pdf])
+        .filter_map(Option::as_deref)
+        .collect();
+    let listed: Vec<ListedTransaction<'_>> = transactions
+        .iter()
+        .map(|transaction| ListedTransaction {
+            transaction,
+            state: transaction.classify(),
+        })
+        .collect();
+    Ok(with_hyperlinks(
+        to_versioned_json(&listed)?,
+        &links,
+        HYPERLINKS.load(Ordering::Relaxed),
+    ))
+}
+
+/// Wrap `url` as an OSC 8 hyperlink whose visible text is the URL itself.
+fn hyperlink(url: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\{url}\x1b]8;;\x1b\\")
+}
+
+/// Replace each quoted `links` value in `json` with its hyperlinked form.
+fn with_hyperlinks(json: String, links: &[&str], enabled: bool) -> String {
+    if !enabled {
+        return json;
+    }
+    links.iter().fold(json, |json, url| {
+        let Ok(quoted) = serde_json::to_string(url) else {
+            return json;
+        };
+        json.replace(&quoted, &format!("\"{}\"", hyperlink(url)))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn envelope_wraps_success_and_failure() {
+        let ok = envelope("domains", Some(r#"[{"name":"example.com"}]"#), None, 12);
+        assert_eq!(
+            ok,
+            serde_json::json!({
+                "ok": true,
+                "command": "domains",
+                "data": [{ "name": "example.com" }],
+                "elapsed_ms": 12
+            })
+        );
+        let keys: Vec<&String> = ok.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["ok", "command", "data", "elapsed_ms"]);
+
+        let export = envelope("dns export", Some("www 3600 IN A 192.0.2.1"), None, 3);
+        assert_eq!(export["data"], "www 3600 IN A 192.0.2.1");
+
+        let failed = envelope("wallet balance", None, Some(&NjallaError::MissingToken), 1);
+        assert_eq!(failed["ok"], false);
+        assert!(failed["data"].is_null());
+        assert_eq!(
+            failed["error"]["message"],
+            NjallaError::MissingToken.to_string()
+        );
+        assert!(failed["error"]["hint"]
+            .as_str()
+            .unwrap()
+            .contains("config --init"));
+        let keys: Vec<&String> = failed.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["ok", "command", "data", "error", "elapsed_ms"]);
+    }
+
+    #[test]
+    fn ascii_mode_escapes_multibyte_glyphs() {
+        let transactions = vec![Transaction {
+            id: "tx1".to_string(),
+            amount: 50,
+            status: "Added 50 € via Bitcoin ✓".to_string(),
+            completed: None,
+            pdf: None,
+            uri: None,
+            address: None,
+            currency: None,
+            amount_btc: None,
+        }];
+
+        let ascii = to_json_with(&transactions, true, false).unwrap();
+        assert!(ascii.is_ascii());
+        assert!(ascii.contains("\\u20ac"));
+
+        let parsed: serde_json::Value = serde_json::from_str(&ascii).unwrap();
+        assert_eq!(parsed[0]["status"], "Added 50 € via Bitcoin ✓");
+
+        let unicode = to_json_with(&transactions, false, false).unwrap();
+        assert!(unicode.contains('€'));
+    }
+
+    #[test]
+    fn escape_non_ascii_uses_surrogate_pairs() {
+        assert_eq!(escape_non_ascii("\"a😀\""), "\"a\\ud83d\\ude00\"");
+    }
+
+    fn search_fixture() -> Vec<MarketDomain> {
+        vec![
+            MarketDomain {
+                name: "example.com".to_string(),
+                status: "available".to_string(),
+                price: 15,
+            },
+            MarketDomain {
+                name: "example.net".to_string(),
+                status: "taken".to_string(),
+                price: 15,
+            },
+            MarketDomain {
+                name: "example.org".to_string(),
+                status: "available".to_string(),
+                price: 18,
+            },
+        ]
+    }
+
+    #[test]
+    fn search_summary_excludes_taken_domains() {
+        let summary = SearchSummary::from_results(&search_fixture());
+        assert_eq!(summary.results, 3);
+        assert_eq!(summary.available, 2);
+        assert_eq!(summary.available_total, 33);
+        assert_eq!(summary.cheapest, Some(15));
+        assert_eq!(summary.priciest, Some(18));
+        assert_eq!(
+            summary.text,
+            "2 of 3 available. Available total: €33 (cheapest €15, priciest €18)"
+        );
+    }
+
+    #[test]
+    fn search_summary_with_nothing_available() {
+        let summary = SearchSummary::from_results(&search_fixture()[1..2]);
+        assert_eq!(summary.available_total, 0);
+        assert!(summary.cheapest.is_none());
+        assert_eq!(summary.text, "0 of 1 available");
+    }
+
+    #[test]
+    fn format_search_with_summary_json() {
+        let result = format_market_domains_with_summary(&search_fixture()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed["domains"].as_array().unwrap().len(), 3);
+        assert_eq!(parsed["summary"]["available_total"], 33);
+    }
+
+    #[test]
+    fn affordable_flag_needs_availability_and_enough_balance() {
+        let result = format_market_domains_affordable(&search_fixture(), 15).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let flags: Vec<bool> = parsed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["affordable"].as_bool().unwrap())
+            .collect();
+        assert_eq!(flags, [true, false, false]);
+        assert_eq!(parsed[0]["name"], "example.com");
+    }
+
     #[test]
     fn format_empty_domains() {
         let result = format_domains(&[]).unwrap();
@@ -100,6 +1061,20 @@ mod tests {
         assert_eq!(parsed["balance"], 150);
     }
 
+    #[test]
+    fn affordable_count_rounds_down() {
+        assert_eq!(affordable_count(150, 15), 10);
+        assert_eq!(affordable_count(44, 15), 2);
+        assert_eq!(affordable_count(14, 15), 0);
+        assert_eq!(affordable_count(-30, 15), 0);
+        assert_eq!(affordable_count(150, 0), 0);
+
+        let result = format_wallet_balance_affordable(&WalletBalance { balance: 100 }, 18).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed["affordable"], 5);
+        assert_eq!(parsed["price"], 18);
+    }
+
     #[test]
     fn format_payment_json() {
         let payment = Payment {
@@ -120,6 +1095,71 @@ mod tests {
         assert_eq!(parsed["amount_btc"], "0.0005128");
         assert_eq!(parsed["address"], "bc1qtest");
         assert_eq!(parsed["uri"], "bitcoin:bc1qtest?amount=0.0005128");
+        assert_eq!(parsed["state"], "pending");
+    }
+
+    #[test]
+    fn format_payment_keeps_non_eur_currency() {
+        let mut payment = Payment {
+            id: Some("pay456".to_string()),
+            amount: 15,
+            currency: Some("USD".to_string()),
+            amount_btc: Some("0.0002564".to_string()),
+            status: None,
+            address: Some("bc1qtest".to_string()),
+            uri: None,
+            url: None,
+        };
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_payment(&payment).unwrap()).unwrap();
+        assert_eq!(parsed["amount"], 15);
+        assert_eq!(parsed["currency"], "USD");
+        assert_eq!(parsed["amount_btc"], "0.0002564");
+        assert_eq!(parsed["address"], "bc1qtest");
+        assert!(parsed.get("state").is_none());
+
+        payment.currency = None;
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_payment(&payment).unwrap()).unwrap();
+        assert_eq!(parsed["currency"], "EUR");
+    }
+
+    #[test]
+    fn balance_history_has_sparkline_and_range() {
+        let points: Vec<BalancePoint> = [150, 135, 120, 300]
+            .into_iter()
+            .zip(1u64..)
+            .map(|(balance, timestamp)| BalancePoint { timestamp, balance })
+            .collect();
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_balance_history(&points).unwrap()).unwrap();
+
+        assert_eq!(parsed["sparkline"], "▂▁▁█");
+        assert_eq!(parsed["min"], 120);
+        assert_eq!(parsed["max"], 300);
+        assert_eq!(parsed["points"][3]["balance"], 300);
+        assert_eq!(sparkline(&[5, 5]), "▁▁");
+        assert_eq!(sparkline(&[0, 7, 3]), "▁█▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn format_exact_btc_prints_only_amount() {
+        let mut payment = Payment {
+            id: Some("pay123".to_string()),
+            amount: 15,
+            currency: Some("EUR".to_string()),
+            amount_btc: Some("0.0002564".to_string()),
+            status: None,
+            address: Some("bc1qtest".to_string()),
+            uri: Some("bitcoin:bc1qtest?amount=0.0002564".to_string()),
+            url: None,
+        };
+        assert_eq!(format_exact_btc(&payment).unwrap(), "0.0002564");
+
+        payment.amount_btc = None;
+        assert!(format_exact_btc(&payment).is_err());
     }
 
     #[test]
@@ -145,6 +1185,7 @@ mod tests {
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed.is_array());
         assert_eq!(parsed[0]["id"], "tx1");
+        assert_eq!(parsed[0]["state"], "completed");
     }
 
     #[test]
@@ -174,9 +1215,52 @@ mod tests {
         assert_eq!(parsed["ttl"], 3600);
     }
 
+    #[test]
+    fn sorted_shuffles_render_identically() {
+        use crate::types::RecordType;
+
+        let record = |id: &str, name: &str, record_type, content: &str| Record {
+            id: id.to_string(),
+            name: name.to_string(),
+            record_type,
+            content: Some(content.to_string()),
+            ttl: Some(3600),
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        };
+        let records = vec![
+            record("1", "www", RecordType::A, "192.0.2.1"),
+            record("2", "@", RecordType::Mx, "mail.example.com"),
+            record("3", "@", RecordType::A, "192.0.2.2"),
+            record("4", "@", RecordType::A, "192.0.2.1"),
+            record("5", "@", RecordType::Aaaa, "2001:db8::1"),
+        ];
+
+        let mut first = records.clone();
+        first.reverse();
+        let mut second = records;
+        second.swap(0, 3);
+        second.swap(1, 4);
+
+        sort_records(&mut first);
+        sort_records(&mut second);
+        assert_eq!(
+            format_records(&first, &BTreeMap::new()).unwrap(),
+            format_records(&second, &BTreeMap::new()).unwrap()
+        );
+
+        let ids: Vec<&str> = first.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["4", "3", "5", "2", "1"]);
+    }
+
     #[test]
     fn format_records_empty() {
-        let result = format_records(&[]).unwrap();
+        let result = format_records(&[], &BTreeMap::new()).unwrap();
         assert_eq!(result, "[]");
     }
 
@@ -214,11 +1298,308 @@ mod tests {
                 ssh_type: None,
             },
         ];
-        let result = format_records(&records).unwrap();
+        let result = format_records(&records, &BTreeMap::new()).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed.is_array());
         assert_eq!(parsed[0]["id"], "rec1");
         assert_eq!(parsed[1]["id"], "rec2");
         assert_eq!(parsed[1]["prio"], 10);
     }
+
+    #[test]
+    fn format_records_flags_default_ttl() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1", "ttl": 10800 },
+            { "id": "2", "name": "www", "type": "A", "content": "192.0.2.2", "ttl": 300 },
+            { "id": "3", "name": "mail", "type": "A", "content": "192.0.2.3" }
+        ]))
+        .unwrap();
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_records(&records, &BTreeMap::new()).unwrap()).unwrap();
+
+        assert_eq!(parsed[0]["ttl"], DEFAULT_RECORD_TTL);
+        assert_eq!(parsed[0]["ttl_default"], true);
+        assert_eq!(parsed[1]["ttl"], 300);
+        assert!(parsed[1].get("ttl_default").is_none());
+        assert_eq!(parsed[2]["ttl_default"], true);
+    }
+
+    #[test]
+    fn porcelain_domain_line() {
+        let domains: Vec<Domain> = serde_json::from_value(serde_json::json!([
+            { "name": "example.com", "status": "active", "expiry": "2026-03-01T00:00:00Z" },
+            { "name": "pending.org", "status": "pending" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            format_domains_porcelain(&domains),
+            "example.com\tactive\t2026-03-01T00:00:00Z\npending.org\tpending\t"
+        );
+    }
+
+    #[test]
+    fn porcelain_record_line_escapes_content() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "1", "name": "_sip._tcp", "type": "SRV", "content": "sip.example.com",
+                "ttl": 3600, "prio": 10, "weight": 5, "port": 5060
+            },
+            { "id": "2", "name": "@", "type": "TXT", "content": "a\tb\\c" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            format_records_porcelain(&records),
+            "1\t_sip._tcp\tSRV\t3600\t10\t5\t5060\t\t\t\t\tsip.example.com\n\
+             2\t@\tTXT\t\t\t\t\t\t\t\t\ta\\tb\\\\c"
+        );
+    }
+
+    #[test]
+    fn format_records_shows_comments_by_record_id() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 300 },
+            { "id": "2", "name": "api", "type": "A", "content": "192.0.2.2", "ttl": 300 }
+        ]))
+        .unwrap();
+        let comments = BTreeMap::from([("1".to_string(), "for staging".to_string())]);
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_records(&records, &comments).unwrap()).unwrap();
+
+        assert_eq!(parsed[0]["comment"], "for staging");
+        assert!(parsed[1].get("comment").is_none());
+    }
+
+    #[test]
+    fn retain_listed_filters_on_list_only_fields() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "www", "type": "A", "content": "192.0.2.1", "ttl": 300 },
+            { "id": "2", "name": "api", "type": "A", "content": "192.0.2.2" },
+            { "id": "3", "name": "mail", "type": "A", "content": "192.0.2.3", "ttl": 300 }
+        ]))
+        .unwrap();
+        let comments = BTreeMap::from([("1".to_string(), "for staging".to_string())]);
+        let kept = |filters: &[&str]| {
+            let filters: Vec<FieldFilter> = filters.iter().map(|f| f.parse().unwrap()).collect();
+            let mut records = records.clone();
+            retain_listed(&mut records, &comments, &filters).unwrap();
+            records.into_iter().map(|r| r.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(kept(&["comment~=staging"]), ["1"]);
+        assert_eq!(kept(&["ttl_default==true"]), ["2"]);
+        assert_eq!(kept(&["ttl_default!=true", "comment!=for staging"]), ["3"]);
+        assert_eq!(kept(&[]), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn format_records_keeps_type_specific_fields() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "1", "name": "_sip._tcp", "type": "SRV", "content": "sip.example.com",
+                "ttl": 3600, "prio": 10, "weight": 5, "port": 5060
+            },
+            {
+                "id": "2", "name": "@", "type": "HTTPS", "ttl": 3600,
+                "prio": 1, "target": ".", "value": "alpn=h2,h3"
+            },
+            {
+                "id": "3", "name": "host", "type": "SSHFP", "content": "abc123",
+                "ttl": 3600, "ssh_algorithm": 4, "ssh_type": 2
+            }
+        ]))
+        .unwrap();
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_records(&records, &BTreeMap::new()).unwrap()).unwrap();
+
+        assert_eq!(parsed[0]["weight"], 5);
+        assert_eq!(parsed[0]["port"], 5060);
+        assert_eq!(parsed[1]["target"], ".");
+        assert_eq!(parsed[1]["value"], "alpn=h2,h3");
+        assert_eq!(parsed[2]["ssh_algorithm"], 4);
+        assert_eq!(parsed[2]["ssh_type"], 2);
+    }
+
+    #[test]
+    fn compact_list_output_is_a_bare_single_line_array() {
+        let domains: Vec<Domain> = serde_json::from_value(serde_json::json!([
+            { "name": "example.com", "status": "active" },
+            { "name": "example.org", "status": "inactive" }
+        ]))
+        .unwrap();
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "rec1", "name": "@", "type": "A", "content": "1.2.3.4" }
+        ]))
+        .unwrap();
+        let transactions: Vec<Transaction> = serde_json::from_value(serde_json::json!([
+            { "id": "tx1", "amount": 15, "status": "Added 15 € via Bitcoin" }
+        ]))
+        .unwrap();
+
+        for json in [
+            to_json_with(&domains, false, true).unwrap(),
+            to_json_with(&records, false, true).unwrap(),
+            to_json_with(&transactions, false, true).unwrap(),
+            to_json_with(&Vec::<Domain>::new(), false, true).unwrap(),
+        ] {
+            assert!(json.starts_with('[') && json.ends_with(']'), "{json}");
+            assert!(!json.contains('\n'));
+            assert!(!json.contains(": "));
+            assert!(serde_json::from_str::<serde_json::Value>(&json)
+                .unwrap()
+                .is_array());
+        }
+    }
+
+    #[test]
+    fn hyperlinks_wrap_payment_uri_only_when_enabled() {
+        let payment = Payment {
+            id: Some("pay123".to_string()),
+            amount: 15,
+            currency: None,
+            amount_btc: Some("0.0002564".to_string()),
+            status: None,
+            address: Some("bc1qtest".to_string()),
+            uri: Some("bitcoin:bc1qtest?amount=0.0002564".to_string()),
+            url: None,
+        };
+        let json = format_payment(&payment).unwrap();
+        assert!(!json.contains('\x1b'));
+
+        let linked = with_hyperlinks(json, &["bitcoin:bc1qtest?amount=0.0002564"], true);
+        assert!(linked.contains(
+            "\"\x1b]8;;bitcoin:bc1qtest?amount=0.0002564\x1b\\\
+             bitcoin:bc1qtest?amount=0.0002564\x1b]8;;\x1b\\\""
+        ));
+        assert!(linked.contains("\"address\": \"bc1qtest\""));
+    }
+
+    #[test]
+    fn schema_version_wraps_only_when_enabled() {
+        let domains: Vec<Domain> = serde_json::from_value(serde_json::json!([
+            { "name": "example.com", "status": "active" }
+        ]))
+        .unwrap();
+
+        let bare = versioned(&domains, false).unwrap();
+        assert!(bare.is_array());
+        assert_eq!(bare[0]["name"], "example.com");
+
+        let wrapped = versioned(&domains, true).unwrap();
+        assert_eq!(wrapped["_schema_version"], SCHEMA_VERSION);
+        assert_eq!(wrapped["data"], bare);
+    }
+
+    #[test]
+    fn streamed_json_matches_buffered_json() {
+        let domains: Vec<Domain> = serde_json::from_value(serde_json::json!([
+            { "name": "example.com", "status": "active", "expiry": "2027-01-01T00:00:00Z" },
+            { "name": "bücher.example", "status": "Grace period" }
+        ]))
+        .unwrap();
+
+        for with_schema in [false, true] {
+            for ascii_only in [false, true] {
+                for compact in [false, true] {
+                    let buffered = to_json_with(
+                        &versioned(&domains, with_schema).unwrap(),
+                        ascii_only,
+                        compact,
+                    )
+                    .unwrap();
+                    let mut streamed = Vec::new();
+                    write_json_with(
+                        &mut streamed,
+                        &Versioned::new(&domains, with_schema),
+                        ascii_only,
+                        compact,
+                    )
+                    .unwrap();
+                    assert_eq!(String::from_utf8(streamed).unwrap(), buffered);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_writer_escapes_characters_split_across_writes() {
+        let mut out = Vec::new();
+        let mut writer = AsciiWriter {
+            inner: &mut out,
+            pending: Vec::new(),
+        };
+        for byte in "\"5 €\"".as_bytes() {
+            io::Write::write_all(&mut writer, std::slice::from_ref(byte)).unwrap();
+        }
+        assert_eq!(out, b"\"5 \\u20ac\"");
+    }
+
+    #[test]
+    fn group_records_by_type_buckets_each_record_once() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1" },
+            { "id": "2", "name": "www", "type": "CNAME", "content": "example.com" },
+            { "id": "3", "name": "www", "type": "A", "content": "192.0.2.2" },
+            { "id": "4", "name": "@", "type": "MX", "content": "mail.example.com" }
+        ]))
+        .unwrap();
+
+        let groups = group_records(records, GroupBy::Type);
+
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["A", "CNAME", "MX"]);
+        let ids: Vec<&str> = groups["A"].iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["1", "3"]);
+        let mut all: Vec<&str> = groups.values().flatten().map(|r| r.id.as_str()).collect();
+        all.sort_unstable();
+        assert_eq!(all, ["1", "2", "3", "4"]);
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_grouped_records(&groups, &BTreeMap::new()).unwrap())
+                .unwrap();
+        assert_eq!(parsed["CNAME"][0]["id"], "2");
+    }
+
+    #[test]
+    fn status_includes_record_counts_by_type() {
+        let records: Vec<Record> = serde_json::from_value(serde_json::json!([
+            { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1" },
+            { "id": "2", "name": "www", "type": "CNAME", "content": "example.com" },
+            { "id": "3", "name": "www", "type": "A", "content": "192.0.2.2" },
+            { "id": "4", "name": "@", "type": "TXT", "content": "v=spf1 -all" }
+        ]))
+        .unwrap();
+
+        let counts = count_by_type(&records);
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [
+                (RecordType::A, 2),
+                (RecordType::Cname, 1),
+                (RecordType::Txt, 1)
+            ]
+        );
+
+        let domain = Domain {
+            name: "example.com".to_string(),
+            status: "active".to_string(),
+            expiry: None,
+            locked: None,
+            mailforwarding: None,
+            max_nameservers: None,
+        };
+        let status = format_domain_status(&domain, Some(&records)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&status).unwrap();
+        assert_eq!(
+            parsed["record_counts"],
+            serde_json::json!({ "A": 2, "CNAME": 1, "TXT": 1 })
+        );
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_domain_status(&domain, None).unwrap()).unwrap();
+        assert!(parsed["record_counts"].is_null());
+    }
 }
@@ -0,0 +1,178 @@
+//! Paging long output through `$PAGER`, like `git`.
+//!
+//! Output is paged when stdout is a terminal and the text is taller than the
+//! terminal, or always with `--pager`. JSON is only paged with `--pager`, so
+//! it reaches the terminal as is. `--no-pager`, a non-terminal stdout or
+//! output meant for other tools (`dns export`) never pages.
+
+use crate::error::{NjallaError, Result};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when neither `NJALLA_PAGER` nor `PAGER` is set.
+pub const DEFAULT_PAGER: &str = "less";
+
+/// Terminal height assumed when `LINES` is not set.
+const DEFAULT_TERMINAL_LINES: usize = 24;
+
+/// When to page output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagerMode {
+    /// Page text that is not JSON when it does not fit on the terminal.
+    Auto,
+    /// Page whenever stdout is a terminal (`--pager`).
+    Always,
+    /// Never page (`--no-pager`).
+    Never,
+}
+
+impl PagerMode {
+    /// Mode for the `--pager` and `--no-pager` flags.
+    #[must_use]
+    pub fn from_flags(pager: bool, no_pager: bool) -> Self {
+        if no_pager {
+            Self::Never
+        } else if pager {
+            Self::Always
+        } else {
+            Self::Auto
+        }
+    }
+}
+
+/// Whether output of `lines` lines should be paged on a terminal of
+/// `height` lines; `json` output is only paged in [`PagerMode::Always`].
+#[must_use]
+pub fn should_page(
+    mode: PagerMode,
+    is_terminal: bool,
+    json: bool,
+    lines: usize,
+    height: usize,
+) -> bool {
+    match mode {
+        PagerMode::Never => false,
+        _ if !is_terminal => false,
+        PagerMode::Always => true,
+        PagerMode::Auto => !json && lines > height,
+    }
+}
+
+/// Pager command from `NJALLA_PAGER`, then `PAGER`, then [`DEFAULT_PAGER`].
+///
+/// An empty value or `cat` turns paging off, as it does for `git`.
+#[must_use]
+pub fn pager_command(njalla_pager: Option<&str>, pager: Option<&str>) -> Option<String> {
+    let command = njalla_pager.or(pager).unwrap_or(DEFAULT_PAGER).trim();
+    if command.is_empty() || command == "cat" {
+        None
+    } else {
+        Some(command.to_string())
+    }
+}
+
+/// Terminal height from `LINES`, or [`DEFAULT_TERMINAL_LINES`].
+fn terminal_lines() -> usize {
+    std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.trim().parse().ok())
+        .filter(|&lines| lines > 0)
+        .unwrap_or(DEFAULT_TERMINAL_LINES)
+}
+
+/// The pager to show `text` in, if it should be paged at all.
+#[must_use]
+pub fn pager_for(mode: PagerMode, is_terminal: bool, text: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok();
+    if !should_page(
+        mode,
+        is_terminal,
+        json,
+        text.lines().count(),
+        terminal_lines(),
+    ) {
+        return None;
+    }
+    pager_command(
+        std::env::var("NJALLA_PAGER").ok().as_deref(),
+        std::env::var("PAGER").ok().as_deref(),
+    )
+}
+
+/// Write `text` to the stdin of `pager` and wait for the user to quit it.
+///
+/// The command may carry arguments, e.g. `less -S`. `LESS` defaults to
+/// `FRX` so short output exits at once and colors pass through. Quitting
+/// before the end of the output is not an error.
+///
+/// # Errors
+///
+/// Returns an error if the pager cannot be started.
+pub fn page(text: &str, pager: &str) -> Result<()> {
+    let mut parts = pager.split_whitespace();
+    let program = parts.next().unwrap_or(pager);
+    let mut command = Command::new(program);
+    command.args(parts).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let mut child = command.spawn().map_err(|e| NjallaError::Config {
+        message: format!("Failed to launch pager '{pager}': {e}"),
+    })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match writeln!(stdin, "{text}") {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => {
+                return Err(NjallaError::Config {
+                    message: format!("Failed to write to pager '{pager}': {e}"),
+                });
+            }
+            _ => {}
+        }
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_pages_only_oversized_output_on_a_terminal() {
+        assert!(should_page(PagerMode::Auto, true, false, 100, 24));
+        assert!(!should_page(PagerMode::Auto, true, false, 24, 24));
+        assert!(!should_page(PagerMode::Auto, false, false, 100, 24));
+    }
+
+    #[test]
+    fn auto_never_pages_json() {
+        assert!(!should_page(PagerMode::Auto, true, true, 100, 24));
+        assert!(should_page(PagerMode::Always, true, true, 100, 24));
+
+        let tall_json = format!("[{}]", vec!["1"; 100].join(",\n"));
+        assert!(pager_for(PagerMode::Auto, true, &tall_json).is_none());
+    }
+
+    #[test]
+    fn flags_force_or_disable_paging() {
+        assert!(should_page(PagerMode::Always, true, false, 1, 24));
+        assert!(!should_page(PagerMode::Always, false, false, 100, 24));
+        assert!(!should_page(PagerMode::Never, true, false, 100, 24));
+        assert_eq!(PagerMode::from_flags(true, false), PagerMode::Always);
+        assert_eq!(PagerMode::from_flags(false, true), PagerMode::Never);
+        assert_eq!(PagerMode::from_flags(false, false), PagerMode::Auto);
+    }
+
+    #[test]
+    fn pager_command_precedence() {
+        assert_eq!(
+            pager_command(Some("most"), Some("more")).as_deref(),
+            Some("most")
+        );
+        assert_eq!(pager_command(None, Some("more")).as_deref(), Some("more"));
+        assert_eq!(pager_command(None, None).as_deref(), Some(DEFAULT_PAGER));
+        assert_eq!(pager_command(Some(""), Some("more")), None);
+        assert_eq!(pager_command(Some("cat"), None), None);
+    }
+}
@@ -0,0 +1,190 @@
+//! Local comments on DNS records.
+//!
+//! Njalla records have no comment field, so `dns comment` keeps them in
+//! `./comments.json` (next to `config.toml`) and `dns list` shows them. A
+//! comment is keyed by domain and record id, with the record's name, type and
+//! content kept as a fallback key, since an edit may give a record a new id.
+
+use crate::error::Result;
+use crate::json_file;
+use crate::types::{normalize_content, Record, RecordType};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Record comments file name.
+pub const COMMENTS_FILE: &str = "comments.json";
+
+/// A comment on one record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordComment {
+    /// Domain the record belongs to.
+    pub domain: String,
+
+    /// Record id when the comment was set.
+    pub id: String,
+
+    /// Record name, for matching after the id changes.
+    pub name: String,
+
+    /// Record type, for matching after the id changes.
+    #[serde(rename = "type")]
+    pub record_type: RecordType,
+
+    /// Record content, for matching after the id changes.
+    #[serde(default)]
+    pub content: Option<String>,
+
+    /// The comment.
+    pub comment: String,
+}
+
+impl RecordComment {
+    /// Whether this comment is on `record`: same id, or failing that, same
+    /// name, type and content (compared after [`normalize_content`]).
+    fn matches(&self, domain: &str, record: &Record) -> bool {
+        let normalized = |content: &Option<String>| {
+            content
+                .as_deref()
+                .map(|c| normalize_content(record.record_type, c))
+        };
+        self.domain == domain
+            && (self.id == record.id
+                || (self.name == record.name
+                    && self.record_type == record.record_type
+                    && normalized(&self.content) == normalized(&record.content)))
+    }
+}
+
+/// Comments on records, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecordComments {
+    /// Stored comments.
+    #[serde(default)]
+    pub comments: Vec<RecordComment>,
+}
+
+impl RecordComments {
+    /// Load the comments, returning none if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path, "record comments")
+    }
+
+    /// Write the comments through a temporary file renamed over `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing or renaming fails.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(self, path, "record comments")
+    }
+
+    /// The comment on `record`, preferring one stored under its id.
+    #[must_use]
+    pub fn comment_for(&self, domain: &str, record: &Record) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|c| c.domain == domain && c.id == record.id)
+            .or_else(|| self.comments.iter().find(|c| c.matches(domain, record)))
+            .map(|c| c.comment.as_str())
+    }
+
+    /// Set the comment on `record`, replacing any comment it already has and
+    /// re-keying it to the record's current id.
+    pub fn set(&mut self, domain: &str, record: &Record, comment: &str) {
+        self.remove(domain, record);
+        self.comments.push(RecordComment {
+            domain: domain.to_string(),
+            id: record.id.clone(),
+            name: record.name.clone(),
+            record_type: record.record_type,
+            content: record.content.clone(),
+            comment: comment.to_string(),
+        });
+    }
+
+    /// Drop the comment on `record`. Returns whether it had one.
+    pub fn remove(&mut self, domain: &str, record: &Record) -> bool {
+        let before = self.comments.len();
+        self.comments.retain(|c| !c.matches(domain, record));
+        self.comments.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, name: &str, content: &str) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": name, "type": "A", "content": content
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn set_replaces_and_scopes_comments_by_domain() {
+        let mut comments = RecordComments::default();
+        let www = record("1", "www", "192.0.2.1");
+
+        comments.set("example.com", &www, "old");
+        comments.set("example.com", &www, "for staging");
+
+        assert_eq!(comments.comments.len(), 1);
+        assert_eq!(
+            comments.comment_for("example.com", &www),
+            Some("for staging")
+        );
+        assert_eq!(comments.comment_for("example.org", &www), None);
+        assert_eq!(
+            comments.comment_for("example.com", &record("2", "api", "192.0.2.2")),
+            None
+        );
+    }
+
+    #[test]
+    fn comment_follows_record_whose_id_changed() {
+        let mut comments = RecordComments::default();
+        comments.set(
+            "example.com",
+            &record("1", "www", "192.0.2.1"),
+            "for staging",
+        );
+
+        let reissued = record("9", "www", "192.0.2.1");
+        assert_eq!(
+            comments.comment_for("example.com", &reissued),
+            Some("for staging")
+        );
+        assert_eq!(
+            comments.comment_for("example.com", &record("9", "www", "192.0.2.9")),
+            None
+        );
+
+        comments.set("example.com", &reissued, "moved");
+        assert_eq!(comments.comments.len(), 1);
+        assert_eq!(comments.comments[0].id, "9");
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("njalla-comments-{}.json", std::process::id()));
+        let mut comments = RecordComments::default();
+        comments.set(
+            "example.com",
+            &record("1", "www", "192.0.2.1"),
+            "for staging",
+        );
+
+        comments.save(&path).unwrap();
+        let loaded = RecordComments::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.comments, comments.comments);
+        assert!(RecordComments::load(&path).unwrap().comments.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+//! Local log of asynchronous tasks submitted by the CLI.
+//!
+//! Njalla has no method to list tasks, so task ids returned by operations
+//! such as `register` are recorded in `./tasks.json` (next to `config.toml`)
+//! and re-checked later with `check-task`.
+
+use crate::error::Result;
+use crate::json_file;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Task log file name.
+pub const TASK_LOG_FILE: &str = "tasks.json";
+
+/// A task submitted by the CLI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskEntry {
+    /// Task ID returned by the API.
+    pub id: String,
+
+    /// Operation that started the task (e.g. "register").
+    pub kind: String,
+
+    /// What the task acts on, usually a domain name.
+    pub target: String,
+}
+
+/// Pending tasks, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskLog {
+    /// Logged tasks.
+    #[serde(default)]
+    pub tasks: Vec<TaskEntry>,
+}
+
+impl TaskLog {
+    /// Load the task log, returning an empty log if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path, "task log")
+    }
+
+    /// Write the task log through a temporary file renamed over `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing or renaming fails.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(self, path, "task log")
+    }
+
+    /// Record a task, ignoring ids that are already logged.
+    pub fn append(&mut self, entry: TaskEntry) {
+        if !self.tasks.iter().any(|t| t.id == entry.id) {
+            self.tasks.push(entry);
+        }
+    }
+
+    /// Drop the task with the given id. Returns whether it was logged.
+    pub fn prune(&mut self, id: &str) -> bool {
+        let before = self.tasks.len();
+        self.tasks.retain(|t| t.id != id);
+        self.tasks.len() != before
+    }
+}
+
+/// Whether a `check-task` status means the task will not change again.
+#[must_use]
+pub fn is_finished(status: &str) -> bool {
+    matches!(status, "completed" | "failed")
+}
+
+/// Append a task to the log file at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the log cannot be read or written.
+pub fn record(path: &Path, entry: TaskEntry) -> Result<()> {
+    let mut log = TaskLog::load(path)?;
+    log.append(entry);
+    log.save(path)
+}
+
+/// Remove a task from the log file at `path`, if present.
+///
+/// # Errors
+///
+/// Returns an error if the log cannot be read or written.
+pub fn forget(path: &Path, id: &str) -> Result<()> {
+    let mut log = TaskLog::load(path)?;
+    if log.prune(id) {
+        log.save(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> TaskEntry {
+        TaskEntry {
+            id: id.to_string(),
+            kind: "register".to_string(),
+            target: format!("{id}.com"),
+        }
+    }
+
+    #[test]
+    fn append_skips_duplicate_ids() {
+        let mut log = TaskLog::default();
+        log.append(entry("a"));
+        log.append(entry("b"));
+        log.append(entry("a"));
+        assert_eq!(log.tasks, vec![entry("a"), entry("b")]);
+    }
+
+    #[test]
+    fn prune_removes_only_matching_task() {
+        let mut log = TaskLog::default();
+        log.append(entry("a"));
+        log.append(entry("b"));
+
+        assert!(log.prune("a"));
+        assert!(!log.prune("missing"));
+        assert_eq!(log.tasks, vec![entry("b")]);
+    }
+
+    #[test]
+    fn record_then_forget_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!("njalla-tasks-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        record(&path, entry("a")).unwrap();
+        record(&path, entry("b")).unwrap();
+        forget(&path, "a").unwrap();
+
+        let log = TaskLog::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(log.tasks, vec![entry("b")]);
+    }
+
+    #[test]
+    fn finished_statuses() {
+        assert!(is_finished("completed"));
+        assert!(is_finished("failed"));
+        assert!(!is_finished("pending"));
+        assert!(!is_finished("processing"));
+    }
+}
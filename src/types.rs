@@ -3,6 +3,7 @@
 //! These types map directly to the Njalla API JSON structures.
 //! See `docs/API.md` for full API documentation.
 
+use crate::error::NjallaError;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,9 @@ pub struct Domain {
     pub name: String,
 
     /// Domain status (e.g., "active", "pending").
+    ///
+    /// Numeric codes are accepted and kept as their decimal string.
+    #[serde(deserialize_with = "string_or_number")]
     pub status: String,
 
     /// Expiration date (ISO 8601 format).
@@ -36,6 +40,133 @@ pub struct Domain {
     pub max_nameservers: Option<i32>,
 }
 
+/// Deserialize a string, accepting numbers and booleans as their text form.
+fn string_or_number<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {
+            Ok(value.to_string())
+        }
+        other => Err(serde::de::Error::custom(format!(
+            "expected a string or number, got {other}"
+        ))),
+    }
+}
+
+impl Domain {
+    /// Normalized status of the domain.
+    #[must_use]
+    pub fn status_kind(&self) -> DomainStatus {
+        let Ok(status) = self.status.parse();
+        status
+    }
+
+    /// Whether the domain has expired, including grace and redemption periods.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.status_kind().is_expired()
+    }
+
+    /// Days from `today` (days since the Unix epoch) until the expiry date.
+    ///
+    /// Returns `None` if the expiry date is missing or unparseable.
+    #[must_use]
+    pub fn days_until_expiry(&self, today: i64) -> Option<i64> {
+        let expiry = parse_iso_date(self.expiry.as_deref()?)?;
+        Some(expiry - today)
+    }
+}
+
+/// Parse the date part of an ISO 8601 timestamp into days since the Unix epoch.
+///
+/// Accepts `2027-01-15` and `2027-01-15T00:00:00Z`; the time is ignored.
+#[must_use]
+pub fn parse_iso_date(s: &str) -> Option<i64> {
+    let date = s.get(..10)?;
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days-from-civil (Howard Hinnant), valid for the proleptic Gregorian calendar.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Normalized domain status.
+///
+/// The API returns free-form status strings; known values are matched
+/// case-insensitively and anything else is kept verbatim in `Other`. Two
+/// `Other` values are equal regardless of case, so `--status` matches an
+/// unknown status however the API capitalizes it.
+#[derive(Debug, Clone, Eq)]
+pub enum DomainStatus {
+    Active,
+    Inactive,
+    Pending,
+    Expired,
+    Grace,
+    Redemption,
+    Other(String),
+}
+
+impl DomainStatus {
+    /// Whether this status means the registration has lapsed.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        matches!(self, Self::Expired | Self::Grace | Self::Redemption)
+    }
+}
+
+impl PartialEq for DomainStatus {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Other(a), Self::Other(b)) => a.eq_ignore_ascii_case(b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl std::str::FromStr for DomainStatus {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace(['-', '_'], " ");
+        Ok(match normalized.as_str() {
+            "active" => Self::Active,
+            "inactive" => Self::Inactive,
+            "pending" => Self::Pending,
+            "expired" => Self::Expired,
+            "grace" | "grace period" => Self::Grace,
+            "redemption" | "redemption period" => Self::Redemption,
+            _ => Self::Other(s.trim().to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for DomainStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Active => "active",
+            Self::Inactive => "inactive",
+            Self::Pending => "pending",
+            Self::Expired => "expired",
+            Self::Grace => "grace",
+            Self::Redemption => "redemption",
+            Self::Other(s) => s,
+        };
+        write!(f, "{s}")
+    }
+}
+
 /// Domain availability and pricing from `find-domains`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketDomain {
@@ -54,7 +185,7 @@ pub struct MarketDomain {
 // ============================================================================
 
 /// DNS record type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
 pub enum RecordType {
     A,
     #[serde(rename = "AAAA")]
@@ -166,6 +297,202 @@ pub struct Record {
     pub ssh_type: Option<i32>,
 }
 
+impl Record {
+    /// Short hash of the record's name, type and data, as shown by `dns list`.
+    ///
+    /// `dns edit --if-unchanged` compares it against a fresh read to detect
+    /// edits made since the record was listed. The hash (64-bit FNV-1a) is
+    /// taken over the fields written out as text in a fixed order, separated
+    /// by NUL bytes, with missing fields empty, so it is stable across runs,
+    /// builds and releases.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let text = |value: Option<&str>| value.unwrap_or_default().to_string();
+        let number = |value: Option<i32>| value.map(|v| v.to_string()).unwrap_or_default();
+        let fields = [
+            self.name.clone(),
+            self.record_type.to_string(),
+            text(self.content.as_deref()),
+            number(self.ttl),
+            number(self.priority),
+            number(self.weight),
+            number(self.port),
+            text(self.target.as_deref()),
+            text(self.value.as_deref()),
+            number(self.ssh_algorithm),
+            number(self.ssh_type),
+        ]
+        .join("\0");
+        let hash = fields
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{hash:016x}")
+    }
+}
+
+/// The same `TYPE name -> content (ttl=..., prio=...)` label as
+/// [`AddRecordParams`], so listed and planned records read alike.
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = AddRecordParams {
+            domain: String::new(),
+            record_type: self.record_type,
+            name: self.name.clone(),
+            content: self.content.clone(),
+            ttl: self.ttl,
+            priority: self.priority,
+            weight: self.weight,
+            port: self.port,
+            target: self.target.clone(),
+            value: self.value.clone(),
+            ssh_algorithm: self.ssh_algorithm,
+            ssh_type: self.ssh_type,
+        };
+        write!(f, "{params}")
+    }
+}
+
+/// Normalize a record name to the relative form Njalla uses.
+///
+/// The apex is `@`, whether given as `@`, an empty name, or the domain
+/// itself (`example.com` or `example.com.`). Names inside the domain lose
+/// the domain suffix, so `www.example.com.` becomes `www`. Anything else,
+/// including names outside the domain, is returned trimmed but unchanged.
+#[must_use]
+pub fn normalize_record_name(name: &str, domain: &str) -> String {
+    let name = name.trim();
+    let domain = domain.trim().trim_end_matches('.');
+    let bare = name.strip_suffix('.').unwrap_or(name);
+
+    if bare.is_empty() || bare == "@" || bare.eq_ignore_ascii_case(domain) {
+        return "@".to_string();
+    }
+    let relative = bare
+        .len()
+        .checked_sub(domain.len() + 1)
+        .filter(|&at| bare.is_char_boundary(at))
+        .map(|at| bare.split_at(at))
+        .filter(|(_, suffix)| suffix.starts_with('.') && suffix[1..].eq_ignore_ascii_case(domain));
+    match relative {
+        Some((relative, _)) if !domain.is_empty() => relative.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Canonical form of a record's content (or SRV target) for comparing
+/// records.
+///
+/// DNS names are case-insensitive and Njalla accepts hostnames with or
+/// without a trailing dot, so for ANAME, CNAME, MX, NS, PTR and SRV the
+/// content is trimmed, lowercased and loses its trailing dot. AAAA addresses
+/// are lowercased. Everything else, TXT in particular, is returned
+/// byte-for-byte.
+#[must_use]
+pub fn normalize_content(record_type: RecordType, content: &str) -> String {
+    match record_type {
+        RecordType::Aname
+        | RecordType::Cname
+        | RecordType::Mx
+        | RecordType::Ns
+        | RecordType::Ptr
+        | RecordType::Srv => {
+            let content = content.trim();
+            content.strip_suffix('.').unwrap_or(content).to_lowercase()
+        }
+        RecordType::Aaaa => content.trim().to_ascii_lowercase(),
+        _ => content.to_string(),
+    }
+}
+
+/// Longest domain name, in characters, that DNS allows.
+const MAX_DOMAIN_LEN: usize = 253;
+
+/// Longest label (the part between dots) that DNS allows.
+const MAX_LABEL_LEN: usize = 63;
+
+/// Check a domain name given on the command line and normalize it.
+///
+/// A pasted URL is reduced to its host, so `https://Example.com/path`
+/// becomes `example.com`; a trailing dot is dropped and the name is
+/// lowercased. The name must have at least two labels of letters, digits
+/// and inner hyphens, so typos like `example..com` fail here rather than as
+/// a confusing API error.
+///
+/// # Errors
+///
+/// Returns [`NjallaError::Input`] if the name is malformed.
+pub fn validate_domain_name(input: &str) -> crate::error::Result<String> {
+    let invalid = |reason: &str| NjallaError::Input {
+        message: format!("Invalid domain name '{input}': {reason}"),
+    };
+
+    let trimmed = input.trim();
+    let host = ["https://", "http://"]
+        .iter()
+        .find_map(|scheme| {
+            trimmed
+                .get(..scheme.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+                .map(|_| &trimmed[scheme.len()..])
+        })
+        .unwrap_or(trimmed);
+    let host = host.split(['/', '?', '#']).next().unwrap_or_default();
+    let name = host.strip_suffix('.').unwrap_or(host).to_lowercase();
+
+    if name.is_empty() {
+        return Err(invalid("name is empty"));
+    }
+    if name.chars().count() > MAX_DOMAIN_LEN {
+        return Err(invalid("longer than 253 characters"));
+    }
+    if !name.contains('.') {
+        return Err(invalid("missing a top-level domain, e.g. example.com"));
+    }
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err(invalid("empty label (double dot?)"));
+        }
+        if label.chars().count() > MAX_LABEL_LEN {
+            return Err(invalid("label longer than 63 characters"));
+        }
+        if let Some(c) = label.chars().find(|&c| c != '-' && !c.is_alphanumeric()) {
+            return Err(invalid(&format!("invalid character '{c}'")));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(invalid("label starts or ends with a hyphen"));
+        }
+    }
+    Ok(name)
+}
+
+/// Whether Njalla manages `record` of `domain` itself.
+///
+/// Such records come with every zone, so a zone reimported elsewhere
+/// conflicts with them. The rules:
+///
+/// - An NS record at the apex pointing at a Njalla nameserver (a host under
+///   `njalla.<tld>`, e.g. `1-you.njalla.no`) is a system record.
+/// - NS records for subdomains (delegations) and apex NS records pointing
+///   elsewhere are the user's.
+/// - SOA records would count too, but the API does not list them and
+///   [`RecordType`] has no SOA variant.
+#[must_use]
+pub fn is_system_record(record: &Record, domain: &str) -> bool {
+    if record.record_type != RecordType::Ns || normalize_record_name(&record.name, domain) != "@" {
+        return false;
+    }
+    let host = record
+        .content
+        .as_deref()
+        .unwrap_or_default()
+        .trim()
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    host.rsplit('.').nth(1) == Some("njalla")
+}
+
 /// Parameters for adding a DNS record.
 #[derive(Debug, Clone)]
 pub struct AddRecordParams {
@@ -195,6 +522,49 @@ pub struct AddRecordParams {
     pub ssh_type: Option<i32>,
 }
 
+impl AddRecordParams {
+    /// Fill in the TTL from a zone default if the record doesn't set one.
+    ///
+    /// An explicit TTL always wins, matching `$TTL` semantics in zone files.
+    #[must_use]
+    pub fn with_default_ttl(mut self, default_ttl: Option<i32>) -> Self {
+        if self.ttl.is_none() {
+            self.ttl = default_ttl;
+        }
+        self
+    }
+}
+
+/// `TYPE name -> content (ttl=..., prio=...)`, listing only the set fields
+/// the record type uses. HTTPS and SVCB records show their target.
+impl std::fmt::Display for AddRecordParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.record_type, self.name)?;
+        if let Some(content) = self.content.as_deref().or(self.target.as_deref()) {
+            write!(f, " -> {content}")?;
+        }
+        let mut fields = vec![("ttl", number(self.ttl))];
+        match self.record_type {
+            RecordType::Mx => fields.push(("prio", number(self.priority))),
+            RecordType::Srv => fields.extend([
+                ("prio", number(self.priority)),
+                ("weight", number(self.weight)),
+                ("port", number(self.port)),
+            ]),
+            RecordType::Https | RecordType::Svcb => fields.extend([
+                ("prio", number(self.priority)),
+                ("value", self.value.clone()),
+            ]),
+            RecordType::Sshfp => fields.extend([
+                ("ssh_algorithm", number(self.ssh_algorithm)),
+                ("ssh_type", number(self.ssh_type)),
+            ]),
+            _ => {}
+        }
+        write_fields(f, &fields)
+    }
+}
+
 /// Parameters for editing a DNS record.
 #[derive(Debug, Clone)]
 pub struct EditRecordParams {
@@ -224,6 +594,77 @@ pub struct EditRecordParams {
     pub ssh_type: Option<i32>,
 }
 
+impl EditRecordParams {
+    /// Parameters that set every field of `record` in `domain`.
+    ///
+    /// Used by `dns edit --json`, so a record from `dns list` can be edited
+    /// and sent back as a whole. The record type cannot be changed by an edit
+    /// and is not sent.
+    #[must_use]
+    pub fn from_record(domain: &str, record: Record) -> Self {
+        Self {
+            domain: domain.to_string(),
+            id: record.id,
+            name: Some(record.name),
+            content: record.content,
+            ttl: record.ttl,
+            priority: record.priority,
+            weight: record.weight,
+            port: record.port,
+            target: record.target,
+            value: record.value,
+            ssh_algorithm: record.ssh_algorithm,
+            ssh_type: record.ssh_type,
+        }
+    }
+}
+
+/// `record ID name -> content (ttl=..., prio=...)`, listing only the fields
+/// the edit sets, since the record type is not part of an edit.
+impl std::fmt::Display for EditRecordParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "record {}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, " {name}")?;
+        }
+        if let Some(content) = self.content.as_deref().or(self.target.as_deref()) {
+            write!(f, " -> {content}")?;
+        }
+        write_fields(
+            f,
+            &[
+                ("ttl", number(self.ttl)),
+                ("prio", number(self.priority)),
+                ("weight", number(self.weight)),
+                ("port", number(self.port)),
+                ("value", self.value.clone()),
+                ("ssh_algorithm", number(self.ssh_algorithm)),
+                ("ssh_type", number(self.ssh_type)),
+            ],
+        )
+    }
+}
+
+/// A numeric field as text for [`write_fields`].
+fn number(value: Option<i32>) -> Option<String> {
+    value.as_ref().map(ToString::to_string)
+}
+
+/// Write ` (key=value, ...)` for the fields that are set, or nothing.
+fn write_fields(
+    f: &mut std::fmt::Formatter<'_>,
+    fields: &[(&str, Option<String>)],
+) -> std::fmt::Result {
+    let set: Vec<String> = fields
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| format!("{key}={value}")))
+        .collect();
+    if set.is_empty() {
+        return Ok(());
+    }
+    write!(f, " ({})", set.join(", "))
+}
+
 // ============================================================================
 // Task Types
 // ============================================================================
@@ -242,19 +683,97 @@ pub struct TaskStatus {
 // API Request/Response Types
 // ============================================================================
 
+/// Njalla API method called by the client.
+///
+/// Serializes and displays as its wire name, e.g. `list-domains`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiMethod {
+    ListDomains,
+    GetDomain,
+    GetAuthCode,
+    FindDomains,
+    RegisterDomain,
+    CheckTask,
+    ListRecords,
+    AddRecord,
+    EditRecord,
+    RemoveRecord,
+    GetBalance,
+    AddPayment,
+    GetPayment,
+    ListTransactions,
+}
+
+impl ApiMethod {
+    /// Method name as sent in the request body.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ListDomains => "list-domains",
+            Self::GetDomain => "get-domain",
+            Self::GetAuthCode => "get-auth-code",
+            Self::FindDomains => "find-domains",
+            Self::RegisterDomain => "register-domain",
+            Self::CheckTask => "check-task",
+            Self::ListRecords => "list-records",
+            Self::AddRecord => "add-record",
+            Self::EditRecord => "edit-record",
+            Self::RemoveRecord => "remove-record",
+            Self::GetBalance => "get-balance",
+            Self::AddPayment => "add-payment",
+            Self::GetPayment => "get-payment",
+            Self::ListTransactions => "list-transactions",
+        }
+    }
+
+    /// Whether sending the request twice has the same effect as sending it
+    /// once. Lookups are; registering, paying and changing records are not.
+    #[must_use]
+    pub const fn is_idempotent(self) -> bool {
+        !matches!(
+            self,
+            Self::RegisterDomain
+                | Self::AddRecord
+                | Self::EditRecord
+                | Self::RemoveRecord
+                | Self::AddPayment
+        )
+    }
+}
+
+impl std::fmt::Display for ApiMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ApiMethod {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 /// API request body (JSON-RPC style).
 #[derive(Debug, Serialize)]
 pub struct ApiRequest {
-    /// API method name.
-    pub method: String,
+    /// API method.
+    pub method: ApiMethod,
 
     /// Method parameters.
     pub params: serde_json::Value,
+
+    /// Request id, unique per client, echoed back by the API if it supports
+    /// correlation.
+    pub id: u64,
 }
 
 /// API response wrapper.
 #[derive(Debug, Deserialize)]
 pub struct ApiResponse<T> {
+    /// Id of the request this answers; absent if the API does not echo it.
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+
     /// Successful result.
     pub result: Option<T>,
 
@@ -288,7 +807,7 @@ pub struct Payment {
     #[serde(default)]
     pub id: Option<String>,
 
-    /// Payment amount in euros.
+    /// Payment amount in `currency` (euros when absent).
     pub amount: i32,
 
     /// Currency (e.g., "EUR").
@@ -316,6 +835,60 @@ pub struct Payment {
     pub url: Option<String>,
 }
 
+/// Classified payment state, see [`Payment::classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    /// Waiting for the payment to arrive or confirm.
+    Pending,
+    /// Paid and credited to the wallet.
+    Confirmed,
+    /// Failed, cancelled or expired.
+    Failed,
+    /// No status, or status text not recognized above.
+    Unknown,
+}
+
+/// Words marking a failed payment or transaction in its status text.
+const FAILED_STATUS_WORDS: [&str; 4] = ["fail", "cancel", "expire", "reject"];
+
+/// Whether lowercased status text means the payment or transaction failed.
+///
+/// Shared by [`Payment::classify`] and [`Transaction::classify`] so the two
+/// never disagree on what counts as a failure.
+fn is_failed_status(status: &str) -> bool {
+    FAILED_STATUS_WORDS.iter().any(|word| status.contains(word))
+}
+
+impl Payment {
+    /// Classify the payment from its `status` text.
+    ///
+    /// Uses the same wording as [`Transaction::classify`]. Pending is checked
+    /// before confirmed, since "Waiting for transaction ... to be confirmed"
+    /// mentions both. Payments carry no completion date, so the text is all
+    /// there is to go on.
+    #[must_use]
+    pub fn classify(&self) -> PaymentStatus {
+        let Some(status) = &self.status else {
+            return PaymentStatus::Unknown;
+        };
+        let status = status.trim().to_lowercase();
+        if is_failed_status(&status) {
+            PaymentStatus::Failed
+        } else if status.starts_with("waiting") || status.contains("pending") {
+            PaymentStatus::Pending
+        } else if status.starts_with("added")
+            || ["completed", "confirmed", "paid"]
+                .iter()
+                .any(|word| status.contains(word))
+        {
+            PaymentStatus::Confirmed
+        } else {
+            PaymentStatus::Unknown
+        }
+    }
+}
+
 /// A wallet transaction.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -353,6 +926,43 @@ pub struct Transaction {
     pub amount_btc: Option<String>,
 }
 
+/// Classified transaction state.
+///
+/// Derived from the free-form `status` text and the `completed` date, see
+/// [`Transaction::classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    /// Funds were added (e.g. "Added 210 € via Bitcoin").
+    Completed,
+    /// Waiting for a payment to arrive or confirm.
+    Pending,
+    /// Failed, cancelled or expired.
+    Failed,
+    /// Any status text not recognized above.
+    Other,
+}
+
+impl Transaction {
+    /// Classify the transaction.
+    ///
+    /// Failure wording wins, then a `completed` date or "Added ..." text
+    /// means completed, then "Waiting ..."/"pending" means pending.
+    #[must_use]
+    pub fn classify(&self) -> TransactionStatus {
+        let status = self.status.trim().to_lowercase();
+        if is_failed_status(&status) {
+            TransactionStatus::Failed
+        } else if self.completed.is_some() || status.starts_with("added") {
+            TransactionStatus::Completed
+        } else if status.starts_with("waiting") || status.contains("pending") {
+            TransactionStatus::Pending
+        } else {
+            TransactionStatus::Other
+        }
+    }
+}
+
 /// Response for `list-transactions`.
 #[derive(Debug, Deserialize)]
 pub struct TransactionsResult {
@@ -379,11 +989,39 @@ impl std::fmt::Display for PaymentMethod {
 // Response Result Types
 // ============================================================================
 
+/// Response for `get-domain`, which may be the domain itself or wrapped
+/// as `{"domain": {...}}`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GetDomainResult {
+    /// `{"domain": {...}}`.
+    Wrapped {
+        /// The domain.
+        domain: Domain,
+    },
+    /// The domain object itself.
+    Bare(Domain),
+}
+
+impl GetDomainResult {
+    /// The domain, however it was wrapped.
+    #[must_use]
+    pub fn into_domain(self) -> Domain {
+        match self {
+            Self::Wrapped { domain } | Self::Bare(domain) => domain,
+        }
+    }
+}
+
 /// Response for `list-domains`.
 #[derive(Debug, Deserialize)]
 pub struct DomainsResult {
     /// List of domains.
     pub domains: Vec<Domain>,
+
+    /// Cursor for the next page, if the API paginates. Not sent today.
+    #[serde(default, alias = "cursor")]
+    pub next: Option<String>,
 }
 
 /// Response for `find-domains`.
@@ -407,10 +1045,146 @@ pub struct RegisterResult {
     pub task: String,
 }
 
+/// Response for `get-auth-code`.
+#[derive(Deserialize)]
+pub struct AuthCodeResult {
+    /// EPP auth code for transferring the domain out.
+    #[serde(alias = "authcode")]
+    pub auth_code: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn system_records_are_njalla_apex_ns_only() {
+        let system = |name: &str, record_type: &str, content: &str| {
+            let record: Record = serde_json::from_value(serde_json::json!({
+                "id": "1", "name": name, "type": record_type, "content": content
+            }))
+            .unwrap();
+            is_system_record(&record, "example.com")
+        };
+
+        assert!(system("@", "NS", "1-you.njalla.no"));
+        assert!(system("", "NS", "3-GET.njalla.fo."));
+        assert!(system("example.com.", "NS", "2-can.njalla.in"));
+        assert!(!system("@", "NS", "ns1.other-dns.net"));
+        assert!(!system("sub", "NS", "1-you.njalla.no"));
+        assert!(!system("@", "A", "192.0.2.1"));
+        assert!(!system("@", "CNAME", "njalla.no"));
+    }
+
+    #[test]
+    fn normalize_content_table() {
+        let cases = [
+            (RecordType::Cname, "Example.COM.", "example.com"),
+            (RecordType::Cname, "example.com", "example.com"),
+            (RecordType::Mx, " mail.Example.com. ", "mail.example.com"),
+            (RecordType::Ns, "NS1.njalla.no.", "ns1.njalla.no"),
+            (
+                RecordType::Srv,
+                "10 5060 SIP.example.com.",
+                "10 5060 sip.example.com",
+            ),
+            (RecordType::Aaaa, "2001:DB8::1", "2001:db8::1"),
+            (RecordType::A, "192.0.2.1", "192.0.2.1"),
+        ];
+        for (record_type, content, expected) in cases {
+            assert_eq!(
+                normalize_content(record_type, content),
+                expected,
+                "{record_type} {content:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_content_leaves_txt_untouched() {
+        for txt in ["v=spf1 Include:Example.com. -all", " padded. ", "Trailing."] {
+            assert_eq!(normalize_content(RecordType::Txt, txt), txt);
+        }
+    }
+
+    #[test]
+    fn validate_domain_name_strips_urls() {
+        assert_eq!(
+            validate_domain_name("https://Example.COM/path?q=1").unwrap(),
+            "example.com"
+        );
+        assert_eq!(
+            validate_domain_name(" http://example.com/ ").unwrap(),
+            "example.com"
+        );
+        assert_eq!(validate_domain_name("example.com.").unwrap(), "example.com");
+    }
+
+    #[test]
+    fn validate_domain_name_accepts_valid_names() {
+        for name in [
+            "example.com",
+            "sub.example.co.uk",
+            "xn--bcher-kva.example",
+            "a-b.io",
+        ] {
+            assert_eq!(validate_domain_name(name).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn validate_domain_name_rejects_malformed_names() {
+        for (name, reason) in [
+            ("example..com", "empty label"),
+            ("", "empty"),
+            ("https://", "empty"),
+            ("localhost", "top-level domain"),
+            ("exa mple.com", "invalid character ' '"),
+            ("-example.com", "hyphen"),
+            ("example.com:8080", "invalid character ':'"),
+        ] {
+            match validate_domain_name(name) {
+                Err(NjallaError::Input { message }) => {
+                    assert!(message.contains(reason), "{name}: {message}");
+                }
+                other => panic!("{name}: expected input error, got {other:?}"),
+            }
+        }
+        assert!(validate_domain_name(&format!("{}.com", "a".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn normalize_record_name_table() {
+        let cases = [
+            ("@", "@"),
+            ("", "@"),
+            ("  ", "@"),
+            ("example.com", "@"),
+            ("example.com.", "@"),
+            ("EXAMPLE.com.", "@"),
+            ("www", "www"),
+            ("www.example.com", "www"),
+            ("www.example.com.", "www"),
+            ("a.b.example.com.", "a.b"),
+            ("*.example.com", "*"),
+            ("*.api.example.com.", "*.api"),
+            ("_sip._tcp", "_sip._tcp"),
+            ("www.other.org.", "www.other.org."),
+            ("notexample.com", "notexample.com"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                normalize_record_name(name, "example.com"),
+                expected,
+                "name {name:?}"
+            );
+        }
+        assert_eq!(
+            normalize_record_name("www.example.com", "example.com."),
+            "www"
+        );
+    }
+
     #[test]
     fn deserialize_domain() {
         let json = r#"{
@@ -426,6 +1200,82 @@ mod tests {
         assert_eq!(domain.locked, Some(false));
     }
 
+    #[test]
+    fn deserialize_get_domain_bare_or_wrapped() {
+        let bare = r#"{"name": "example.com", "status": "active", "extra": [1, 2]}"#;
+        let wrapped = r#"{"domain": {"name": "example.com", "status": "active"}}"#;
+
+        for json in [bare, wrapped] {
+            let result: GetDomainResult = serde_json::from_str(json).unwrap();
+            let domain = result.into_domain();
+            assert_eq!(domain.name, "example.com");
+            assert_eq!(domain.status_kind(), DomainStatus::Active);
+        }
+    }
+
+    #[test]
+    fn deserialize_domain_numeric_status() {
+        let domain: Domain =
+            serde_json::from_str(r#"{"name": "example.com", "status": 2}"#).unwrap();
+        assert_eq!(domain.status, "2");
+        assert!(serde_json::from_str::<Domain>(r#"{"name": "x.com", "status": null}"#).is_err());
+    }
+
+    #[test]
+    fn domain_status_parses_known_values() {
+        assert_eq!("active".parse::<DomainStatus>().unwrap(), DomainStatus::Active);
+        assert_eq!("ACTIVE".parse::<DomainStatus>().unwrap(), DomainStatus::Active);
+        assert_eq!(" Pending ".parse::<DomainStatus>().unwrap(), DomainStatus::Pending);
+        assert_eq!("inactive".parse::<DomainStatus>().unwrap(), DomainStatus::Inactive);
+        assert_eq!("Grace-Period".parse::<DomainStatus>().unwrap(), DomainStatus::Grace);
+        assert_eq!(
+            "redemption period".parse::<DomainStatus>().unwrap(),
+            DomainStatus::Redemption
+        );
+    }
+
+    #[test]
+    fn domain_status_keeps_unknown_values() {
+        let status: DomainStatus = "Transfer Pending".parse().unwrap();
+        assert_eq!(status, DomainStatus::Other("Transfer Pending".to_string()));
+        assert_eq!(status, "transfer pending".parse().unwrap());
+        assert_ne!(status, DomainStatus::Other("Transfer Out".to_string()));
+        assert_eq!(status.to_string(), "Transfer Pending");
+        assert!(!status.is_expired());
+    }
+
+    #[test]
+    fn domain_is_expired() {
+        let json = r#"{"name": "old.com", "status": "Redemption"}"#;
+        let domain: Domain = serde_json::from_str(json).unwrap();
+        assert_eq!(domain.status_kind(), DomainStatus::Redemption);
+        assert!(domain.is_expired());
+
+        let json = r#"{"name": "new.com", "status": "active"}"#;
+        let domain: Domain = serde_json::from_str(json).unwrap();
+        assert!(!domain.is_expired());
+    }
+
+    #[test]
+    fn parse_iso_date_days_since_epoch() {
+        assert_eq!(parse_iso_date("1970-01-01"), Some(0));
+        assert_eq!(parse_iso_date("2000-03-01T12:00:00Z"), Some(11_017));
+        assert_eq!(parse_iso_date("2027-01-15T00:00:00Z"), Some(20_833));
+        assert_eq!(parse_iso_date("2027-13-01"), None);
+        assert_eq!(parse_iso_date("soon"), None);
+    }
+
+    #[test]
+    fn domain_days_until_expiry() {
+        let json = r#"{"name": "example.com", "status": "active", "expiry": "2027-01-15T00:00:00Z"}"#;
+        let domain: Domain = serde_json::from_str(json).unwrap();
+        assert_eq!(domain.days_until_expiry(20_803), Some(30));
+
+        let json = r#"{"name": "example.com", "status": "active"}"#;
+        let domain: Domain = serde_json::from_str(json).unwrap();
+        assert_eq!(domain.days_until_expiry(20_803), None);
+    }
+
     #[test]
     fn deserialize_market_domain() {
         let json = r#"{
@@ -479,6 +1329,23 @@ mod tests {
         assert_eq!(record.port, Some(5060));
     }
 
+    #[test]
+    fn fingerprint_tracks_record_data_but_not_id() {
+        let record = |id: &str, content: &str| -> Record {
+            serde_json::from_value(serde_json::json!(
+                { "id": id, "name": "www", "type": "A", "content": content }
+            ))
+            .unwrap()
+        };
+
+        let fingerprint = record("rec1", "192.0.2.1").fingerprint();
+
+        // Pinned: listed fingerprints must keep matching after an upgrade.
+        assert_eq!(fingerprint, "3afc880f25bb9350");
+        assert_eq!(record("rec2", "192.0.2.1").fingerprint(), fingerprint);
+        assert_ne!(record("rec1", "192.0.2.2").fingerprint(), fingerprint);
+    }
+
     #[test]
     fn deserialize_record_dynamic() {
         let json = r#"{
@@ -494,6 +1361,102 @@ mod tests {
         assert!(record.ttl.is_none());
     }
 
+    #[test]
+    fn default_ttl_fills_only_missing_ttl() {
+        let params = AddRecordParams {
+            domain: "example.com".to_string(),
+            record_type: RecordType::A,
+            name: "www".to_string(),
+            content: Some("192.0.2.1".to_string()),
+            ttl: None,
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        };
+
+        let inherited = params.clone().with_default_ttl(Some(600));
+        assert_eq!(inherited.ttl, Some(600));
+
+        let explicit = AddRecordParams {
+            ttl: Some(60),
+            ..params.clone()
+        }
+        .with_default_ttl(Some(600));
+        assert_eq!(explicit.ttl, Some(60));
+
+        let no_default = params.with_default_ttl(None);
+        assert!(no_default.ttl.is_none());
+    }
+
+    fn add_params(record_type: RecordType, name: &str, content: &str) -> AddRecordParams {
+        AddRecordParams {
+            domain: "example.com".to_string(),
+            record_type,
+            name: name.to_string(),
+            content: Some(content.to_string()),
+            ttl: None,
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        }
+    }
+
+    #[test]
+    fn add_params_display_shows_fields_the_type_uses() {
+        let a = AddRecordParams {
+            ttl: Some(300),
+            // Not used by A records, so not shown.
+            priority: Some(10),
+            ..add_params(RecordType::A, "www", "192.0.2.1")
+        };
+        assert_eq!(a.to_string(), "A www -> 192.0.2.1 (ttl=300)");
+
+        let mx = AddRecordParams {
+            priority: Some(10),
+            ..add_params(RecordType::Mx, "@", "mail.example.com")
+        };
+        assert_eq!(mx.to_string(), "MX @ -> mail.example.com (prio=10)");
+
+        let srv = AddRecordParams {
+            ttl: Some(3600),
+            priority: Some(10),
+            weight: Some(5),
+            port: Some(5060),
+            ..add_params(RecordType::Srv, "_sip._tcp", "sip.example.com")
+        };
+        assert_eq!(
+            srv.to_string(),
+            "SRV _sip._tcp -> sip.example.com (ttl=3600, prio=10, weight=5, port=5060)"
+        );
+    }
+
+    #[test]
+    fn edit_params_display_shows_set_fields() {
+        let edit = EditRecordParams {
+            domain: "example.com".to_string(),
+            id: "rec1".to_string(),
+            name: None,
+            content: Some("192.0.2.2".to_string()),
+            ttl: Some(600),
+            priority: None,
+            weight: None,
+            port: None,
+            target: None,
+            value: None,
+            ssh_algorithm: None,
+            ssh_type: None,
+        };
+        assert_eq!(edit.to_string(), "record rec1 -> 192.0.2.2 (ttl=600)");
+    }
+
     #[test]
     fn record_type_display() {
         assert_eq!(RecordType::A.to_string(), "A");
@@ -522,12 +1485,59 @@ mod tests {
     #[test]
     fn serialize_api_request() {
         let req = ApiRequest {
-            method: "list-domains".to_string(),
+            method: ApiMethod::ListDomains,
             params: serde_json::json!({}),
+            id: 7,
         };
 
         let json = serde_json::to_string(&req).unwrap();
-        assert!(json.contains("list-domains"));
+        assert_eq!(json, r#"{"method":"list-domains","params":{},"id":7}"#);
+    }
+
+    #[test]
+    fn api_method_wire_names() {
+        let all = [
+            ApiMethod::ListDomains,
+            ApiMethod::GetDomain,
+            ApiMethod::GetAuthCode,
+            ApiMethod::FindDomains,
+            ApiMethod::RegisterDomain,
+            ApiMethod::CheckTask,
+            ApiMethod::ListRecords,
+            ApiMethod::AddRecord,
+            ApiMethod::EditRecord,
+            ApiMethod::RemoveRecord,
+            ApiMethod::GetBalance,
+            ApiMethod::AddPayment,
+            ApiMethod::GetPayment,
+            ApiMethod::ListTransactions,
+        ];
+        let names: Vec<String> = all.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            names,
+            [
+                "list-domains",
+                "get-domain",
+                "get-auth-code",
+                "find-domains",
+                "register-domain",
+                "check-task",
+                "list-records",
+                "add-record",
+                "edit-record",
+                "remove-record",
+                "get-balance",
+                "add-payment",
+                "get-payment",
+                "list-transactions",
+            ]
+        );
+        for method in all {
+            assert_eq!(
+                serde_json::to_value(method).unwrap(),
+                serde_json::Value::from(method.as_str())
+            );
+        }
     }
 
     #[test]
@@ -598,4 +1608,68 @@ mod tests {
         assert!(tx.uri.is_some());
         assert!(tx.completed.is_none());
     }
+
+    #[test]
+    fn classify_payments() {
+        use PaymentStatus::{Confirmed, Failed, Pending, Unknown};
+
+        let payment = |status: Option<&str>| Payment {
+            id: Some("pay123".to_string()),
+            amount: 15,
+            currency: None,
+            amount_btc: None,
+            status: status.map(ToString::to_string),
+            address: None,
+            uri: None,
+            url: None,
+        };
+
+        let cases = [
+            (Some("Waiting for transaction"), Pending),
+            (
+                Some("Waiting for transaction of 15 € via Bitcoin to be confirmed"),
+                Pending,
+            ),
+            (Some("completed"), Confirmed),
+            (Some("Added 15 € via Bitcoin"), Confirmed),
+            (Some("Payment expired"), Failed),
+            (Some("Refunded"), Unknown),
+            (None, Unknown),
+        ];
+        for (status, expected) in cases {
+            assert_eq!(payment(status).classify(), expected, "status {status:?}");
+        }
+    }
+
+    #[test]
+    fn classify_transactions() {
+        use TransactionStatus::{Completed, Failed, Other, Pending};
+
+        let tx = |status: &str, completed: Option<&str>| Transaction {
+            id: "tx".to_string(),
+            amount: 15,
+            status: status.to_string(),
+            completed: completed.map(ToString::to_string),
+            pdf: None,
+            uri: None,
+            address: None,
+            currency: None,
+            amount_btc: None,
+        };
+
+        let pending = "Waiting for transaction of 15 € via Bitcoin to be confirmed";
+        let cases = [
+            ("Added 210 € via Bitcoin", Some("2026-02-01"), Completed),
+            ("Added 50 € via Monero", None, Completed),
+            (pending, None, Pending),
+            ("Payment pending", None, Pending),
+            ("Payment via Bitcoin failed", None, Failed),
+            ("Cancelled", None, Failed),
+            ("Refund issued", None, Other),
+        ];
+        for (status, completed, expected) in cases {
+            let tx = tx(status, completed);
+            assert_eq!(tx.classify(), expected, "status {status:?}");
+        }
+    }
 }
@@ -0,0 +1,115 @@
+//! Local snapshots of DNS zones for change detection.
+//!
+//! `dns snapshot` saves a domain's records, sorted, with the time they were
+//! taken in `./snapshots.json` (next to `config.toml`), and `dns changed`
+//! compares the live zone with the last snapshot. Each domain keeps only its
+//! newest snapshot.
+
+use crate::error::Result;
+use crate::json_file;
+use crate::output::sort_records;
+use crate::types::Record;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Zone snapshots file name.
+pub const SNAPSHOTS_FILE: &str = "snapshots.json";
+
+/// A domain's records at one point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneSnapshot {
+    /// Domain the records belong to.
+    pub domain: String,
+
+    /// When the snapshot was taken, in seconds since the Unix epoch.
+    pub timestamp: u64,
+
+    /// Records, in [`sort_records`] order.
+    pub records: Vec<Record>,
+}
+
+/// The newest snapshot of each domain.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ZoneSnapshots {
+    /// Stored snapshots.
+    #[serde(default)]
+    pub snapshots: Vec<ZoneSnapshot>,
+}
+
+impl ZoneSnapshots {
+    /// Load the snapshots, returning none if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path, "zone snapshots")
+    }
+
+    /// Write the snapshots through a temporary file renamed over `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing or renaming fails.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(self, path, "zone snapshots")
+    }
+
+    /// The snapshot of `domain`, if one was taken.
+    #[must_use]
+    pub fn get(&self, domain: &str) -> Option<&ZoneSnapshot> {
+        self.snapshots
+            .iter()
+            .find(|s| s.domain.eq_ignore_ascii_case(domain))
+    }
+
+    /// Store `records` as the snapshot of `domain`, replacing any older one.
+    pub fn set(&mut self, domain: &str, timestamp: u64, mut records: Vec<Record>) {
+        sort_records(&mut records);
+        self.snapshots
+            .retain(|s| !s.domain.eq_ignore_ascii_case(domain));
+        self.snapshots.push(ZoneSnapshot {
+            domain: domain.to_string(),
+            timestamp,
+            records,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(json: serde_json::Value) -> Vec<Record> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn set_sorts_records_and_replaces_older_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("njalla-snapshots-{}.json", std::process::id()));
+        let mut snapshots = ZoneSnapshots::default();
+        snapshots.set("example.com", 1, Vec::new());
+        snapshots.set(
+            "example.com",
+            2,
+            records(serde_json::json!([
+                { "id": "2", "name": "www", "type": "A", "content": "192.0.2.2" },
+                { "id": "1", "name": "@", "type": "A", "content": "192.0.2.1" }
+            ])),
+        );
+
+        snapshots.save(&path).unwrap();
+        let loaded = ZoneSnapshots::load(&path).unwrap();
+        let tmp_exists = path.with_extension("json.tmp").exists();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.snapshots.len(), 1);
+        let snapshot = loaded.get("EXAMPLE.com").unwrap();
+        assert_eq!(snapshot.timestamp, 2);
+        let names: Vec<&str> = snapshot.records.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["@", "www"]);
+        assert!(loaded.get("example.org").is_none());
+        assert!(!tmp_exists);
+    }
+}
@@ -1,4 +1,115 @@
 //! Integration tests for njalla-cli.
 //!
-//! Real integration tests will be added using wiremock for mocking the API.
-//! For tests against the real Njalla API, use cargo test with NJALLA_API_TOKEN.
+//! These run the `njalla` binary against a wiremock server standing in for
+//! the API. For tests against the real Njalla API, use cargo test with
+//! `NJALLA_API_TOKEN`.
+
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+});
+
+/// Answer `api_method` with `result`.
+fn mount(server: &MockServer, api_method: &str, result: &serde_json::Value) {
+    RT.block_on(
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": api_method }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "result": result })),
+            )
+            .mount(server),
+    );
+}
+
+/// Serve a registration of example.com that completes on the first poll.
+fn register_server() -> MockServer {
+    let server = RT.block_on(MockServer::start());
+    mount(
+        &server,
+        "find-domains",
+        &serde_json::json!({ "domains": [
+            { "name": "example.com", "status": "available", "price": 15 }
+        ] }),
+    );
+    mount(
+        &server,
+        "get-balance",
+        &serde_json::json!({ "balance": 100 }),
+    );
+    mount(
+        &server,
+        "register-domain",
+        &serde_json::json!({ "task": "task-1" }),
+    );
+    mount(
+        &server,
+        "check-task",
+        &serde_json::json!({ "id": "task-1", "status": "completed" }),
+    );
+    mount(
+        &server,
+        "get-domain",
+        &serde_json::json!({ "name": "example.com", "status": "active" }),
+    );
+    server
+}
+
+/// Run `njalla` with `args` in a scratch directory, returning its stdout.
+fn njalla(server: &MockServer, args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!(
+        "njalla-integration-{}-{}",
+        std::process::id(),
+        args.join("-").replace(['/', ' '], "_")
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_njalla"))
+        .args(["--base-url", &server.uri()])
+        .args(args)
+        .current_dir(&dir)
+        .env("NJALLA_API_TOKEN", "token")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn json_envelope_keeps_register_wait_progress_off_stdout() {
+    let server = register_server();
+
+    let stdout = njalla(
+        &server,
+        &[
+            "--json-envelope",
+            "register",
+            "example.com",
+            "--confirm",
+            "--wait",
+        ],
+    );
+
+    let envelope: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(envelope["ok"], true);
+    assert_eq!(envelope["data"]["domain_status"], "active");
+}
+
+#[test]
+fn json_envelope_keeps_register_prompt_off_stdout() {
+    let server = register_server();
+
+    let stdout = njalla(&server, &["--json-envelope", "register", "example.com"]);
+
+    let envelope: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(envelope["command"], "register");
+}